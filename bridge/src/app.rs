@@ -1,16 +1,15 @@
 use std::{
     collections::HashMap,
     error::Error,
-    fs, panic,
+    fs::{self, File},
+    panic,
     path::{Path, PathBuf},
 };
 
 use mapgen_core::{
-    generator::{Generator, GeneratorParams},
-    brush::Brush,
-    map::Map,
-    random::{random_seed, Random, Seed},
-    walker::{NormalWaypoints, Walker, WalkerParams},
+    generator::Generator,
+    pipeline::Pipeline,
+    random::{random_seed, Seed},
 };
 use mapgen_exporter::{Exporter, ExporterConfig};
 
@@ -22,6 +21,9 @@ use twmap::TwMap;
 
 use crate::econ::*;
 
+/// generations with fresh seeds before a vote is given up
+const MAX_GENERATION_ATTEMPTS: usize = 10;
+
 #[derive(Parser, Debug)]
 #[command(name = "DDNet Bridge")]
 #[command(version = crate_version!())]
@@ -56,17 +58,17 @@ struct BridgeArgs {
     #[arg(default_value = "../data/maps")]
     base_maps: PathBuf,
 
-    /// path to generation configurations directory
-    #[arg(default_value = "../data/configs/generator")]
-    gen_configs: PathBuf,
-
-    /// path to walker configurations directory
-    #[arg(default_value = "../data/configs/walker")]
-    wal_configs: PathBuf,
+    /// path to pipelines directory
+    #[arg(default_value = "../data/configs/pipelines")]
+    pipelines: PathBuf,
 
-    /// path to walker configurations directory
+    /// path to waypoints configurations directory
     #[arg(default_value = "../data/configs/waypoints")]
     way_configs: PathBuf,
+
+    /// path to exporter configuration
+    #[arg(default_value = "../data/configs/exporter/default.json")]
+    exporter_config: PathBuf,
 }
 
 /// keeps track of the server bridge state
@@ -74,23 +76,17 @@ pub struct ServerBridge {
     /// econ connection to game server
     econ: Option<Econ>,
 
-    /// loaded base maps
+    /// loaded base maps the exporter can merge into
     base_maps: Vec<TwMap>,
 
-    /// stores all available generation configs
-    generator_configs: HashMap<String, GeneratorParams>,
-
-    /// stores all available walker configs
-    walker_configs: HashMap<String, WalkerParams>,
-
-    /// stores all available map configs
-    waypoints_configs: HashMap<String, NormalWaypoints>,
+    /// stores all available pipelines
+    pipelines: HashMap<String, Pipeline>,
 
-    /// selected generator config
-    current_generator_params: String,
+    /// stores all available waypoints
+    waypoints_configs: HashMap<String, Vec<(f32, f32)>>,
 
-    /// selected walker config
-    current_walker_params: String,
+    /// selected pipeline
+    current_pipeline: String,
 
     /// selected waypoints
     current_waypoints: String,
@@ -98,63 +94,62 @@ pub struct ServerBridge {
     /// stores start arguments
     args: BridgeArgs,
 
-    /// map generator
+    /// map generator, set up with the selected pipeline
     generator: Generator,
+
+    /// merges generated maps into base maps
+    exporter: Exporter,
 }
 
 impl ServerBridge {
     fn new(args: BridgeArgs) -> ServerBridge {
-        let mut base_maps = Vec::new();
+        let exporter_config =
+            ExporterConfig::load(&args.exporter_config).expect("failed to load exporter config");
+        let mut exporter = Exporter::new(exporter_config);
 
-        for path in load_base_maps_paths(args.base_maps.as_path()) {
-            let mut tw_map = TwMap::parse_file(path).expect("failed to parse base map");
-            tw_map.load().expect("failed to load base map");
-
-            base_maps.push(tw_map);
-        }
-
-        let generator_configs =
-            load_configs_from_dir::<GeneratorParams, _>(args.gen_configs.as_path()).unwrap();
-        let walker_configs =
-            load_configs_from_dir::<WalkerParams, _>(args.wal_configs.as_path()).unwrap();
+        let pipelines = load_pipelines_from_dir(args.pipelines.as_path()).unwrap();
         let waypoints_configs =
-            load_configs_from_dir::<NormalWaypoints, _>(args.way_configs.as_path()).unwrap();
+            load_configs_from_dir::<Vec<(f32, f32)>, _>(args.way_configs.as_path()).unwrap();
 
-        let current_generator_params = generator_configs.iter().last().unwrap().0.clone();
-        let current_walker_params = walker_configs.iter().last().unwrap().0.clone();
-        let current_waypoints = waypoints_configs.iter().last().unwrap().0.clone();
+        let current_pipeline = pipelines.keys().sorted().next().unwrap().clone();
+        let current_waypoints = waypoints_configs.keys().sorted().next().unwrap().clone();
 
-        let gen = &generator_configs[&current_generator_params];
-        let wal = &walker_configs[&current_walker_params];
-        let way = &waypoints_configs[&current_waypoints];
+        let mut generator = Generator::new();
+        let pipeline = &pipelines[&current_pipeline];
 
-        let prng = Random::new(random_seed());
+        pipeline.setup(&mut generator);
+        exporter.set_blocks(pipeline.generator.blocks.clone());
 
-        let mut walker = Walker::new(Brush::new(5, 0.0), Brush::new(7, 0.0), prng, wal.clone());
+        let mut base_maps = Vec::new();
 
-        walker.set_waypoints(way.clone()).set_bounds(500, 500);
+        for path in load_base_maps_paths(args.base_maps.as_path()) {
+            let mut tw_map = TwMap::parse_path(&path).expect("failed to parse base map");
+            tw_map.load().expect("failed to load base map");
 
-        let map = Map::new(500, 500);
+            match exporter.check_base(&tw_map) {
+                Ok(()) => base_maps.push(tw_map),
+                Err(err) => warn!("Skipping base map {}: {}", path.display(), err),
+            }
+        }
 
-        let generator = Generator::new(map, walker, gen.clone());
+        assert!(!base_maps.is_empty(), "no usable base map");
 
         ServerBridge {
             econ: None,
             base_maps,
-            generator_configs,
-            walker_configs,
+            pipelines,
             waypoints_configs,
-            current_generator_params,
-            current_walker_params,
+            current_pipeline,
             current_waypoints,
             args,
             generator,
+            exporter,
         }
     }
 
     fn start(&mut self) {
         self.econ = Some(
-            Econ::connect(&format!("127.0.0.1:{}", self.args.port), 1024).unwrap_or_else(|error| {
+            Econ::connect(format!("127.0.0.1:{}", self.args.port), 1024).unwrap_or_else(|error| {
                 panic!("Failed to establish stream connection: {}", error);
             }),
         );
@@ -206,11 +201,11 @@ impl ServerBridge {
         let mut gap_size = 1;
 
         let mut gap = || {
-            let gap = " ".repeat(gap_size);
+            let gap = " ".repeat(gap_size);
 
             gap_size += 1;
 
-            return gap;
+            gap
         };
 
         self.add_vote(
@@ -220,17 +215,7 @@ impl ServerBridge {
         self.add_vote(&gap(), "info");
 
         self.add_vote(
-            &format!(
-                "Current generator configuration: {}",
-                self.current_generator_params
-            ),
-            "info",
-        );
-        self.add_vote(
-            &format!(
-                "Current walker configuration: {}",
-                self.current_walker_params
-            ),
+            &format!("Current pipeline: {}", self.current_pipeline),
             "info",
         );
         self.add_vote(
@@ -247,25 +232,19 @@ impl ServerBridge {
             config_type: &str,
             config_inner_type: &str,
         ) -> Vec<(String, String)> {
-            let mut votes = Vec::new();
-
-            for name in configs.keys() {
-                votes.push((
-                    format!("Set {} configuration: {}", config_type, name),
-                    format!("echo call configurate {} {}", config_inner_type, name),
-                ));
-            }
-
-            return votes;
+            configs
+                .keys()
+                .sorted()
+                .map(|name| {
+                    (
+                        format!("Set {} configuration: {}", config_type, name),
+                        format!("echo call configurate {} {}", config_inner_type, name),
+                    )
+                })
+                .collect()
         }
 
-        for (desc, command) in &list_available(&self.generator_configs, "generator", "generator") {
-            self.add_vote(desc, command);
-        }
-
-        self.add_vote(&gap(), "info");
-
-        for (desc, command) in &list_available(&self.walker_configs, "walker", "walker") {
+        for (desc, command) in &list_available(&self.pipelines, "pipeline", "pipeline") {
             self.add_vote(desc, command);
         }
 
@@ -280,9 +259,7 @@ impl ServerBridge {
     fn check_call(&mut self, data: &str) {
         let mut callback_args = Vec::new();
 
-        let mut idx = 0;
-
-        for piece_view in data.split(' ') {
+        for (idx, piece_view) in data.split(' ').enumerate() {
             if idx == 3 {
                 // handle only echo
                 if piece_view != "console:" {
@@ -296,54 +273,39 @@ impl ServerBridge {
             } else if idx > 4 {
                 callback_args.push(piece_view);
             }
-
-            idx += 1;
         }
 
-        match callback_args[0] {
-            "generate" => {
-                let seed = random_seed();
-                let mut map_name = self.generate_map(seed);
+        match callback_args.first().copied() {
+            Some("generate") => {
+                let map_name =
+                    (0..MAX_GENERATION_ATTEMPTS).find_map(|_| self.generate_map(random_seed()));
 
-                while map_name.is_none() {
-                    map_name = self.generate_map(seed);
+                match map_name {
+                    Some(map_name) => self.change_map(&map_name),
+                    None => {
+                        warn!(gen!("No map after {} attempts"), MAX_GENERATION_ATTEMPTS);
+                        self.say("Map generation failed, try another configuration");
+                    }
                 }
-
-                self.change_map(&map_name.unwrap());
             }
-            "configurate" => {
+            Some("configurate") => {
                 if callback_args.len() < 3 {
                     warn!(gen!("Missing arguments on configuration call"));
                     return;
                 }
 
                 match callback_args[1] {
-                    "generator" => {
-                        if !self.generator_configs.contains_key(callback_args[2]) {
-                            warn!(
-                                gen!("Unknown generator configuration: {}"),
-                                callback_args[2]
-                            );
+                    "pipeline" => {
+                        let Some(pipeline) = self.pipelines.get(callback_args[2]) else {
+                            warn!(gen!("Unknown pipeline: {}"), callback_args[2]);
                             return;
-                        }
+                        };
 
-                        // TODO: quotation marks?
-                        self.current_generator_params = callback_args[2].to_string();
-
-                        self.generator.params =
-                            self.generator_configs[&self.current_generator_params].clone();
-                    }
-                    "walker" => {
-                        if !self.walker_configs.contains_key(callback_args[2]) {
-                            warn!(gen!("Unknown walker configuration: {}"), callback_args[2]);
-                            return;
-                        }
+                        pipeline.setup(&mut self.generator);
+                        self.exporter.set_blocks(pipeline.generator.blocks.clone());
 
                         // TODO: quotation marks?
-                        self.current_walker_params = callback_args[2].to_string();
-
-                        self.generator.walker.params =
-                            self.walker_configs[&self.current_walker_params].clone();
+                        self.current_pipeline = callback_args[2].to_string();
                     }
                     "waypoints" => {
                         if !self.waypoints_configs.contains_key(callback_args[2]) {
@@ -356,11 +318,6 @@ impl ServerBridge {
 
                         // TODO: quotation marks?
                         self.current_waypoints = callback_args[2].to_string();
-
-                        self.generator
-                            .walker
-                            .set_waypoints(self.waypoints_configs[&self.current_waypoints].clone())
-                            .set_bounds(500, 500);
                     }
                     s => warn!(gen!("Unknown configuration: {}"), s),
                 }
@@ -373,11 +330,8 @@ impl ServerBridge {
 
     fn generate_map(&mut self, seed: Seed) -> Option<String> {
         let map_name = format!(
-            "{}_{}_{}_{}",
-            &self.current_generator_params,
-            &self.current_walker_params,
-            &self.current_waypoints,
-            seed
+            "{}_{}_{}",
+            &self.current_pipeline, &self.current_waypoints, seed
         );
 
         let map_path = self
@@ -389,32 +343,44 @@ impl ServerBridge {
 
         info!(gen!("Generating {}"), map_name);
 
-        self.generator.map.clear();
+        let waypoints = self.waypoints_configs[&self.current_waypoints].clone();
 
-        match self.generator.finalize(100_000) {
-            Ok(()) => {
-                info!(gen!("Finished map generation"));
-
-                let idx = random_seed() as usize % self.base_maps.len();
-
-                let mut exporter = Exporter::new(
-                    self.base_maps.get_mut(idx).unwrap(),
-                    &self.generator.map,
-                    ExporterConfig::default(),
-                );
+        let generated = match self.generator.generate(waypoints, seed) {
+            Ok((map, _, _)) => map,
+            Err(generation_error) => {
+                warn!(gen!("Generation Error: {}"), generation_error);
+                return None;
+            }
+        };
 
-                exporter.finalize().save_map(&map_path);
+        info!(gen!("Finished map generation"));
 
-                info!(gen!("Finished map exporting"));
+        let idx = random_seed() as usize % self.base_maps.len();
 
-                return Some(map_name);
-            }
-            Err(generation_error) => {
-                warn!(gen!("Generation Error: {:?}"), generation_error);
+        let mut map = match self.exporter.export(&self.base_maps[idx], &generated, seed) {
+            Ok(map) => map,
+            Err(export_error) => {
+                warn!(gen!("Export Error: {}"), export_error);
+                return None;
             }
+        };
+
+        let saved = File::create(&map_path)
+            .map_err(|err| err.to_string())
+            .and_then(|mut file| map.save(&mut file).map_err(|err| err.to_string()));
+
+        if let Err(save_error) = saved {
+            warn!(
+                gen!("Failed to save {}: {}"),
+                map_path.display(),
+                save_error
+            );
+            return None;
         }
 
-        None
+        info!(gen!("Finished map exporting"));
+
+        Some(map_name)
     }
 
     fn change_map(&mut self, map_name: &str) {
@@ -444,27 +410,19 @@ impl ServerBridge {
 
 fn print_configs(args: BridgeArgs) {
     println!(
-        "GeneratorParams: {}",
-        load_configs_from_dir::<GeneratorParams, _>(args.gen_configs.as_path())
-            .unwrap()
-            .keys()
-            .into_iter()
-            .join(",")
-    );
-    println!(
-        "WalkerParams: {}",
-        load_configs_from_dir::<WalkerParams, _>(args.wal_configs.as_path())
+        "Pipelines: {}",
+        load_pipelines_from_dir(args.pipelines.as_path())
             .unwrap()
             .keys()
-            .into_iter()
+            .sorted()
             .join(",")
     );
     println!(
         "Waypoints: {}",
-        load_configs_from_dir::<NormalWaypoints, _>(args.way_configs.as_path())
+        load_configs_from_dir::<Vec<(f32, f32)>, _>(args.way_configs.as_path())
             .unwrap()
             .keys()
-            .into_iter()
+            .sorted()
             .join(",")
     );
 }
@@ -481,6 +439,25 @@ fn load_base_maps_paths<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
     paths
 }
 
+/// pipelines in `.json` or `.ron`, named after their file stem
+pub fn load_pipelines_from_dir<P: AsRef<Path>>(
+    path: P,
+) -> Result<HashMap<String, Pipeline>, Box<dyn Error>> {
+    let mut pipelines = HashMap::new();
+
+    for file_path in fs::read_dir(path)? {
+        let file_path = file_path?.path();
+        let name = file_path.file_stem().unwrap().to_string_lossy().to_string();
+
+        let pipeline = Pipeline::load(&file_path)
+            .map_err(|err| format!("failed to load '{}': {}", file_path.display(), err))?;
+
+        pipelines.insert(name, pipeline);
+    }
+
+    Ok(pipelines)
+}
+
 pub fn load_configs_from_dir<C, P>(path: P) -> Result<HashMap<String, C>, Box<dyn Error>>
 where
     C: DeserializeOwned,
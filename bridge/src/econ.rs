@@ -53,10 +53,6 @@ impl Econ {
         self.authed
     }
 
-    pub fn is_authed(&self) -> bool {
-        self.authed
-    }
-
     pub fn read(&mut self) -> Result<(), Error> {
         let written = self.connection.read(&mut self.buffer)?;
 
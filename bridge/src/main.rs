@@ -8,6 +8,6 @@ mod econ;
 
 fn main() {
     SimpleLogger::new().init().unwrap();
-    
+
     app::ServerBridge::run();
 }
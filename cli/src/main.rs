@@ -0,0 +1,294 @@
+mod pipeline;
+
+use std::{error::Error, fmt::Display, fs::File, path::PathBuf, process::ExitCode};
+
+use clap::{crate_version, Parser};
+use mapgen_core::{
+    generator::Generator,
+    pipeline::{MutationLoop, Pipeline},
+    random::{seed_from_str, sub_seed, Seed},
+    walker::waypoints::WaypointGenerator,
+};
+
+use crate::pipeline::MutationSpec;
+
+#[derive(Parser, Debug)]
+#[command(name = "mapgen")]
+#[command(version = crate_version!())]
+#[command(about = "Generate gores maps without the editor", long_about = None)]
+struct Args {
+    /// path of the generated map
+    output: PathBuf,
+
+    /// normalized waypoint as `x,y`, repeat for every waypoint in order,
+    /// defaults to the pipeline's waypoints
+    #[arg(
+        short = 'p',
+        long = "waypoint",
+        value_parser = parse_waypoint,
+        required_unless_present_any = ["pattern", "pipeline"],
+        conflicts_with = "pattern"
+    )]
+    waypoints: Vec<(f32, f32)>,
+
+    /// generate the waypoints as `name:arg,...` (random_walk, directed, zigzag, spiral),
+    /// seeded by the generation seed
+    #[arg(long)]
+    pattern: Option<MutationSpec>,
+
+    /// amount of generated waypoints
+    #[arg(long, default_value_t = 6, requires = "pattern")]
+    waypoint_count: usize,
+
+    /// scale of normalized waypoints in tiles, overrides the pipeline's one
+    #[arg(short, long)]
+    scale: Option<f32>,
+
+    /// generation seed, either a number or any string, overrides the pipeline's one
+    #[arg(long, value_parser = parse_seed)]
+    seed: Option<Seed>,
+
+    /// thickness of the freeze border, overrides the pipeline's one
+    #[arg(long)]
+    freeze: Option<usize>,
+
+    /// carve spawn and finish rooms, unless the pipeline already does
+    #[arg(long)]
+    rooms: bool,
+
+    /// pipeline file (.json or .ron) describing the generation
+    #[arg(long, conflicts_with_all = ["brush_mutations", "map_mutations", "walker_mutations"])]
+    pipeline: Option<PathBuf>,
+
+    /// brush mutation as `name:arg,...` (pulse, transition, taper), repeat to chain them
+    #[arg(long = "brush")]
+    brush_mutations: Vec<MutationSpec>,
+
+    /// brush shape as `name:arg,...` (circle, rectangle, diamond, superellipse, blob, stamp),
+    /// overrides the pipeline's one
+    #[arg(long)]
+    shape: Option<MutationSpec>,
+
+    /// brush orientation as `name[:smoothing]` (fixed, mirror, rotate), overrides the pipeline's one
+    #[arg(long)]
+    orientation: Option<MutationSpec>,
+
+    /// brush rim as `block:width` (freeze, deep_freeze, unhookable, hookable), repeat for
+    /// every ring from the inside out, overrides the pipeline's ones
+    #[arg(long = "rim")]
+    rims: Vec<MutationSpec>,
+
+    /// map mutation as `name:arg,...[,steps]` (edge_freeze, blob_fill, roughen),
+    /// repeat to chain them, runs once at the end unless `steps` is given
+    #[arg(long = "map")]
+    map_mutations: Vec<MutationSpec>,
+
+    /// walker mutation as `name:arg,...` (straight, left, right, backwards, random, weighted),
+    /// repeat to chain them, defaults to `straight:1`
+    #[arg(long = "walker")]
+    walker_mutations: Vec<MutationSpec>,
+
+    /// chance to spawn a branch on every walker step, overrides the pipeline's one
+    #[arg(long)]
+    branches: Option<f32>,
+
+    /// chance of a branch to rejoin the main route instead of ending in a dead end
+    #[arg(long, requires = "branches")]
+    rejoin_chance: Option<f32>,
+
+    /// walker mutation of every branch as `name:arg,...`, repeat to chain them,
+    /// overrides the pipeline's ones
+    #[arg(long = "branch-walker", requires = "branches")]
+    branch_walker_mutations: Vec<MutationSpec>,
+
+    /// path of a json file the map's quality metrics are written to
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// path of a json file every step of every walker is written to
+    #[arg(long)]
+    trace: Option<PathBuf>,
+}
+
+fn parse_waypoint(value: &str) -> Result<(f32, f32), String> {
+    let Some((x, y)) = value.split_once(',') else {
+        return Err(format!("expected `x,y`, got '{}'", value));
+    };
+
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<f32>()
+            .map_err(|err| format!("invalid coordinate '{}': {}", v, err))
+    };
+
+    Ok((parse(x)?, parse(y)?))
+}
+
+fn parse_seed(value: &str) -> Result<Seed, String> {
+    Ok(value.parse().unwrap_or_else(|_| seed_from_str(value)))
+}
+
+fn validate(args: &Args) -> Result<(), String> {
+    for &(x, y) in args.waypoints.iter() {
+        if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+            return Err(format!("waypoint ({}, {}) is outside of [0, 1]", x, y));
+        }
+    }
+
+    Ok(())
+}
+
+/// builds a pipeline out of mutation flags, each kind of them is run as one endless loop
+fn pipeline_from_specs(args: &Args) -> Result<Pipeline, String> {
+    let default_walker = ["straight:1".parse()?];
+    let walker_specs = match args.walker_mutations.is_empty() {
+        true => &default_walker[..],
+        false => &args.walker_mutations[..],
+    };
+
+    let brush = args
+        .brush_mutations
+        .iter()
+        .map(MutationSpec::brush_mutation)
+        .collect::<Result<Vec<_>, _>>()?;
+    let map = args
+        .map_mutations
+        .iter()
+        .map(MutationSpec::map_mutation)
+        .collect::<Result<Vec<_>, _>>()?;
+    let walker = walker_specs
+        .iter()
+        .map(MutationSpec::walker_mutation)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Pipeline {
+        brush: vec![MutationLoop::endless(brush)],
+        map: vec![MutationLoop::endless(map)],
+        walker: vec![MutationLoop::endless(walker)],
+        ..Default::default()
+    })
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    validate(&args)?;
+
+    let mut pipeline = match &args.pipeline {
+        Some(path) => Pipeline::load(path)
+            .map_err(|err| format!("failed to load '{}': {}", path.display(), err))?,
+        None => pipeline_from_specs(&args)?,
+    };
+
+    if let Some(scale) = args.scale {
+        pipeline.generator.scale_factor = scale;
+    }
+
+    if let Some(seed) = args.seed {
+        pipeline.generator.seed = seed;
+    }
+
+    if let Some(shape) = &args.shape {
+        pipeline.generator.brush_shape = shape.brush_shape()?;
+    }
+
+    if let Some(orientation) = &args.orientation {
+        pipeline.generator.brush_orientation = orientation.brush_orientation()?;
+    }
+
+    if !args.rims.is_empty() {
+        pipeline.generator.brush_rims = args
+            .rims
+            .iter()
+            .map(MutationSpec::brush_rim)
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
+    if args.rooms {
+        pipeline
+            .generator
+            .rooms
+            .get_or_insert_with(Default::default);
+    }
+
+    if let Some(thickness) = args.freeze {
+        pipeline
+            .generator
+            .freeze
+            .get_or_insert_with(Default::default)
+            .thickness = thickness;
+    }
+
+    if let Some(probability) = args.branches {
+        let branches = pipeline
+            .generator
+            .branches
+            .get_or_insert_with(Default::default);
+
+        branches.config.probability = probability;
+
+        if let Some(rejoin_chance) = args.rejoin_chance {
+            branches.config.rejoin_chance = rejoin_chance;
+        }
+
+        if !args.branch_walker_mutations.is_empty() {
+            let walker = args
+                .branch_walker_mutations
+                .iter()
+                .map(MutationSpec::walker_mutation)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            branches.walker = vec![MutationLoop::endless(walker)];
+        }
+    }
+
+    let waypoints = match &args.pattern {
+        Some(pattern) => {
+            let generator = WaypointGenerator {
+                count: args.waypoint_count,
+                ..WaypointGenerator::new(pattern.waypoint_pattern()?)
+            };
+
+            generator
+                .generate(sub_seed(pipeline.generator.seed, "waypoints"))
+                .waypoints
+        }
+        None if args.waypoints.is_empty() => pipeline.generator.waypoints.clone(),
+        None => args.waypoints,
+    };
+
+    let mut generator = Generator::new();
+
+    pipeline.setup(&mut generator);
+
+    let (mut map, report, trace) = generator
+        .generate(waypoints, pipeline.generator.seed)
+        .map_err(|err| format!("generation failed: {}", err))?;
+
+    let save_error =
+        |path: &PathBuf, err: &dyn Display| format!("failed to save '{}': {}", path.display(), err);
+
+    let mut file = File::create(&args.output).map_err(|err| save_error(&args.output, &err))?;
+    map.save(&mut file)
+        .map_err(|err| save_error(&args.output, &err))?;
+
+    if let Some(path) = &args.report {
+        let file = File::create(path).map_err(|err| save_error(path, &err))?;
+        serde_json::to_writer_pretty(file, &report).map_err(|err| save_error(path, &err))?;
+    }
+
+    if let Some(path) = &args.trace {
+        let file = File::create(path).map_err(|err| save_error(path, &err))?;
+        serde_json::to_writer(file, &trace).map_err(|err| save_error(path, &err))?;
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
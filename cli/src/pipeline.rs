@@ -0,0 +1,336 @@
+use std::{fmt::Display, str::FromStr};
+
+use mapgen_core::{
+    block::BlockType,
+    brush::{BrushOrientation, BrushRim, BrushShape},
+    mutations::{
+        brush::{
+            pulse::PulseBrushMutation, taper::TaperBrushMutation,
+            transition::TransitionBrushMutation,
+        },
+        map::{
+            blob_fill::BlobFillMapMutation, edge_freeze::EdgeFreezeMapMutation,
+            roughen::RoughenMapMutation,
+        },
+        walker::{
+            backwards::BackwardsWalkerMutation, left::LeftWalkerMutation,
+            random::RandomWalkerMutation, right::RightWalkerMutation,
+            straight::StraightWalkerMutation, weighted::WeightedWalkerMutation,
+        },
+    },
+    pipeline::{BrushMutation, MapMutation, WalkerMutation},
+    stamp::{BrushStamp, StampConfig},
+    walker::waypoints::WaypointPattern,
+};
+
+/// textual mutation description in form of `name:arg,arg,...`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutationSpec {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl FromStr for MutationSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = s.split_once(':').unwrap_or((s, ""));
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(format!("missing mutation name in '{}'", s));
+        }
+
+        let args = args
+            .split(',')
+            .map(str::trim)
+            .filter(|arg| !arg.is_empty())
+            .map(String::from)
+            .collect();
+
+        Ok(Self {
+            name: name.to_lowercase(),
+            args,
+        })
+    }
+}
+
+impl Display for MutationSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name, self.args.join(","))
+    }
+}
+
+impl MutationSpec {
+    fn expect_args(&self, names: &[&str]) -> Result<(), String> {
+        if self.args.len() != names.len() {
+            return Err(format!(
+                "'{}' expects {} argument(s) ({}), got {}",
+                self.name,
+                names.len(),
+                names.join(", "),
+                self.args.len()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn arg<T: FromStr>(&self, index: usize, name: &str) -> Result<T, String> {
+        self.args[index]
+            .parse()
+            .map_err(|_| format!("invalid {} '{}' for '{}'", name, self.args[index], self))
+    }
+
+    pub fn brush_mutation(&self) -> Result<BrushMutation, String> {
+        match self.name.as_str() {
+            "pulse" => {
+                self.expect_args(&["border", "climax", "steps", "peak"])?;
+
+                Ok(BrushMutation::Pulse(PulseBrushMutation::new(
+                    self.arg(0, "border")?,
+                    self.arg(1, "climax")?,
+                    self.arg(2, "steps")?,
+                    self.arg(3, "peak")?,
+                )))
+            }
+            "transition" => {
+                self.expect_args(&["from", "to", "steps"])?;
+
+                Ok(BrushMutation::Transition(TransitionBrushMutation::new(
+                    self.arg(0, "from")?,
+                    self.arg(1, "to")?,
+                    self.arg(2, "steps")?,
+                )))
+            }
+            "taper" => {
+                self.expect_args(&["from", "to"])?;
+
+                Ok(BrushMutation::Taper(TaperBrushMutation::new(
+                    self.arg(0, "from")?,
+                    self.arg(1, "to")?,
+                )))
+            }
+            name => Err(format!("unknown brush mutation '{}'", name)),
+        }
+    }
+
+    pub fn brush_shape(&self) -> Result<BrushShape, String> {
+        match self.name.as_str() {
+            "circle" => {
+                self.expect_args(&["circularity"])?;
+                Ok(BrushShape::Circle {
+                    circularity: self.arg(0, "circularity")?,
+                })
+            }
+            "rectangle" => {
+                self.expect_args(&["aspect"])?;
+                Ok(BrushShape::Rectangle {
+                    aspect: self.arg(0, "aspect")?,
+                })
+            }
+            "diamond" => {
+                self.expect_args(&[])?;
+                Ok(BrushShape::Diamond)
+            }
+            "superellipse" => {
+                self.expect_args(&["exponent"])?;
+                Ok(BrushShape::Superellipse {
+                    exponent: self.arg(0, "exponent")?,
+                })
+            }
+            "blob" => {
+                self.expect_args(&["seed", "roughness"])?;
+                Ok(BrushShape::Blob {
+                    seed: self.arg(0, "seed")?,
+                    roughness: self.arg(1, "roughness")?,
+                })
+            }
+            "stamp" => {
+                self.expect_args(&["path"])?;
+                let config = StampConfig {
+                    path: self.arg(0, "path")?,
+                    ..Default::default()
+                };
+
+                BrushStamp::load(config)
+                    .map(BrushShape::Stamp)
+                    .map_err(|err| err.to_string())
+            }
+            name => Err(format!("unknown brush shape '{}'", name)),
+        }
+    }
+
+    pub fn brush_orientation(&self) -> Result<BrushOrientation, String> {
+        match self.name.as_str() {
+            "fixed" => {
+                self.expect_args(&[])?;
+                Ok(BrushOrientation::Fixed)
+            }
+            "mirror" => {
+                self.expect_args(&[])?;
+                Ok(BrushOrientation::Mirror)
+            }
+            "rotate" => {
+                self.expect_args(&["smoothing"])?;
+                Ok(BrushOrientation::Rotate {
+                    smoothing: self.arg(0, "smoothing")?,
+                })
+            }
+            name => Err(format!("unknown brush orientation '{}'", name)),
+        }
+    }
+
+    pub fn waypoint_pattern(&self) -> Result<WaypointPattern, String> {
+        match self.name.as_str() {
+            "random_walk" => {
+                self.expect_args(&[])?;
+                Ok(WaypointPattern::RandomWalk)
+            }
+            "directed" => {
+                self.expect_args(&["jitter"])?;
+                Ok(WaypointPattern::Directed {
+                    jitter: self.arg(0, "jitter")?,
+                })
+            }
+            "zigzag" => {
+                self.expect_args(&["amplitude", "frequency"])?;
+                Ok(WaypointPattern::ZigZag {
+                    amplitude: self.arg(0, "amplitude")?,
+                    frequency: self.arg(1, "frequency")?,
+                })
+            }
+            "spiral" => {
+                self.expect_args(&["turns"])?;
+                Ok(WaypointPattern::Spiral {
+                    turns: self.arg(0, "turns")?,
+                })
+            }
+            name => Err(format!("unknown waypoint pattern '{}'", name)),
+        }
+    }
+
+    /// the name is the block of the rim
+    pub fn brush_rim(&self) -> Result<BrushRim, String> {
+        let block = match self.name.as_str() {
+            "freeze" => BlockType::Freeze,
+            "deep_freeze" => BlockType::DeepFreeze,
+            "unhookable" => BlockType::Unhookable,
+            "hookable" => BlockType::Hookable,
+            name => return Err(format!("unknown rim block '{}'", name)),
+        };
+
+        self.expect_args(&["width"])?;
+
+        Ok(BrushRim {
+            block,
+            width: self.arg(0, "width")?,
+        })
+    }
+
+    /// map passes run once at the end, unless a trailing `steps` argument is given
+    pub fn map_mutation(&self) -> Result<MapMutation, String> {
+        let with_steps = |names: &[&str]| -> Result<(bool, usize), String> {
+            match self.args.len() == names.len() + 1 {
+                true => Ok((false, self.arg(names.len(), "steps")?)),
+                false => self.expect_args(names).map(|_| (true, 0)),
+            }
+        };
+
+        match self.name.as_str() {
+            "edge_freeze" => {
+                let (at_end, steps) = with_steps(&["thickness"])?;
+                Ok(MapMutation::EdgeFreeze(EdgeFreezeMapMutation::new(
+                    self.arg(0, "thickness")?,
+                    at_end,
+                    steps,
+                )))
+            }
+            "blob_fill" => {
+                let (at_end, steps) = with_steps(&["max_empty", "max_wall"])?;
+                Ok(MapMutation::BlobFill(BlobFillMapMutation::new(
+                    self.arg(0, "max_empty")?,
+                    self.arg(1, "max_wall")?,
+                    at_end,
+                    steps,
+                )))
+            }
+            "roughen" => {
+                let (at_end, steps) = with_steps(&["scale", "strength"])?;
+                Ok(MapMutation::Roughen(RoughenMapMutation::new(
+                    self.arg(0, "scale")?,
+                    self.arg(1, "strength")?,
+                    at_end,
+                    steps,
+                )))
+            }
+            name => Err(format!("unknown map mutation '{}'", name)),
+        }
+    }
+
+    pub fn walker_mutation(&self) -> Result<WalkerMutation, String> {
+        match self.name.as_str() {
+            "straight" => {
+                self.expect_args(&["steps"])?;
+                Ok(WalkerMutation::Straight(StraightWalkerMutation::new(
+                    self.arg(0, "steps")?,
+                )))
+            }
+            "left" => {
+                self.expect_args(&["steps"])?;
+                Ok(WalkerMutation::Left(LeftWalkerMutation::new(
+                    self.arg(0, "steps")?,
+                )))
+            }
+            "right" => {
+                self.expect_args(&["steps"])?;
+                Ok(WalkerMutation::Right(RightWalkerMutation::new(
+                    self.arg(0, "steps")?,
+                )))
+            }
+            "backwards" => {
+                self.expect_args(&["steps"])?;
+                Ok(WalkerMutation::Backwards(BackwardsWalkerMutation::new(
+                    self.arg(0, "steps")?,
+                )))
+            }
+            "random" => {
+                self.expect_args(&["steps"])?;
+                Ok(WalkerMutation::Random(RandomWalkerMutation::new(
+                    self.arg(0, "steps")?,
+                )))
+            }
+            "weighted" => {
+                self.expect_args(&[
+                    "steps",
+                    "straight",
+                    "left",
+                    "right",
+                    "backwards",
+                    "momentum",
+                ])?;
+
+                let mutation = WeightedWalkerMutation::from_weights(
+                    self.arg(0, "steps")?,
+                    [
+                        self.arg(1, "straight")?,
+                        self.arg(2, "left")?,
+                        self.arg(3, "right")?,
+                        self.arg(4, "backwards")?,
+                    ],
+                    self.arg(5, "momentum")?,
+                );
+
+                if !mutation.dist.is_valid() {
+                    return Err(format!(
+                        "weights of '{}' must be non-negative with a positive sum",
+                        self
+                    ));
+                }
+
+                Ok(WalkerMutation::Weighted(mutation))
+            }
+            name => Err(format!("unknown walker mutation '{}'", name)),
+        }
+    }
+}
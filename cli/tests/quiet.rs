@@ -0,0 +1,26 @@
+use std::{env, process::Command};
+
+#[test]
+fn generation_keeps_stdout_clean() {
+    let output_path = env::temp_dir().join(format!("mapgen-quiet-{}.map", std::process::id()));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mapgen"))
+        .arg(&output_path)
+        .args(["-p", "0,1", "-p", "1,0", "--scale", "40", "--seed", "1"])
+        .args(["--brush", "pulse:2,5,20,0.5"])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&output_path);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        output.stdout.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
@@ -0,0 +1,356 @@
+//! Quality metrics of generated maps.
+//!
+//! A [`MapReport`] is computed from the finished game layer alone, so it can
+//! be used to compare or filter maps regardless of how they were generated.
+//! Walls are solid blocks, everything else is passable.
+
+use std::collections::VecDeque;
+
+use ndarray::Array2;
+use twmap::{GameLayer, GameTile, TwMap};
+
+use crate::{
+    block::{BlockMapping, BlockType},
+    position::Direction,
+};
+
+/// length of the pieces the way is split into when counting turns, so
+/// that the stairs of diagonal corridors don't count as turns
+const TURN_SEGMENT: usize = 8;
+
+/// start and end of the way through a map
+pub type WayEnds = [(usize, usize); 2];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapReport {
+    /// tiles along the shortest way from a spawn to the finish, `None` if there is none.
+    /// Maps without spawn or finish tiles are measured between the ends passed
+    /// to [`MapReport::new`] instead
+    pub path_length: Option<usize>,
+    /// direction changes along that way
+    pub turns: Option<usize>,
+    /// corridors branching off that way, longer than the corridor they start at is wide
+    pub dead_ends: Option<usize>,
+    /// `corridor_widths[w]` is the amount of passable tiles in corridors `w` tiles wide
+    pub corridor_widths: Vec<usize>,
+    /// ratios of tiles within the bounding box
+    pub freeze_ratio: f32,
+    pub hookable_ratio: f32,
+    pub empty_ratio: f32,
+    /// smallest box around all passable tiles, `None` if there are none
+    pub bounding_box: Option<BoundingBox>,
+}
+
+impl MapReport {
+    /// analyzes the game layer of `map`, its tiles have to be loaded. `ends`
+    /// stand in for the spawn and the finish where the map lacks them
+    pub fn new(map: &TwMap, blocks: &BlockMapping, ends: Option<WayEnds>) -> Self {
+        let game = map.find_physics_layer::<GameLayer>().unwrap();
+
+        Self::from_tiles(game.tiles.unwrap_ref(), blocks, ends)
+    }
+
+    pub fn from_tiles(
+        tiles: &Array2<GameTile>,
+        blocks: &BlockMapping,
+        ends: Option<WayEnds>,
+    ) -> Self {
+        let passable = tiles.map(|tile| !blocks.is_solid(tile.id));
+        let widths = corridor_widths(&passable);
+
+        let bounding_box = bounding_box(&passable);
+        let ratio = |block: BlockType| match bounding_box {
+            Some(BoundingBox {
+                x,
+                y,
+                width,
+                height,
+            }) => {
+                let area = tiles.slice(ndarray::s![x..x + width, y..y + height]);
+                let id = blocks.tile_id(block);
+                let count = area.iter().filter(|tile| tile.id == id).count();
+
+                count as f32 / (width * height) as f32
+            }
+            None => 0.0,
+        };
+
+        let mut corridor_widths = vec![0; widths.iter().max().map_or(0, |&max| max + 1)];
+
+        for &width in widths.iter().filter(|&&width| width != 0) {
+            corridor_widths[width] += 1;
+        }
+
+        let path = shortest_path(tiles, &passable, blocks, ends);
+
+        Self {
+            path_length: path.as_ref().map(|path| path.len() - 1),
+            turns: path.as_deref().map(count_turns),
+            dead_ends: path
+                .as_deref()
+                .map(|path| count_dead_ends(&passable, &widths, path)),
+            corridor_widths,
+            freeze_ratio: ratio(BlockType::Freeze),
+            hookable_ratio: ratio(BlockType::Hookable),
+            empty_ratio: ratio(BlockType::Empty),
+            bounding_box,
+        }
+    }
+
+    /// average corridor width over all passable tiles
+    pub fn mean_corridor_width(&self) -> f32 {
+        let (sum, count) = self
+            .corridor_widths
+            .iter()
+            .enumerate()
+            .fold((0, 0), |(sum, count), (width, &tiles)| {
+                (sum + width * tiles, count + tiles)
+            });
+
+        match count {
+            0 => 0.0,
+            _ => sum as f32 / count as f32,
+        }
+    }
+}
+
+/// 4-neighborhood of a tile, clipped to the grid
+fn neighbors<T>(grid: &Array2<T>, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    let (width, height) = grid.dim();
+
+    [
+        (x.wrapping_sub(1), y),
+        (x + 1, y),
+        (x, y.wrapping_sub(1)),
+        (x, y + 1),
+    ]
+    .into_iter()
+    .filter(move |&(x, y)| x < width && y < height)
+}
+
+/// smallest box around all passable tiles, `None` if there are none
+pub fn passable_bounds(tiles: &Array2<GameTile>, blocks: &BlockMapping) -> Option<BoundingBox> {
+    bounding_box(&tiles.map(|tile| !blocks.is_solid(tile.id)))
+}
+
+fn bounding_box(passable: &Array2<bool>) -> Option<BoundingBox> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+    for ((x, y), _) in passable.indexed_iter().filter(|(_, &passable)| passable) {
+        bounds = Some(match bounds {
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+
+    bounds.map(|(x0, y0, x1, y1)| BoundingBox {
+        x: x0,
+        y: y0,
+        width: x1 - x0 + 1,
+        height: y1 - y0 + 1,
+    })
+}
+
+/// width of the corridor at every passable tile, the shortest of its
+/// horizontal, vertical and diagonal runs, 0 for walls
+fn corridor_widths(passable: &Array2<bool>) -> Array2<usize> {
+    let mut widths = Array2::from_elem(passable.dim(), usize::MAX);
+
+    for step in [(1, 0), (0, 1), (1, 1), (1, -1)] {
+        ndarray::Zip::from(&mut widths)
+            .and(&runs(passable, step))
+            .for_each(|width, &run| *width = (*width).min(run));
+    }
+
+    widths
+}
+
+/// length of the run of passable tiles in direction `step` every tile is part of
+fn runs(passable: &Array2<bool>, (dx, dy): (isize, isize)) -> Array2<usize> {
+    let (width, height) = passable.dim();
+    let mut runs = Array2::zeros((width, height));
+
+    let at = |x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && passable[[x as usize, y as usize]]
+    };
+
+    for ((x, y), _) in passable.indexed_iter() {
+        let (x, y) = (x as isize, y as isize);
+
+        // only start at the first tile of every run
+        if !at(x, y) || at(x - dx, y - dy) {
+            continue;
+        }
+
+        let mut length = 0;
+
+        while at(x + length * dx, y + length * dy) {
+            length += 1;
+        }
+
+        for i in 0..length {
+            runs[[(x + i * dx) as usize, (y + i * dy) as usize]] = length as usize;
+        }
+    }
+
+    runs
+}
+
+/// breadth first search from all spawns to the nearest finish tile, the
+/// passable `ends` replace the spawns and the finish if there are none
+fn shortest_path(
+    tiles: &Array2<GameTile>,
+    passable: &Array2<bool>,
+    blocks: &BlockMapping,
+    ends: Option<WayEnds>,
+) -> Option<Vec<(usize, usize)>> {
+    let [spawn, finish] = [BlockType::Spawn, BlockType::Finish].map(|block| blocks.tile_id(block));
+    let ends = ends.filter(|ends| ends.iter().all(|&pos| passable.get(pos) == Some(&true)));
+
+    let mut starts: Vec<_> = tiles
+        .indexed_iter()
+        .filter(|(_, tile)| tile.id == spawn)
+        .map(|(pos, _)| pos)
+        .collect();
+
+    if starts.is_empty() {
+        starts.extend(ends.map(|[start, _]| start));
+    }
+
+    let end = match tiles.iter().any(|tile| tile.id == finish) {
+        true => None,
+        false => Some(ends?[1]),
+    };
+    let is_end = |pos: (usize, usize)| match end {
+        Some(end) => pos == end,
+        None => tiles[pos].id == finish,
+    };
+
+    let mut parent: Array2<Option<(usize, usize)>> = Array2::from_elem(tiles.dim(), None);
+    let mut queue = VecDeque::new();
+
+    for pos in starts {
+        parent[pos] = Some(pos);
+        queue.push_back(pos);
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        if is_end(pos) {
+            let mut path = vec![pos];
+            let mut current = pos;
+
+            // spawns are their own parent
+            while let Some(prev) = parent[current].filter(|&prev| prev != current) {
+                path.push(prev);
+                current = prev;
+            }
+
+            path.reverse();
+
+            return Some(path);
+        }
+
+        for next in neighbors(passable, pos) {
+            if passable[next] && parent[next].is_none() {
+                parent[next] = Some(pos);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+fn count_turns(path: &[(usize, usize)]) -> usize {
+    let directions: Vec<Direction> = path
+        .iter()
+        .step_by(TURN_SEGMENT)
+        .zip(path.iter().skip(TURN_SEGMENT).step_by(TURN_SEGMENT))
+        .map(|(&(x0, y0), &(x1, y1))| {
+            let (dx, dy) = (x1 as isize - x0 as isize, y1 as isize - y0 as isize);
+
+            match (dx.abs() >= dy.abs(), dx >= 0, dy >= 0) {
+                (true, true, _) => Direction::Right,
+                (true, false, _) => Direction::Left,
+                (false, _, true) => Direction::Down,
+                (false, _, false) => Direction::Up,
+            }
+        })
+        .collect();
+
+    directions
+        .windows(2)
+        .filter(|pair| pair[0] != pair[1])
+        .count()
+}
+
+/// tiles further away from the way than the corridor is wide where it was
+/// left belong to dead ends, every connected group of them is one
+fn count_dead_ends(
+    passable: &Array2<bool>,
+    widths: &Array2<usize>,
+    path: &[(usize, usize)],
+) -> usize {
+    // distance to the way and the corridor width where it was left
+    let mut distance: Array2<Option<(usize, usize)>> = Array2::from_elem(passable.dim(), None);
+    let mut queue = VecDeque::new();
+
+    for &pos in path {
+        distance[pos] = Some((0, 0));
+        queue.push_back(pos);
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        let (d, width) = distance[pos].unwrap();
+
+        for next in neighbors(passable, pos) {
+            if passable[next] && distance[next].is_none() {
+                // the way itself is usually widened by side corridors, their first tile isn't
+                let width = if d == 0 { widths[next] } else { width };
+
+                distance[next] = Some((d + 1, width));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let off_path = distance.map(|distance| matches!(distance, Some((d, width)) if d > width));
+    let mut visited = Array2::from_elem(passable.dim(), false);
+    let mut dead_ends = 0;
+
+    for ((x, y), _) in off_path.indexed_iter().filter(|(_, &off_path)| off_path) {
+        if visited[[x, y]] {
+            continue;
+        }
+
+        dead_ends += 1;
+        visited[[x, y]] = true;
+
+        let mut stack = vec![(x, y)];
+
+        while let Some(pos) = stack.pop() {
+            for next in neighbors(&off_path, pos) {
+                if off_path[next] && !visited[next] {
+                    visited[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    dead_ends
+}
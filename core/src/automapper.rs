@@ -0,0 +1,399 @@
+//! DDNet automapper.
+//!
+//! Parses `.rules` files as shipped with DDNet and applies their configs to
+//! tile layers, the same way the in-game editor's automapper does. A file
+//! consists of configs, each of them made of runs of index rules:
+//!
+//! ```text
+//! [Config]
+//! Index 1
+//!
+//! Index 16 XFLIP
+//! Pos 0 -1 EMPTY
+//! Pos 1 0 INDEX 1 OR 2 ROTATE
+//! Random 4
+//!
+//! NewRun
+//! NoLayerCopy
+//! Index 2
+//! Pos 0 -1 NOTINDEX 16
+//! NoDefaultRule
+//! ```
+//!
+//! Every index rule places its tile where all of its `Pos` conditions hold,
+//! later rules overwrite earlier ones. Unless `NoDefaultRule` is given, a rule
+//! without a condition at `Pos 0 0` only applies to tiles that aren't empty.
+//! Tiles are indexed `[y, x]`, like in twmap.
+
+use std::{fmt::Display, fs, path::Path, str::FromStr};
+
+use ndarray::Array2;
+use twmap::{Tile, TileFlags};
+
+use crate::random::Seed;
+
+/// flags compared by index conditions
+const ORIENTATION: TileFlags = TileFlags::FLIP_X
+    .union(TileFlags::FLIP_Y)
+    .union(TileFlags::ROTATE);
+
+#[derive(Debug)]
+pub enum AutomapperError {
+    Io(std::io::Error),
+    /// malformed line, `line` starts at 1
+    Parse {
+        line: usize,
+        reason: String,
+    },
+}
+
+impl Display for AutomapperError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AutomapperError::Io(err) => write!(f, "{}", err),
+            AutomapperError::Parse { line, reason } => {
+                write!(f, "invalid rules in line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AutomapperError {}
+
+/// index conditions use it for positions outside of the layer
+const OUTSIDE: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq)]
+struct IndexInfo {
+    /// tile index or [`OUTSIDE`]
+    id: i32,
+    /// only compared if the condition names any orientation
+    flags: Option<TileFlags>,
+}
+
+impl IndexInfo {
+    fn matches(&self, tile: Option<&Tile>) -> bool {
+        let (id, flags) = tile.map_or((OUTSIDE, TileFlags::empty()), |tile| {
+            (tile.id as i32, tile.flags & ORIENTATION)
+        });
+
+        id == self.id && self.flags.is_none_or(|expected| flags == expected)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Index(Vec<IndexInfo>),
+    NotIndex(Vec<IndexInfo>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PosRule {
+    x: isize,
+    y: isize,
+    condition: Condition,
+}
+
+impl PosRule {
+    /// positions outside of the layer are only matched by [`OUTSIDE`]
+    fn holds(&self, tiles: &Array2<Tile>, x: usize, y: usize) -> bool {
+        let (height, width) = tiles.dim();
+        let (check_x, check_y) = (x as isize + self.x, y as isize + self.y);
+
+        let tile = (check_x >= 0
+            && check_y >= 0
+            && (check_x as usize) < width
+            && (check_y as usize) < height)
+            .then(|| &tiles[[check_y as usize, check_x as usize]]);
+
+        match &self.condition {
+            Condition::Index(infos) => infos.iter().any(|info| info.matches(tile)),
+            Condition::NotIndex(infos) => !infos.iter().any(|info| info.matches(tile)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct IndexRule {
+    id: u8,
+    flags: TileFlags,
+    rules: Vec<PosRule>,
+    probability: f32,
+    default_rule: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    rules: Vec<IndexRule>,
+    /// rules see the layer as it was before the run, otherwise changes are seen immediately
+    copy: bool,
+}
+
+impl Run {
+    fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            copy: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutomapperConfig {
+    pub name: String,
+    runs: Vec<Run>,
+}
+
+impl AutomapperConfig {
+    /// tiles the layer, `seed` drives the `Random` rules
+    pub fn apply(&self, tiles: &mut Array2<Tile>, seed: Seed) {
+        let (height, width) = tiles.dim();
+
+        for (run_index, run) in self.runs.iter().enumerate() {
+            let copy = run.copy.then(|| tiles.clone());
+
+            for y in 0..height {
+                for x in 0..width {
+                    for (rule_index, rule) in run.rules.iter().enumerate() {
+                        let read = copy.as_ref().unwrap_or(tiles);
+
+                        if !rule.rules.iter().all(|pos| pos.holds(read, x, y)) {
+                            continue;
+                        }
+
+                        if rule.probability < 1.0
+                            && hash_location(seed, run_index, rule_index, x, y) >= rule.probability
+                        {
+                            continue;
+                        }
+
+                        tiles[[y, x]] = Tile::new(rule.id, rule.flags);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// uniform value in [0, 1) for every tile, run and rule
+fn hash_location(seed: Seed, run: usize, rule: usize, x: usize, y: usize) -> f32 {
+    let mut bytes = Vec::with_capacity(40);
+
+    for value in [seed, run as u64, rule as u64, x as u64, y as u64] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    (seahash::hash(&bytes) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Automapper {
+    configs: Vec<AutomapperConfig>,
+}
+
+impl Automapper {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, AutomapperError> {
+        let data = fs::read_to_string(path).map_err(AutomapperError::Io)?;
+
+        Self::parse(&data)
+    }
+
+    /// unknown lines are skipped, just like DDNet does
+    pub fn parse(data: &str) -> Result<Self, AutomapperError> {
+        let mut configs: Vec<AutomapperConfig> = Vec::new();
+
+        for (line_index, line) in data.lines().enumerate() {
+            let error = |reason: String| AutomapperError::Parse {
+                line: line_index + 1,
+                reason,
+            };
+
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let Some(name) = name.strip_suffix(']') else {
+                    return Err(error(format!("unclosed config name '{}'", line)));
+                };
+
+                configs.push(AutomapperConfig {
+                    name: name.to_string(),
+                    runs: vec![Run::new()],
+                });
+
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+
+            let Some(config) = configs.last_mut() else {
+                return Err(error(format!("'{}' outside of a config", keyword)));
+            };
+
+            let run = config.runs.last_mut().unwrap();
+
+            match keyword {
+                "NewRun" => config.runs.push(Run::new()),
+                "NoLayerCopy" => run.copy = false,
+                "Index" => {
+                    let id = parse_id(words.next(), &error)?;
+                    let flags = parse_flags(words, TileFlags::empty(), &error)?;
+
+                    run.rules.push(IndexRule {
+                        id,
+                        flags,
+                        rules: Vec::new(),
+                        probability: 1.0,
+                        default_rule: true,
+                    });
+                }
+                "Pos" | "Random" | "NoDefaultRule" => {
+                    let Some(rule) = run.rules.last_mut() else {
+                        return Err(error(format!("'{}' before any 'Index'", keyword)));
+                    };
+
+                    match keyword {
+                        "Pos" => rule.rules.extend(parse_pos(words, &error)?),
+                        "Random" => rule.probability = parse_probability(words.next(), &error)?,
+                        _ => rule.default_rule = false,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // rules without a condition on their own tile only apply to tiles that aren't empty
+        for rule in configs
+            .iter_mut()
+            .flat_map(|config| &mut config.runs)
+            .flat_map(|run| &mut run.rules)
+            .filter(|rule| rule.default_rule)
+        {
+            if !rule.rules.iter().any(|pos| pos.x == 0 && pos.y == 0) {
+                rule.rules.push(PosRule {
+                    x: 0,
+                    y: 0,
+                    condition: Condition::NotIndex(vec![IndexInfo { id: 0, flags: None }]),
+                });
+            }
+        }
+
+        Ok(Self { configs })
+    }
+
+    pub fn configs(&self) -> &[AutomapperConfig] {
+        &self.configs
+    }
+
+    pub fn config(&self, index: usize) -> Option<&AutomapperConfig> {
+        self.configs.get(index)
+    }
+
+    pub fn find_config(&self, name: &str) -> Option<&AutomapperConfig> {
+        self.configs.iter().find(|config| config.name == name)
+    }
+}
+
+fn parse_id<T: FromStr>(
+    word: Option<&str>,
+    error: &impl Fn(String) -> AutomapperError,
+) -> Result<T, AutomapperError> {
+    let word = word.ok_or_else(|| error("missing tile index".to_string()))?;
+
+    word.parse()
+        .map_err(|_| error(format!("invalid tile index '{}'", word)))
+}
+
+/// applies orientation words to `flags`, `ROTATE` toggles like in DDNet
+fn parse_flags<'a>(
+    words: impl Iterator<Item = &'a str>,
+    mut flags: TileFlags,
+    error: &impl Fn(String) -> AutomapperError,
+) -> Result<TileFlags, AutomapperError> {
+    for word in words {
+        match word {
+            "XFLIP" => flags |= TileFlags::FLIP_X,
+            "YFLIP" => flags |= TileFlags::FLIP_Y,
+            "ROTATE" => flags ^= TileFlags::ROTATE,
+            "NONE" => flags = TileFlags::empty(),
+            _ => return Err(error(format!("unknown orientation '{}'", word))),
+        }
+    }
+
+    Ok(flags)
+}
+
+/// `None` for conditions DDNet ignores as well
+fn parse_pos<'a>(
+    mut words: impl Iterator<Item = &'a str>,
+    error: &impl Fn(String) -> AutomapperError,
+) -> Result<Option<PosRule>, AutomapperError> {
+    let mut offset = || {
+        let word = words
+            .next()
+            .ok_or_else(|| error("missing position".to_string()))?;
+
+        word.parse::<isize>()
+            .map_err(|_| error(format!("invalid position '{}'", word)))
+    };
+
+    let (x, y) = (offset()?, offset()?);
+
+    let empty = || vec![IndexInfo { id: 0, flags: None }];
+
+    let condition = match words.next() {
+        Some("EMPTY") => Condition::Index(empty()),
+        Some("FULL") => Condition::NotIndex(empty()),
+        Some(kind @ ("INDEX" | "NOTINDEX")) => {
+            let words: Vec<&str> = words.collect();
+            let infos = words
+                .split(|&word| word == "OR")
+                .map(|info| {
+                    let id = parse_id(info.first().copied(), error)?;
+                    let flags = match info.len() {
+                        0 | 1 => None,
+                        _ => Some(parse_flags(
+                            info[1..].iter().copied(),
+                            TileFlags::empty(),
+                            error,
+                        )?),
+                    };
+
+                    Ok(IndexInfo { id, flags })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            match kind {
+                "INDEX" => Condition::Index(infos),
+                _ => Condition::NotIndex(infos),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(PosRule { x, y, condition }))
+}
+
+/// `Random 4` is a chance of 1/4, `Random 25%` the same
+fn parse_probability(
+    word: Option<&str>,
+    error: &impl Fn(String) -> AutomapperError,
+) -> Result<f32, AutomapperError> {
+    let word = word.ok_or_else(|| error("missing random value".to_string()))?;
+    let invalid = || error(format!("invalid random value '{}'", word));
+
+    let probability = match word.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().map_err(|_| invalid())? / 100.0,
+        None => 1.0 / word.parse::<f32>().map_err(|_| invalid())?,
+    };
+
+    if !probability.is_finite() || probability <= 0.0 {
+        return Err(invalid());
+    }
+
+    Ok(probability)
+}
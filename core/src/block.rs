@@ -0,0 +1,138 @@
+//! Semantic block types.
+//!
+//! Generation, analysis and export talk about blocks, a [`BlockMapping`]
+//! decides which game tile id and which design layer every block ends up as.
+//! With the `serde` feature mappings are stored as a map of overrides, e.g.
+//! `{ "Freeze": { "tile_id": 9, "design_layer": "Freeze" } }`, blocks that
+//! aren't listed keep their defaults.
+
+use std::collections::BTreeMap;
+
+use twmap::{GameTile, TileFlags};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockType {
+    Empty,
+    Hookable,
+    Unhookable,
+    Freeze,
+    DeepFreeze,
+    Spawn,
+    Start,
+    Finish,
+}
+
+impl BlockType {
+    pub const ALL: [BlockType; 8] = [
+        BlockType::Empty,
+        BlockType::Hookable,
+        BlockType::Unhookable,
+        BlockType::Freeze,
+        BlockType::DeepFreeze,
+        BlockType::Spawn,
+        BlockType::Start,
+        BlockType::Finish,
+    ];
+
+    /// walls, everything else can be passed
+    pub fn is_solid(&self) -> bool {
+        matches!(self, BlockType::Hookable | BlockType::Unhookable)
+    }
+
+    /// DDNet's game tile and the design layer of the block
+    pub fn default_info(&self) -> BlockInfo {
+        let (tile_id, design_layer) = match self {
+            BlockType::Empty => (0, None),
+            BlockType::Hookable => (1, Some(DesignLayer::Hookable)),
+            BlockType::Unhookable => (3, Some(DesignLayer::Unhookable)),
+            BlockType::Freeze => (9, Some(DesignLayer::Freeze)),
+            BlockType::DeepFreeze => (11, Some(DesignLayer::Freeze)),
+            BlockType::Spawn => (192, None),
+            BlockType::Start => (33, None),
+            BlockType::Finish => (34, None),
+        };
+
+        BlockInfo {
+            tile_id,
+            design_layer,
+        }
+    }
+}
+
+/// tile layers drawn on top of the game layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DesignLayer {
+    Unhookable,
+    Hookable,
+    Freeze,
+}
+
+impl DesignLayer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DesignLayer::Unhookable => "Unhookable",
+            DesignLayer::Hookable => "Hookable",
+            DesignLayer::Freeze => "Freeze",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockInfo {
+    pub tile_id: u8,
+    /// `None` if the block isn't drawn in any design layer
+    pub design_layer: Option<DesignLayer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct BlockMapping {
+    overrides: BTreeMap<BlockType, BlockInfo>,
+}
+
+impl BlockMapping {
+    pub fn info(&self, block: BlockType) -> BlockInfo {
+        self.overrides
+            .get(&block)
+            .copied()
+            .unwrap_or_else(|| block.default_info())
+    }
+
+    pub fn set_info(&mut self, block: BlockType, info: BlockInfo) {
+        if info == block.default_info() {
+            self.overrides.remove(&block);
+        } else {
+            self.overrides.insert(block, info);
+        }
+    }
+
+    pub fn tile_id(&self, block: BlockType) -> u8 {
+        self.info(block).tile_id
+    }
+
+    pub fn tile(&self, block: BlockType) -> GameTile {
+        GameTile::new(self.tile_id(block), TileFlags::empty())
+    }
+
+    /// first block mapped to the game tile `id`
+    pub fn block_type(&self, id: u8) -> Option<BlockType> {
+        BlockType::ALL
+            .into_iter()
+            .find(|&block| self.tile_id(block) == id)
+    }
+
+    /// tiles of unknown blocks are passable
+    pub fn is_solid(&self, id: u8) -> bool {
+        self.block_type(id).is_some_and(|block| block.is_solid())
+    }
+
+    /// design layer the game tile `id` is drawn in
+    pub fn design_layer(&self, id: u8) -> Option<DesignLayer> {
+        self.block_type(id)
+            .and_then(|block| self.info(block).design_layer)
+    }
+}
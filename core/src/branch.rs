@@ -0,0 +1,46 @@
+//! Secondary walkers branching off the main path.
+//!
+//! While the main walker moves, every step may spawn a branch at its current
+//! position. A branch leaves the main path sideways and walks with its own
+//! brush and step handler. It either heads for a later waypoint of the main
+//! walker and rejoins its route there, or heads away from it and ends in a
+//! freeze capped dead end once its lifetime is over. Branches still walking
+//! when the main walker arrives are walked until they end.
+
+/// how a branch ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BranchEnd {
+    /// reached a waypoint of the main walker
+    Rejoin,
+    /// ran out of steps, its end is filled with freeze
+    DeadEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BranchConfig {
+    /// chance to spawn a branch on every step of the main walker
+    pub probability: f32,
+    /// most branches walking at the same time
+    pub max_active: usize,
+    /// steps after which a branch ends, rejoining ones that didn't arrive become dead ends
+    pub lifetime: usize,
+    /// chance of a branch to head for the main route instead of a dead end
+    pub rejoin_chance: f32,
+    /// size of the brush a branch starts with
+    pub brush_size: usize,
+}
+
+impl Default for BranchConfig {
+    fn default() -> Self {
+        Self {
+            probability: 0.01,
+            max_active: 2,
+            lifetime: 60,
+            rejoin_chance: 0.5,
+            brush_size: 3,
+        }
+    }
+}
@@ -1,12 +1,217 @@
+use std::f32::consts::{PI, SQRT_2};
+
 use ndarray::Array2;
-use twmap::AnyTile;
+use twmap::{AnyTile, GameTile};
+
+use crate::{
+    block::{BlockMapping, BlockType},
+    position::{Direction, Vector2},
+    random::{value_noise, Seed},
+    stamp::BrushStamp,
+};
+
+/// position of a brush applied outside of the tiles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutOfBounds {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// outline of a brush, rasterized anew for every size
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BrushShape {
+    /// 1.0 is a circle, 0.0 a square
+    Circle { circularity: f32 },
+    /// height relative to the width, in (0, 1]
+    Rectangle { aspect: f32 },
+    Diamond,
+    /// 1.0 is a diamond, 2.0 a circle and higher values approach a square
+    Superellipse { exponent: f32 },
+    /// circle with its radius shrunk by up to `roughness` of it, in [0, 1]
+    Blob { seed: Seed, roughness: f32 },
+    /// image drawn by hand, its longer side is resampled to the size
+    Stamp(BrushStamp),
+}
+
+impl Default for BrushShape {
+    fn default() -> Self {
+        BrushShape::Circle { circularity: 1.0 }
+    }
+}
+
+impl BrushShape {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushShape::Circle { .. } => "circle",
+            BrushShape::Rectangle { .. } => "rectangle",
+            BrushShape::Diamond => "diamond",
+            BrushShape::Superellipse { .. } => "superellipse",
+            BrushShape::Blob { .. } => "blob",
+            BrushShape::Stamp(_) => "stamp",
+        }
+    }
+
+    /// texture of `size` x `size` tiles at most, tiles are covered if their center is inside,
+    /// the tile the brush is applied at always is unless the shape is a stamp
+    pub fn rasterize(&self, size: usize) -> Array2<bool> {
+        if let BrushShape::Stamp(stamp) = self {
+            return stamp.rasterize(size);
+        }
+
+        let size = size.max(1);
+        let height = match self {
+            BrushShape::Rectangle { aspect } => {
+                ((size as f32 * aspect.clamp(0.0, 1.0)).round() as usize).clamp(1, size)
+            }
+            _ => size,
+        };
+
+        let radius = size as f32 / 2.0;
+        let center = (size as f32 / 2.0, height as f32 / 2.0);
+
+        let mut texture = Array2::from_shape_fn((size, height), |(x, y)| {
+            // relative to the radius, so every shape fits into [-1, 1]
+            let dx = (x as f32 + 0.5 - center.0) / radius;
+            let dy = (y as f32 + 0.5 - center.1) / radius;
+
+            match self {
+                BrushShape::Circle { circularity } => {
+                    let circularity = circularity.clamp(0.0, 1.0);
+                    let max_radius = circularity + (1.0 - circularity) * f32::sqrt(2.0);
+
+                    dx.hypot(dy) <= max_radius
+                }
+                BrushShape::Rectangle { .. } => true,
+                BrushShape::Diamond => dx.abs() + dy.abs() <= 1.0,
+                BrushShape::Superellipse { exponent } => {
+                    let exponent = exponent.max(0.1);
 
-use crate::position::{as_index, Vector2};
+                    dx.abs().powf(exponent) + dy.abs().powf(exponent) <= 1.0
+                }
+                BrushShape::Blob { seed, roughness } => {
+                    // noise along a circle, so the outline closes without a seam
+                    let angle = dy.atan2(dx);
+                    let noise = value_noise(*seed, angle.cos() * 2.0, angle.sin() * 2.0, 1.0);
+                    let max_radius = 1.0 - roughness.clamp(0.0, 1.0) * noise;
+
+                    dx.hypot(dy) <= max_radius
+                }
+                BrushShape::Stamp(_) => unreachable!(),
+            }
+        });
+
+        texture[[size / 2, height / 2]] = true;
+        texture
+    }
+}
+
+/// how the brush follows the heading of the walker, textures face right
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BrushOrientation {
+    /// always stamped as drawn
+    #[default]
+    Fixed,
+    /// mirrored horizontally when heading left and vertically when heading up
+    Mirror,
+    /// rotated towards the heading, on every step the angle only closes
+    /// `1 - smoothing` of the gap to it, in [0, 1)
+    Rotate { smoothing: f32 },
+}
+
+impl BrushOrientation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushOrientation::Fixed => "fixed",
+            BrushOrientation::Mirror => "mirror",
+            BrushOrientation::Rotate { .. } => "rotate",
+        }
+    }
+
+    /// longest side a texture of `size` tiles can take
+    pub fn max_extent(&self, size: usize) -> usize {
+        match self {
+            BrushOrientation::Rotate { .. } => (size as f32 * SQRT_2).ceil() as usize,
+            _ => size,
+        }
+    }
+}
+
+/// angle of the direction in radians, y points down
+fn heading_angle(direction: Direction) -> f32 {
+    match direction {
+        Direction::Right => 0.0,
+        Direction::Down => PI / 2.0,
+        Direction::Left => PI,
+        Direction::Up => -PI / 2.0,
+    }
+}
+
+/// nearest neighbour rotation around the center, quarter turns are exact
+fn rotate(texture: &Array2<bool>, angle: f32) -> Array2<bool> {
+    let snap = |value: f32| match value.abs() < 1e-4 {
+        true => 0.0,
+        false => value,
+    };
+    let (cos, sin) = (snap(angle.cos()), snap(angle.sin()));
+
+    let (width, height) = texture.dim();
+    let (width, height) = (width as f32, height as f32);
+    let extent = |a: f32, b: f32| ((a * cos.abs() + b * sin.abs()) - 1e-4).ceil().max(1.0);
+    let (target_width, target_height) = (extent(width, height), extent(height, width));
+
+    Array2::from_shape_fn(
+        (target_width as usize, target_height as usize),
+        |(x, y)| {
+            let dx = x as f32 + 0.5 - target_width / 2.0;
+            let dy = y as f32 + 0.5 - target_height / 2.0;
+
+            // rotate back into the texture
+            let source_x = (dx * cos + dy * sin + width / 2.0).floor();
+            let source_y = (-dx * sin + dy * cos + height / 2.0).floor();
+
+            source_x >= 0.0
+                && source_y >= 0.0
+                && texture
+                    .get([source_x as usize, source_y as usize])
+                    .copied()
+                    .unwrap_or(false)
+        },
+    )
+}
+
+/// ring of a block around the carved part of a brush
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrushRim {
+    pub block: BlockType,
+    /// in tiles, measured from the ring inside of it
+    pub width: usize,
+}
+
+/// part of a brush's stamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushMaterial {
+    /// carved to empty, whatever was there
+    Core,
+    /// ring with the given index, only replaces solid tiles
+    Rim(usize),
+}
 
 #[derive(Clone)]
 pub struct Brush {
     texture: Array2<bool>,
     scaled_texture: Option<Array2<bool>>,
+    /// textures of shaped brushes are rasterized for every size instead of being scaled
+    shape: Option<BrushShape>,
+    /// rings around the texture, innermost first
+    rims: Vec<BrushRim>,
+    orientation: BrushOrientation,
+    /// smoothed angle of the walker's heading, `None` before the first step
+    heading: Option<f32>,
+    /// texture turned to the heading
+    oriented_texture: Option<Array2<bool>>,
 }
 
 impl Default for Brush {
@@ -17,39 +222,116 @@ impl Default for Brush {
 
 impl Brush {
     pub fn new() -> Self {
+        Self::from_shape(BrushShape::default())
+    }
+
+    pub fn from_texture(texture: Array2<bool>) -> Self {
         Self {
-            texture: Array2::from_elem((1, 1), true),
+            texture,
             scaled_texture: None,
+            shape: None,
+            rims: Vec::new(),
+            orientation: BrushOrientation::Fixed,
+            heading: None,
+            oriented_texture: None,
         }
     }
 
-    pub fn from_texture(texture: Array2<bool>) -> Self {
+    /// brush of size 1, scaling it rasterizes the shape at the new size
+    pub fn from_shape(shape: BrushShape) -> Self {
         Self {
-            texture,
+            texture: shape.rasterize(1),
             scaled_texture: None,
+            shape: Some(shape),
+            rims: Vec::new(),
+            orientation: BrushOrientation::Fixed,
+            heading: None,
+            oriented_texture: None,
         }
     }
 
-    pub fn circular(size: usize, circularity: f32) -> Self {
-        let circularity = circularity.clamp(0.0, 1.0);
-        let center = (size - 1) as f32 / 2.0;
+    pub fn shape(&self) -> Option<&BrushShape> {
+        self.shape.as_ref()
+    }
+
+    pub fn set_rims(&mut self, rims: Vec<BrushRim>) {
+        self.rims = rims;
+    }
 
-        let min_radius = center; // min radius is from center to nearest border
-        let max_radius = f32::sqrt(center * center + center * center); // max radius is from center to corner
+    pub fn rims(&self) -> &[BrushRim] {
+        &self.rims
+    }
 
-        let radius = circularity * min_radius + (1.0 - circularity) * max_radius;
+    /// tiles the rims reach beyond the texture on every side
+    pub fn rim_width(&self) -> usize {
+        self.rims.iter().map(|rim| rim.width).sum()
+    }
 
-        let mut texture = Array2::from_elem((size, size), false);
+    pub fn set_orientation(&mut self, orientation: BrushOrientation) {
+        self.orientation = orientation;
+        self.orient();
+    }
 
-        for ((x, y), value) in texture.indexed_iter_mut() {
-            let distance = f32::sqrt((x as f32 - center).powi(2) + (y as f32 - center).powi(2));
-            *value = distance <= radius;
-        }
+    pub fn orientation(&self) -> &BrushOrientation {
+        &self.orientation
+    }
+
+    /// smoothed heading in radians, 0 is right and y points down
+    pub fn heading(&self) -> Option<f32> {
+        self.heading
+    }
+
+    /// turns the brush towards the direction the walker moves in
+    pub fn follow(&mut self, direction: Direction) {
+        let target = heading_angle(direction);
 
-        Self { texture, scaled_texture: None }
+        self.heading = Some(match (self.heading, &self.orientation) {
+            (Some(heading), BrushOrientation::Rotate { smoothing }) => {
+                // shortest way around
+                let gap = (target - heading + PI).rem_euclid(2.0 * PI) - PI;
+
+                heading + gap * (1.0 - smoothing.clamp(0.0, 1.0))
+            }
+            _ => target,
+        });
+
+        self.orient();
     }
 
+    fn orient(&mut self) {
+        let texture = self.scaled_texture.as_ref().unwrap_or(&self.texture);
+
+        self.oriented_texture = match (&self.orientation, self.heading) {
+            (BrushOrientation::Fixed, _) | (_, None) => None,
+            (BrushOrientation::Mirror, Some(heading)) => {
+                let (width, height) = texture.dim();
+                let (mirror_x, mirror_y) = (heading.cos() < -0.5, heading.sin() < -0.5);
+
+                Some(Array2::from_shape_fn((width, height), |(x, y)| {
+                    let x = if mirror_x { width - 1 - x } else { x };
+                    let y = if mirror_y { height - 1 - y } else { y };
+
+                    texture[[x, y]]
+                }))
+            }
+            (BrushOrientation::Rotate { .. }, Some(heading)) => Some(rotate(texture, heading)),
+        };
+    }
+
+    pub fn circular(size: usize, circularity: f32) -> Self {
+        let mut brush = Self::from_shape(BrushShape::Circle { circularity });
+        brush.apply_scale(size as f32);
+        brush
+    }
+
+    /// shaped brushes become `factor` tiles wide, others are scaled with nearest neighbour sampling
     pub fn apply_scale(&mut self, factor: f32) {
+        if let Some(shape) = &self.shape {
+            self.scaled_texture = Some(shape.rasterize(factor.round() as usize));
+            self.orient();
+            return;
+        }
+
         let (old_width, old_height) = self.texture.dim();
         let width = (old_width as f32 * factor) as usize;
         let height = (old_height as f32 * factor) as usize;
@@ -65,31 +347,180 @@ impl Brush {
         }
 
         self.scaled_texture = Some(texture);
+        self.orient();
     }
 
     pub fn reset_scale(&mut self) {
         self.scaled_texture = None;
+        self.orient();
     }
 
-    pub fn apply<T: AnyTile>(&self, tiles: &mut Array2<T>, pos: Vector2, tile: T) {
-        let used_texture = if let Some(t) = &self.scaled_texture {
-            t
-        } else {
-            &self.texture
-        };
+    /// longer side of the scaled texture, before it's turned to the heading
+    pub fn size(&self) -> usize {
+        let (width, height) = self.scaled_texture.as_ref().unwrap_or(&self.texture).dim();
+
+        width.max(height)
+    }
+
+    /// texture as it's stamped, scaled and oriented
+    pub fn used_texture(&self) -> &Array2<bool> {
+        self.oriented_texture
+            .as_ref()
+            .or(self.scaled_texture.as_ref())
+            .unwrap_or(&self.texture)
+    }
+
+    /// texture surrounded by the rims, every ring covers the tiles within its width of the ones inside
+    pub fn materials(&self) -> Array2<Option<BrushMaterial>> {
+        let texture = self.used_texture();
+        let (width, height) = texture.dim();
+        let border = self.rim_width();
+
+        let mut materials = Array2::from_elem((width + 2 * border, height + 2 * border), None);
+
+        for ((x, y), &not_empty) in texture.indexed_iter() {
+            if not_empty {
+                materials[[x + border, y + border]] = Some(BrushMaterial::Core);
+            }
+        }
+
+        for (index, rim) in self.rims.iter().enumerate() {
+            let inside = materials.clone();
+            let (width, height) = inside.dim();
+
+            for ((x, y), material) in materials.indexed_iter_mut() {
+                if material.is_some() {
+                    continue;
+                }
+
+                let near = (x.saturating_sub(rim.width)..(x + rim.width + 1).min(width))
+                    .any(|nx| {
+                        (y.saturating_sub(rim.width)..(y + rim.width + 1).min(height))
+                            .any(|ny| inside[[nx, ny]].is_some())
+                    });
+
+                if near {
+                    *material = Some(BrushMaterial::Rim(index));
+                }
+            }
+        }
+
+        materials
+    }
 
-        let (width, height) = used_texture.dim();
+    /// top left corner of a texture of the given size centered at `pos`
+    fn origin(
+        (width, height): (usize, usize),
+        (tiles_width, tiles_height): (usize, usize),
+        pos: &Vector2,
+    ) -> Result<(isize, isize), OutOfBounds> {
         let (offx, offy) = (
             (width as f32 / 2.0) as usize,
             (height as f32 / 2.0) as usize,
         );
 
-        let top_left = pos - Vector2::from(vec![offx as f32, offy as f32]);
+        let (pos_x, pos_y) = (pos[[0]].floor(), pos[[1]].floor());
+
+        if !(0.0..tiles_width as f32).contains(&pos_x)
+            || !(0.0..tiles_height as f32).contains(&pos_y)
+        {
+            return Err(OutOfBounds {
+                x: pos[[0]],
+                y: pos[[1]],
+            });
+        }
+
+        // signed, so that the brush can reach over the top left border
+        Ok((pos_x as isize - offx as isize, pos_y as isize - offy as isize))
+    }
+
+    /// parts of the brush outside of the tiles are clipped, fails if `pos` itself is outside
+    pub fn apply<T: AnyTile>(
+        &self,
+        tiles: &mut Array2<T>,
+        pos: Vector2,
+        tile: T,
+    ) -> Result<(), OutOfBounds> {
+        let used_texture = self.used_texture();
+        let (left, top) = Self::origin(used_texture.dim(), tiles.dim(), &pos)?;
+
         for ((x, y), &not_empty) in used_texture.indexed_iter() {
-            let real_pos = top_left.clone() + Vector2::from(vec![x as f32, y as f32]);
-            if not_empty {
-                tiles[as_index(real_pos.view())] = tile;
+            let (real_x, real_y) = (left + x as isize, top + y as isize);
+
+            if !not_empty || real_x < 0 || real_y < 0 {
+                continue;
+            }
+
+            if let Some(target) = tiles.get_mut([real_x as usize, real_y as usize]) {
+                *target = tile;
             }
         }
+
+        Ok(())
+    }
+
+    /// replaces the covered tiles with id `from` by `tile`, like [`Brush::apply`]
+    pub fn replace<T: AnyTile>(
+        &self,
+        tiles: &mut Array2<T>,
+        pos: Vector2,
+        from: u8,
+        tile: T,
+    ) -> Result<(), OutOfBounds> {
+        let used_texture = self.used_texture();
+        let (left, top) = Self::origin(used_texture.dim(), tiles.dim(), &pos)?;
+
+        for ((x, y), &not_empty) in used_texture.indexed_iter() {
+            let (real_x, real_y) = (left + x as isize, top + y as isize);
+
+            if !not_empty || real_x < 0 || real_y < 0 {
+                continue;
+            }
+
+            if let Some(target) = tiles.get_mut([real_x as usize, real_y as usize]) {
+                if target.id() == from {
+                    *target = tile;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// carves the texture and draws the rims around it, like [`Brush::apply`]
+    pub fn apply_materials(
+        &self,
+        tiles: &mut Array2<GameTile>,
+        pos: Vector2,
+        blocks: &BlockMapping,
+    ) -> Result<(), OutOfBounds> {
+        if self.rims.is_empty() {
+            return self.apply(tiles, pos, blocks.tile(BlockType::Empty));
+        }
+
+        let materials = self.materials();
+        let (left, top) = Self::origin(materials.dim(), tiles.dim(), &pos)?;
+
+        for ((x, y), material) in materials.indexed_iter() {
+            let (real_x, real_y) = (left + x as isize, top + y as isize);
+
+            if real_x < 0 || real_y < 0 {
+                continue;
+            }
+
+            let Some(target) = tiles.get_mut([real_x as usize, real_y as usize]) else {
+                continue;
+            };
+
+            match material {
+                Some(BrushMaterial::Core) => *target = blocks.tile(BlockType::Empty),
+                Some(BrushMaterial::Rim(index)) if blocks.is_solid(target.id) => {
+                    *target = blocks.tile(self.rims[*index].block);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
     }
 }
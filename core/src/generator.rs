@@ -1,95 +1,820 @@
-use twmap::{GameTile, TileFlags, TwMap};
+use std::fmt::Display;
+
+use twmap::{GameLayer, TwMap};
 
 use crate::{
-    brush::Brush,
-    map::Map,
-    position::{from_raw, shift_by_direction},
+    analysis::{passable_bounds, MapReport, WayEnds},
+    block::{BlockMapping, BlockType},
+    branch::{BranchConfig, BranchEnd},
+    brush::{Brush, BrushOrientation, BrushRim, BrushShape, OutOfBounds},
+    map::{FreezeConfig, Map, RoomConfig},
+    mutations::StepInfo,
+    position::{as_index, euclidian, from_raw, shift_by_direction, Vector2},
+    random::{sub_seed, Random, Seed},
+    trace::{GenerationTrace, TraceStep, WalkerRole, WalkerTrace},
     walker::Walker,
 };
 
+/// drives mutations during generation, any closure taking the arguments of `step` is one
+pub trait StepHandler {
+    /// called once before the first step of every generation
+    fn begin(&mut self, _seed: Seed) {}
+
+    fn step(&mut self, walker: &mut Walker, map: &mut Map, brush: &mut Brush, info: StepInfo);
+
+    /// called once after the last step of every generation
+    fn end(&mut self, _walker: &mut Walker, _map: &mut Map, _brush: &mut Brush) {}
+
+    /// names of the mutations that ran during the last step, recorded in the trace
+    fn active_mutations(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl<F: FnMut(&mut Walker, &mut Map, &mut Brush, StepInfo)> StepHandler for F {
+    fn step(&mut self, walker: &mut Walker, map: &mut Map, brush: &mut Brush, info: StepInfo) {
+        self(walker, map, brush, info)
+    }
+}
+
+/// handlers are `Send`, so generation can run on another thread
+pub type StepCallback = Box<dyn StepHandler + Send>;
+
+/// creates the step handler of every branch
+pub type BranchHandlerFactory = Box<dyn FnMut() -> StepCallback + Send>;
+
+pub const DEFAULT_MAX_STEPS: usize = 100_000;
+/// free space for the walker beyond the waypoints, in tiles
+pub const DEFAULT_PADDING: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerationError {
+    InvalidConfig(String),
+    /// brush left the map at the given position
+    BrushOutOfBounds {
+        x: f32,
+        y: f32,
+    },
+    /// walker didn't reach the last waypoint within the given amount of steps
+    StepLimitExceeded(usize),
+    FinishUnreachable,
+    /// stepped without a generation in progress
+    NotStarted,
+}
+
+impl Display for GenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationError::InvalidConfig(reason) => write!(f, "invalid config: {}", reason),
+            GenerationError::BrushOutOfBounds { x, y } => {
+                write!(f, "brush is out of map bounds at ({}, {})", x, y)
+            }
+            GenerationError::StepLimitExceeded(steps) => {
+                write!(f, "walker didn't finish within {} steps", steps)
+            }
+            GenerationError::FinishUnreachable => write!(f, "finish is not reachable from spawn"),
+            GenerationError::NotStarted => write!(f, "no generation in progress"),
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
 pub struct Generator {
-    walker: Walker,
-    brush: Brush,
-    before_step: Option<Box<dyn FnMut(&mut Walker, &mut Map, &mut Brush)>>,
+    scale_factor: f32,
+    brush_shape: BrushShape,
+    brush_rims: Vec<BrushRim>,
+    brush_orientation: BrushOrientation,
+    before_step: Option<StepCallback>,
+    branches: Option<BranchConfig>,
+    branch_handler: Option<BranchHandlerFactory>,
+    session: Option<GenerationSession>,
+    freeze: Option<FreezeConfig>,
+    rooms: Option<RoomConfig>,
+    max_steps: usize,
+    max_brush_size: usize,
+    padding: usize,
+    blocks: BlockMapping,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Generator {
     pub fn new() -> Self {
         Self {
-            walker: Walker::new(1.0),
-            brush: Brush::new(),
+            scale_factor: 1.0,
+            brush_shape: BrushShape::default(),
+            brush_rims: Vec::new(),
+            brush_orientation: BrushOrientation::Fixed,
             before_step: None,
+            branches: None,
+            branch_handler: None,
+            session: None,
+            freeze: None,
+            rooms: None,
+            max_steps: DEFAULT_MAX_STEPS,
+            max_brush_size: 1,
+            padding: DEFAULT_PADDING,
+            blocks: BlockMapping::default(),
         }
     }
 
     pub fn set_scale_factor(&mut self, scale_factor: f32) {
-        self.walker.set_scale_factor(scale_factor);
+        self.scale_factor = scale_factor;
     }
 
     pub fn get_scale_factor(&self) -> f32 {
-        self.walker.get_scale_factor()
+        self.scale_factor
     }
 
-    pub fn on_step(&mut self, func: impl FnMut(&mut Walker, &mut Map, &mut Brush) + 'static) {
-        self.before_step = Some(Box::new(func));
+    /// freeze border placed after generation, `None` to leave it out
+    pub fn set_freeze(&mut self, freeze: Option<FreezeConfig>) {
+        self.freeze = freeze;
     }
 
-    pub fn generate(&mut self, waypoints: Vec<(f32, f32)>) -> TwMap {
-        // prepare canvas
-        let mut map = Map::new();
+    /// spawn and finish rooms carved after generation, `None` to leave them out
+    pub fn set_rooms(&mut self, rooms: Option<RoomConfig>) {
+        self.rooms = rooms;
+    }
 
-        let scale_factor = self.walker.get_scale_factor();
+    pub fn on_step(&mut self, handler: impl StepHandler + Send + 'static) {
+        self.before_step = Some(Box::new(handler));
+    }
 
-        // 1. calculate bounds and enlarge them to let walker freely... walk
-        let mut freaky_waypoints = waypoints.clone();
+    /// branches spawned from the main path, `None` to leave them out
+    pub fn set_branches(&mut self, branches: Option<BranchConfig>) {
+        self.branches = branches;
+    }
+
+    /// every branch gets its own handler, branches without one walk straight to their target
+    pub fn on_branch_step(&mut self, factory: impl FnMut() -> StepCallback + Send + 'static) {
+        self.branch_handler = Some(Box::new(factory));
+    }
+
+    /// amount of walker steps after which generation is aborted
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps;
+    }
+
+    /// shape of the brush the walker carves with, rasterized at every size it's scaled to
+    pub fn set_brush_shape(&mut self, shape: BrushShape) {
+        self.brush_shape = shape;
+    }
+
+    /// rings of blocks drawn around the carved corridor where the map is still solid
+    pub fn set_brush_rims(&mut self, rims: Vec<BrushRim>) {
+        self.brush_rims = rims;
+    }
+
+    /// whether the brush turns with the walker's heading
+    pub fn set_brush_orientation(&mut self, orientation: BrushOrientation) {
+        self.brush_orientation = orientation;
+    }
+
+    fn new_brush(&self) -> Brush {
+        let mut brush = Brush::from_shape(self.brush_shape.clone());
+        brush.set_rims(self.brush_rims.clone());
+        brush.set_orientation(self.brush_orientation.clone());
+
+        brush
+    }
+
+    /// largest brush size the step handler can reach, widens the map's margin
+    pub fn set_max_brush_size(&mut self, max_brush_size: usize) {
+        self.max_brush_size = max_brush_size;
+    }
+
+    /// free space for the walker beyond the waypoints, in tiles
+    pub fn set_padding(&mut self, padding: usize) {
+        self.padding = padding;
+    }
+
+    /// game tile ids of the placed blocks
+    pub fn set_blocks(&mut self, blocks: BlockMapping) {
+        self.blocks = blocks;
+    }
 
-        freaky_waypoints.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    /// space around the waypoints, fits the biggest brush with its rims and rooms plus padding
+    pub fn margin(&self) -> usize {
+        let rim_width: usize = self.brush_rims.iter().map(|rim| rim.width).sum();
+        let brush_extent = self.brush_orientation.max_extent(self.max_brush_size);
+        let brush_reach = brush_extent / 2 + 1 + rim_width;
+        let room_reach = self
+            .rooms
+            .as_ref()
+            .map_or(0, |rooms| rooms.width.max(rooms.height) / 2 + 1);
 
-        let normal_width = freaky_waypoints.last().unwrap().0 - freaky_waypoints.first().unwrap().0;
+        brush_reach.max(room_reach) + self.padding
+    }
 
-        freaky_waypoints.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    /// same seed, waypoints and step handler always give the same map,
+    /// it comes with a report of its metrics and the trace of every walker
+    pub fn generate(
+        &mut self,
+        waypoints: Vec<(f32, f32)>,
+        seed: Seed,
+    ) -> Result<(TwMap, MapReport, GenerationTrace), GenerationError> {
+        self.begin(waypoints, seed)?;
+        self.finish()
+    }
+
+    /// generation in progress, between [`Generator::begin`] and [`Generator::finish`]
+    pub fn session(&self) -> Option<&GenerationSession> {
+        self.session.as_ref()
+    }
 
-        let normal_height =
-            freaky_waypoints.last().unwrap().1 - freaky_waypoints.first().unwrap().1;
+    /// starts a generation walked by [`Generator::step`], replaces the one in progress
+    pub fn begin(&mut self, waypoints: Vec<(f32, f32)>, seed: Seed) -> Result<(), GenerationError> {
+        self.session = None;
+        self.validate(&waypoints)?;
+
+        // prepare canvas
+        let mut map = Map::new();
+        map.set_blocks(self.blocks.clone());
+
+        let scale_factor = self.scale_factor;
+
+        // 1. calculate bounds and enlarge them to let walker freely... walk
+        let margin = self.margin();
+
+        let normal_width = waypoints.iter().map(|w| w.0).fold(0.0, f32::max);
+        let normal_height = waypoints.iter().map(|w| w.1).fold(0.0, f32::max);
 
         let approx_width = normal_width * scale_factor;
         let approx_height = normal_height * scale_factor;
 
         // 2. create map with enlarged bounds
-        map.reshape(approx_width as usize + 400, approx_height as usize + 400);
-        map.fill_game(GameTile::new(1, TileFlags::empty()));
+        map.reshape(
+            approx_width.ceil() as usize + 2 * margin,
+            approx_height.ceil() as usize + 2 * margin,
+        );
+        map.fill_game(self.blocks.tile(BlockType::Hookable));
 
         // 3. setup initial position
-        let mut current_pos = from_raw(waypoints[0], scale_factor);
-        current_pos[[0]] += 200.0;
-        current_pos[[1]] += 200.0;
+        let offset = Vector2::from(vec![margin as f32, margin as f32]);
+        let position = from_raw(waypoints[0], scale_factor) + &offset;
+        let finish_pos = from_raw(*waypoints.last().unwrap(), scale_factor) + &offset;
 
-        self.walker.set_waypoints(waypoints);
+        let mut walker = Walker::new(scale_factor);
+        walker
+            .set_waypoints(waypoints.clone())
+            .set_offset(margin as f32);
+
+        let mut session = GenerationSession {
+            seed,
+            map,
+            walker,
+            brush: self.new_brush(),
+            spawn: as_index(position.view()),
+            finish: as_index(finish_pos.view()),
+            position,
+            walking: true,
+            main: WalkerTrace::new(WalkerRole::Main),
+            trace: GenerationTrace {
+                seed,
+                waypoints,
+                walkers: Vec::new(),
+            },
+            branches: Vec::new(),
+            branch_prng: Random::new(sub_seed(seed, "branches")),
+            spawned: 0,
+        };
 
         if let Some(ref mut on_step) = &mut self.before_step {
-            on_step(&mut self.walker, &mut map, &mut self.brush);
+            let info = StepInfo::new(&session.walker, session.position.view(), 0);
+
+            on_step.begin(seed);
+            on_step.step(
+                &mut session.walker,
+                &mut session.map,
+                &mut session.brush,
+                info,
+            );
+        }
+
+        self.session = Some(session);
+
+        Ok(())
+    }
+
+    /// moves the walkers by one step, `false` once all of them arrived,
+    /// on errors the generation is dropped
+    pub fn step(&mut self) -> Result<bool, GenerationError> {
+        let mut session = self.session.take().ok_or(GenerationError::NotStarted)?;
+        let walking = self.advance(&mut session)?;
+
+        self.session = Some(session);
+
+        Ok(walking)
+    }
+
+    /// moves the walkers by up to `n` steps, `false` once all of them arrived
+    pub fn step_n(&mut self, n: usize) -> Result<bool, GenerationError> {
+        for _ in 0..n {
+            if !self.step()? {
+                return Ok(false);
+            }
         }
 
-        // loop thru generation
-        while self.walker.step(current_pos.view()) != 0 {
-            if let Some(ref mut on_step) = &mut self.before_step {
-                on_step(&mut self.walker, &mut map, &mut self.brush);
+        self.session
+            .as_ref()
+            .map(GenerationSession::is_walking)
+            .ok_or(GenerationError::NotStarted)
+    }
+
+    /// walks the rest of the way and finishes the map, same as [`Generator::generate`]
+    pub fn finish(&mut self) -> Result<(TwMap, MapReport, GenerationTrace), GenerationError> {
+        let mut session = self.session.take().ok_or(GenerationError::NotStarted)?;
+
+        while self.advance(&mut session)? {}
+
+        let (map, trace, ends) = self.complete(session)?;
+        let bounds = |map: &TwMap| {
+            let game = map.find_physics_layer::<GameLayer>().unwrap();
+
+            passable_bounds(game.tiles.unwrap_ref(), &self.blocks)
+        };
+        let before = bounds(map.raw_map());
+
+        // shrink map, it only loses solid borders, so the corridors move by
+        // as much as their bounds do
+        let map = map.finalize();
+        let ends = match (before, bounds(&map)) {
+            (Some(before), Some(after)) => {
+                Some(ends.map(|(x, y)| (x - before.x + after.x, y - before.y + after.y)))
             }
+            _ => None,
+        };
+        let report = MapReport::new(&map, &self.blocks, ends);
 
-            shift_by_direction(&mut current_pos, 1.0, self.walker.current_state().direction);
+        Ok((map, report, trace))
+    }
 
-            self.brush.apply(
-                map.game_layer().tiles.unwrap_mut(),
-                current_pos.clone(),
-                GameTile::new(0, TileFlags::empty()),
+    /// drops the generation in progress
+    pub fn cancel(&mut self) {
+        self.session = None;
+    }
+
+    fn validate(&self, waypoints: &[(f32, f32)]) -> Result<(), GenerationError> {
+        let scale_factor = self.scale_factor;
+
+        if !scale_factor.is_finite() || scale_factor <= 0.0 {
+            return Err(GenerationError::InvalidConfig(format!(
+                "scale factor must be positive, got {}",
+                scale_factor
+            )));
+        }
+
+        if waypoints.len() < 2 {
+            return Err(GenerationError::InvalidConfig(
+                "at least two waypoints are required".to_string(),
+            ));
+        }
+
+        if let Some(&(x, y)) = waypoints
+            .iter()
+            .find(|(x, y)| !x.is_finite() || !y.is_finite() || *x < 0.0 || *y < 0.0)
+        {
+            return Err(GenerationError::InvalidConfig(format!(
+                "waypoint ({}, {}) must be finite and not negative",
+                x, y
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// one step of the main walker and the branches, once it arrived only the
+    /// branches walk on until they end
+    fn advance(&mut self, session: &mut GenerationSession) -> Result<bool, GenerationError> {
+        if session.walking {
+            if session.walker.step(session.position.view()) != 0 {
+                self.step_main(session)?;
+
+                return Ok(true);
+            }
+
+            session.walking = false;
+        }
+
+        // branches still walking end on their own
+        if session.branches.is_empty() {
+            return Ok(false);
+        }
+
+        self.step_branches(session);
+
+        Ok(session.is_walking())
+    }
+
+    fn step_main(&mut self, session: &mut GenerationSession) -> Result<(), GenerationError> {
+        if session.main.steps.len() == self.max_steps {
+            return Err(GenerationError::StepLimitExceeded(self.max_steps));
+        }
+
+        if let Some(ref mut on_step) = &mut self.before_step {
+            let info = StepInfo::new(
+                &session.walker,
+                session.position.view(),
+                session.main.steps.len(),
             );
+
+            on_step.step(
+                &mut session.walker,
+                &mut session.map,
+                &mut session.brush,
+                info,
+            );
+        }
+
+        let state = *session.walker.current_state();
+
+        shift_by_direction(&mut session.position, 1.0, state.direction);
+
+        session.main.steps.push(TraceStep {
+            position: as_index(session.position.view()),
+            direction: state.direction,
+            waypoint: state.waypoint,
+            brush_size: session.brush.size(),
+            mutations: self
+                .before_step
+                .as_ref()
+                .map_or_else(Vec::new, |on_step| on_step.active_mutations()),
+        });
+
+        session.brush.follow(state.direction);
+        session
+            .brush
+            .apply_materials(
+                session.map.game_layer().tiles.unwrap_mut(),
+                session.position.clone(),
+                &self.blocks,
+            )
+            .map_err(|OutOfBounds { x, y }| GenerationError::BrushOutOfBounds { x, y })?;
+
+        let Some(config) = self.branches else {
+            return Ok(());
+        };
+
+        if session.branches.len() < config.max_active
+            && session.branch_prng.gen_bool(config.probability)
+        {
+            let branch = self.spawn_branch(&config, session);
+
+            session.branches.push(branch);
+            session.spawned += 1;
+        }
+
+        self.step_branches(session);
+
+        Ok(())
+    }
+
+    /// places freeze and rooms on the walked map and checks it can be finished
+    fn complete(
+        &mut self,
+        mut session: GenerationSession,
+    ) -> Result<(Map, GenerationTrace, WayEnds), GenerationError> {
+        let map = &mut session.map;
+
+        if let Some(ref mut on_step) = &mut self.before_step {
+            on_step.end(&mut session.walker, map, &mut session.brush);
+        }
+
+        let path = &session.main.steps;
+
+        if let Some(freeze) = &self.freeze {
+            map.place_freeze(freeze, sub_seed(session.seed, "freeze"));
+        }
+
+        // without rooms the walk itself has to lead from spawn to finish
+        let (from, to) = match &self.rooms {
+            Some(rooms) => {
+                let (spawn, finish) = (
+                    (session.spawn[0], session.spawn[1]),
+                    (session.finish[0], session.finish[1]),
+                );
+
+                place_rooms(map, rooms, spawn, finish, path);
+
+                (spawn, finish)
+            }
+            None => match (path.first(), path.last()) {
+                (Some(first), Some(last)) => (
+                    (first.position[0], first.position[1]),
+                    (last.position[0], last.position[1]),
+                ),
+                _ => return Err(GenerationError::FinishUnreachable),
+            },
+        };
+
+        if !map.is_reachable(from, to) {
+            return Err(GenerationError::FinishUnreachable);
+        }
+
+        session.trace.walkers.insert(0, session.main);
+
+        Ok((session.map, session.trace, [from, to]))
+    }
+
+    /// leaves the main path at the current position sideways
+    fn spawn_branch(&mut self, config: &BranchConfig, session: &mut GenerationSession) -> Branch {
+        let prng = &mut session.branch_prng;
+        let seed = sub_seed(session.seed, &format!("branch/{}", session.spawned));
+
+        let end = match prng.gen_bool(config.rejoin_chance) {
+            true => BranchEnd::Rejoin,
+            false => BranchEnd::DeadEnd,
+        };
+
+        let heading = session.walker.current_state().direction;
+        let direction = match prng.gen_bool(0.5) {
+            true => heading.prev(),
+            false => heading.next(),
+        };
+
+        let scale_factor = self.scale_factor;
+        let margin = self.margin() as f32;
+        let waypoints = session.walker.get_waypoints();
+
+        // rejoining branches head for the waypoint after the one the main walker
+        // heads for, dead ends for a point their lifetime away from the main path
+        let target = match end {
+            BranchEnd::Rejoin => {
+                let waypoint = session.walker.preferred_state().waypoint + 1;
+
+                waypoints[waypoint.min(waypoints.len() - 1)]
+            }
+            BranchEnd::DeadEnd => {
+                let mut target = session.position.clone();
+                shift_by_direction(&mut target, config.lifetime as f32, direction);
+
+                let normal = |value: f32, size: usize| {
+                    ((value - margin) / scale_factor)
+                        .clamp(0.0, (size as f32 - 2.0 * margin).max(0.0) / scale_factor)
+                };
+
+                (
+                    normal(target[0], session.map.width()),
+                    normal(target[1], session.map.height()),
+                )
+            }
+        };
+
+        let mut walker = Walker::new(scale_factor);
+        walker
+            .set_waypoints(vec![target])
+            .set_offset(margin)
+            .set_next_direction(direction)
+            .set_next_waypoint(0);
+
+        let mut brush = self.new_brush();
+        brush.apply_scale(config.brush_size as f32);
+
+        let mut handler = self.branch_handler.as_mut().map(|factory| factory());
+
+        if let Some(handler) = &mut handler {
+            handler.begin(seed);
+        }
+
+        Branch {
+            walker,
+            brush,
+            handler,
+            start: session.position.clone(),
+            position: session.position.clone(),
+            end,
+            lifetime: config.lifetime,
+            origin: session.main.steps.len(),
+            steps: Vec::new(),
         }
+    }
+
+    /// moves every branch by one step, ended ones are removed and their traces kept
+    fn step_branches(&mut self, session: &mut GenerationSession) {
+        let branches = &mut session.branches;
+        let map = &mut session.map;
+        let mut index = 0;
+
+        while index < branches.len() {
+            let Some(end) = branches[index].step(map, &self.blocks) else {
+                index += 1;
+                continue;
+            };
+
+            let mut branch = branches.remove(index);
+
+            if end == BranchEnd::DeadEnd && branch.left_main_path() {
+                let freeze = self.blocks.tile(BlockType::Freeze);
+                let empty = self.blocks.tile_id(BlockType::Empty);
+                let tiles = map.game_layer().tiles.unwrap_mut();
+
+                // the position is on the map, the brush was applied there
+                let _ = branch
+                    .brush
+                    .replace(tiles, branch.position.clone(), empty, freeze);
+            }
 
-        // reset our tools
-        self.walker.reset();
-        self.brush = Brush::new();
+            if let Some(handler) = &mut branch.handler {
+                handler.end(&mut branch.walker, map, &mut branch.brush);
+            }
 
-        // shrink map
-        map.finalize()
+            session.trace.walkers.push(WalkerTrace {
+                role: WalkerRole::Branch {
+                    origin: branch.origin,
+                    end,
+                },
+                steps: branch.steps,
+            });
+        }
     }
 }
+
+/// generation in progress, owns the map and the tools of the main walker
+pub struct GenerationSession {
+    seed: Seed,
+    map: Map,
+    walker: Walker,
+    brush: Brush,
+    /// position of the main walker
+    position: Vector2,
+    spawn: [usize; 2],
+    finish: [usize; 2],
+    /// whether the main walker is still on its way to the last waypoint
+    walking: bool,
+    main: WalkerTrace,
+    /// ended branches
+    trace: GenerationTrace,
+    branches: Vec<Branch>,
+    branch_prng: Random,
+    /// branches spawned so far
+    spawned: usize,
+}
+
+impl GenerationSession {
+    pub fn seed(&self) -> Seed {
+        self.seed
+    }
+
+    /// map as carved so far, without freeze and rooms
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub fn walker(&self) -> &Walker {
+        &self.walker
+    }
+
+    pub fn brush(&self) -> &Brush {
+        &self.brush
+    }
+
+    /// position of the main walker
+    pub fn position(&self) -> [usize; 2] {
+        as_index(self.position.view())
+    }
+
+    /// steps of the main walker so far
+    pub fn steps(&self) -> usize {
+        self.main.steps.len()
+    }
+
+    /// walk of the main walker so far
+    pub fn main(&self) -> &WalkerTrace {
+        &self.main
+    }
+
+    /// branches that ended so far
+    pub fn ended_branches(&self) -> impl Iterator<Item = &WalkerTrace> {
+        self.trace.walkers.iter()
+    }
+
+    pub fn active_branches(&self) -> usize {
+        self.branches.len()
+    }
+
+    /// whether the main walker reached the last waypoint, only branches walk on then
+    pub fn has_arrived(&self) -> bool {
+        !self.walking
+    }
+
+    /// whether the main walker or any branch is still walking
+    pub fn is_walking(&self) -> bool {
+        self.walking || !self.branches.is_empty()
+    }
+}
+
+/// secondary walker carving next to the main one
+struct Branch {
+    walker: Walker,
+    brush: Brush,
+    handler: Option<StepCallback>,
+    /// position on the main path it was spawned at
+    start: Vector2,
+    position: Vector2,
+    /// the way it's meant to end
+    end: BranchEnd,
+    /// steps left
+    lifetime: usize,
+    /// step of the main walker it was spawned at
+    origin: usize,
+    steps: Vec<TraceStep>,
+}
+
+impl Branch {
+    /// whether its brush no longer reaches back to where it started, a dead
+    /// end filled any closer would freeze the main path
+    fn left_main_path(&self) -> bool {
+        euclidian(self.start.view(), self.position.view()) > self.brush.size() as f32
+    }
+
+    /// `None` while it's still walking, otherwise how it ended
+    fn step(&mut self, map: &mut Map, blocks: &BlockMapping) -> Option<BranchEnd> {
+        if self.lifetime == 0 {
+            return Some(BranchEnd::DeadEnd);
+        }
+
+        if self.walker.step(self.position.view()) == 0 {
+            return Some(self.end);
+        }
+
+        // heads for its target unless the handler picks another way
+        let preferred = *self.walker.preferred_state();
+        self.walker
+            .set_next_direction(preferred.direction)
+            .set_next_waypoint(preferred.waypoint);
+
+        if let Some(handler) = &mut self.handler {
+            let info = StepInfo::new(&self.walker, self.position.view(), self.steps.len());
+
+            handler.step(&mut self.walker, map, &mut self.brush, info);
+        }
+
+        let state = *self.walker.current_state();
+        let mut position = self.position.clone();
+
+        shift_by_direction(&mut position, 1.0, state.direction);
+
+        self.brush.follow(state.direction);
+
+        // branches stop at the border of the map
+        if self
+            .brush
+            .apply_materials(
+                map.game_layer().tiles.unwrap_mut(),
+                position.clone(),
+                blocks,
+            )
+            .is_err()
+        {
+            return Some(BranchEnd::DeadEnd);
+        }
+
+        self.position = position;
+        self.steps.push(TraceStep {
+            position: as_index(self.position.view()),
+            direction: state.direction,
+            waypoint: state.waypoint,
+            brush_size: self.brush.size(),
+            mutations: self
+                .handler
+                .as_ref()
+                .map_or_else(Vec::new, |handler| handler.active_mutations()),
+        });
+        self.lifetime -= 1;
+
+        None
+    }
+}
+
+/// rooms go after freeze, so they stay free of it
+fn place_rooms(
+    map: &mut Map,
+    rooms: &RoomConfig,
+    spawn: (usize, usize),
+    finish: (usize, usize),
+    path: &[TraceStep],
+) {
+    map.carve_room(spawn, rooms);
+    map.carve_room(finish, rooms);
+
+    let outside =
+        |center, step: &&TraceStep| !rooms.contains(center, (step.position[0], step.position[1]));
+
+    // start line where the path leaves the spawn room, finish line where it enters the finish room
+    if let Some(step) = path.iter().find(|step| outside(spawn, step)) {
+        let [x, y] = step.position;
+        map.draw_line((x, y), step.direction, BlockType::Start);
+    }
+
+    if let Some(step) = path.iter().rev().find(|step| outside(finish, step)) {
+        let [x, y] = step.position;
+        map.draw_line((x, y), step.direction, BlockType::Finish);
+    }
+
+    map.place_spawns(spawn, rooms.spawn_count);
+}
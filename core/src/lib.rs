@@ -1,7 +1,14 @@
+pub mod analysis;
+pub mod automapper;
+pub mod block;
+pub mod branch;
 pub mod brush;
 pub mod generator;
 pub mod map;
 pub mod mutations;
+pub mod pipeline;
 pub mod position;
 pub mod random;
+pub mod stamp;
+pub mod trace;
 pub mod walker;
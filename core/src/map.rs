@@ -1,19 +1,98 @@
-use crate::position::{as_index, VectorView2};
+use std::collections::VecDeque;
+
+use crate::{
+    block::{BlockMapping, BlockType},
+    position::{as_index, Direction, VectorView2},
+    random::{sub_seed, value_noise, Random, Seed},
+};
 use ndarray::Array2;
 use twmap::{
     AnyTile, CompressedData, GameLayer, GameTile, Group, Layer, Speedup, Switch, Tele, TileFlags,
     Tune, TwMap, Version,
 };
 
-// TileTag::Empty | TileTag::EmptyReserved => 0,
-// TileTag::Hookable | TileTag::Platform => 1,
-// TileTag::Freeze => 9,
-// TileTag::Spawn => 192,
-// TileTag::Start => 33,
-// TileTag::Finish => 34,
+/// freeze border placed around walls after generation
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct FreezeConfig {
+    /// tiles of freeze between a wall and the free corridor
+    pub thickness: usize,
+    /// 0 to 1, chance to leave out a freeze tile that doesn't touch a wall
+    pub thinning: f32,
+    /// max tiles the noise adds to or removes from the thickness
+    pub noise_amplitude: usize,
+    /// size of noise features in tiles
+    pub noise_scale: f32,
+    /// corridors keep at least that many tiles free of freeze
+    pub min_corridor: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoomShape {
+    #[default]
+    Rect,
+    Ellipse,
+}
+
+/// spawn room at the first waypoint and finish room at the last one
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RoomConfig {
+    pub width: usize,
+    pub height: usize,
+    pub shape: RoomShape,
+    /// spawn tiles placed on the floor of the spawn room
+    pub spawn_count: usize,
+}
+
+impl Default for RoomConfig {
+    fn default() -> Self {
+        Self {
+            width: 10,
+            height: 8,
+            shape: RoomShape::Rect,
+            spawn_count: 4,
+        }
+    }
+}
+
+impl RoomConfig {
+    pub fn contains(&self, center: (usize, usize), pos: (usize, usize)) -> bool {
+        let dx = pos.0 as f32 - center.0 as f32;
+        let dy = pos.1 as f32 - center.1 as f32;
+        let (rx, ry) = (self.width as f32 / 2.0, self.height as f32 / 2.0);
+
+        match self.shape {
+            RoomShape::Rect => dx.abs() <= rx && dy.abs() <= ry,
+            RoomShape::Ellipse => (dx / rx).powi(2) + (dy / ry).powi(2) <= 1.0,
+        }
+    }
+}
+
+impl Default for FreezeConfig {
+    fn default() -> Self {
+        Self {
+            thickness: 1,
+            thinning: 0.0,
+            noise_amplitude: 0,
+            noise_scale: 8.0,
+            min_corridor: 3,
+        }
+    }
+}
 
 pub struct Map {
     raw: TwMap,
+    blocks: BlockMapping,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Map {
@@ -32,25 +111,50 @@ impl Map {
             )),
         }));
 
-        Self { raw: map }
+        Self {
+            raw: map,
+            blocks: BlockMapping::default(),
+        }
+    }
+
+    /// game tile ids of the blocks placed by the map's methods
+    pub fn blocks(&self) -> &BlockMapping {
+        &self.blocks
     }
 
+    pub fn set_blocks(&mut self, blocks: BlockMapping) {
+        self.blocks = blocks;
+    }
+
+    /// tiles along x, tiles are indexed `[x, y]` during generation
     pub fn width(&self) -> usize {
         let game: &GameLayer = self.raw.find_physics_layer::<GameLayer>().unwrap();
 
-        game.tiles.shape().w
+        game.tiles.unwrap_ref().dim().0
     }
 
+    /// tiles along y
     pub fn height(&self) -> usize {
         let game: &GameLayer = self.raw.find_physics_layer::<GameLayer>().unwrap();
 
-        game.tiles.shape().h
+        game.tiles.unwrap_ref().dim().1
     }
 
     pub fn game_layer(&mut self) -> &mut GameLayer {
         self.raw.find_physics_layer_mut().unwrap()
     }
 
+    /// `None` outside of the map
+    pub fn game_tile(&self, pos: (usize, usize)) -> Option<GameTile> {
+        let game: &GameLayer = self.raw.find_physics_layer::<GameLayer>().unwrap();
+
+        game.tiles.unwrap_ref().get([pos.0, pos.1]).copied()
+    }
+
+    pub fn raw_map(&self) -> &TwMap {
+        &self.raw
+    }
+
     pub fn raw_map_mut(&mut self) -> &mut TwMap {
         &mut self.raw
     }
@@ -184,3 +288,191 @@ impl Map {
         });
     }
 }
+
+impl Map {
+    /// places freeze around walls, `seed` drives thinning and noise
+    pub fn place_freeze(&mut self, config: &FreezeConfig, seed: Seed) {
+        let [empty, hookable] = [BlockType::Empty, BlockType::Hookable].map(|b| self.blocks.tile_id(b));
+        let freeze = self.blocks.tile(BlockType::Freeze);
+        let tiles = self.game_layer().tiles.unwrap_mut();
+        let (width, height) = tiles.dim();
+
+        // chebyshev distance of every empty tile to the nearest wall
+        let mut distance = Array2::from_elem((width, height), usize::MAX);
+        let mut queue = VecDeque::new();
+
+        for ((x, y), tile) in tiles.indexed_iter() {
+            if tile.id == hookable {
+                distance[[x, y]] = 0;
+                queue.push_back((x, y));
+            }
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            let next = distance[[x, y]] + 1;
+
+            for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    if tiles[[nx, ny]].id == empty && distance[[nx, ny]] > next {
+                        distance[[nx, ny]] = next;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+
+        let mut prng = Random::new(sub_seed(seed, "thinning"));
+        let noise_seed = sub_seed(seed, "noise");
+        let radius = config.min_corridor / 2 + 1;
+
+        for ((x, y), tile) in tiles.indexed_iter_mut() {
+            let d = distance[[x, y]];
+
+            if tile.id != empty || d == usize::MAX {
+                continue;
+            }
+
+            // consume a value for every tile, so thinning doesn't depend on other settings
+            let thinned = prng.gen_bool(config.thinning);
+
+            let amplitude = config.noise_amplitude as f32;
+            let noise = value_noise(noise_seed, x as f32, y as f32, config.noise_scale);
+            let thickness = config.thickness as f32 + (noise * 2.0 - 1.0) * amplitude;
+
+            if d as f32 > thickness.round() || (d > 1 && thinned) {
+                continue;
+            }
+
+            // the ridge of the corridor has the highest distance, a corridor is
+            // at least `2 * ridge - 1` wide and keeps `min_corridor` tiles free
+            // as long as no more than half of the rest is freeze on each side
+            let ridge = (x.saturating_sub(radius)..(x + radius + 1).min(width))
+                .flat_map(|rx| {
+                    (y.saturating_sub(radius)..(y + radius + 1).min(height)).map(move |ry| (rx, ry))
+                })
+                .map(|pos| distance[pos])
+                .filter(|&d| d != usize::MAX)
+                .max()
+                .unwrap_or(0);
+
+            let limit = (2 * ridge).saturating_sub(1 + config.min_corridor) / 2;
+
+            if d <= limit {
+                *tile = freeze;
+            }
+        }
+    }
+}
+
+impl Map {
+    /// clears the room, the walls around it are kept as they are
+    pub fn carve_room(&mut self, center: (usize, usize), config: &RoomConfig) {
+        let empty = self.blocks.tile(BlockType::Empty);
+
+        for ((x, y), tile) in self.game_layer().tiles.unwrap_mut().indexed_iter_mut() {
+            if config.contains(center, (x, y)) {
+                *tile = empty;
+            }
+        }
+    }
+
+    /// puts up to `count` spawns next to each other on the floor below `center`
+    pub fn place_spawns(&mut self, center: (usize, usize), count: usize) {
+        let empty = self.blocks.tile_id(BlockType::Empty);
+        let spawn = self.blocks.tile(BlockType::Spawn);
+        let tiles = self.game_layer().tiles.unwrap_mut();
+        let (width, height) = tiles.dim();
+        let (x, mut y) = center;
+
+        while y + 1 < height && tiles[[x, y + 1]].id == empty {
+            y += 1;
+        }
+
+        let from = x.saturating_sub(count / 2);
+
+        for x in from..(from + count).min(width) {
+            if tiles[[x, y]].id == empty {
+                tiles[[x, y]] = spawn;
+            }
+        }
+    }
+
+    /// draws a line of `block` through `pos` across the whole corridor, perpendicular to `direction`,
+    /// up to the walls on both sides
+    pub fn draw_line(&mut self, pos: (usize, usize), direction: Direction, block: BlockType) {
+        let blocks = &self.blocks;
+        let tile = blocks.tile(block);
+        let tiles = self.raw.find_physics_layer_mut::<GameLayer>().unwrap().tiles.unwrap_mut();
+        let (width, height) = tiles.dim();
+
+        let (dx, dy) = match direction {
+            Direction::Up | Direction::Down => (1, 0),
+            Direction::Left | Direction::Right => (0, 1),
+        };
+
+        for sign in [-1isize, 1] {
+            let (mut x, mut y) = (pos.0 as isize, pos.1 as isize);
+
+            // the first round covers `pos` itself
+            if sign == 1 {
+                x += dx;
+                y += dy;
+            }
+
+            while x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+                let current = &mut tiles[[x as usize, y as usize]];
+
+                if blocks.is_solid(current.id) {
+                    break;
+                }
+
+                *current = tile;
+                x += sign * dx;
+                y += sign * dy;
+            }
+        }
+    }
+}
+
+impl Map {
+    /// flood fills from `from` through everything but walls
+    pub fn is_reachable(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        let tiles = self.raw.find_physics_layer::<GameLayer>().unwrap().tiles.unwrap_ref();
+        let (width, height) = tiles.dim();
+
+        let passable = |(x, y): (usize, usize)| {
+            x < width && y < height && !self.blocks.is_solid(tiles[[x, y]].id)
+        };
+
+        if !passable(from) || !passable(to) {
+            return false;
+        }
+
+        let mut visited = Array2::from_elem((width, height), false);
+        let mut stack = vec![from];
+
+        visited[[from.0, from.1]] = true;
+
+        while let Some((x, y)) = stack.pop() {
+            if (x, y) == to {
+                return true;
+            }
+
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+
+            for (nx, ny) in neighbors {
+                if passable((nx, ny)) && !visited[[nx, ny]] {
+                    visited[[nx, ny]] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        false
+    }
+}
@@ -1,2 +1,3 @@
 pub mod transition;
 pub mod pulse;
+pub mod taper;
@@ -1,20 +1,21 @@
 use crate::{
     brush::Brush,
-    mutations::{MutationState, Mutator},
+    mutations::{MutationContext, MutationState, Mutator},
 };
 
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PulseBrushMutation {
     pub value_border: usize, // from, to
     pub value_climax: usize,
     pub normal_peak: f32, // 0 to 1
     pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     steps: usize,
 }
 
 impl PulseBrushMutation {
     pub fn new(value_min: usize, value_max: usize, overall_steps: usize, normal_peak: f32) -> Self {
-        println!("pulse: steps: {}", overall_steps);
         Self {
             value_border: value_min,
             value_climax: value_max,
@@ -26,7 +27,7 @@ impl PulseBrushMutation {
 }
 
 impl Mutator<Brush> for PulseBrushMutation {
-    fn mutate(&mut self, mutant: &mut Brush) -> MutationState {
+    fn mutate(&mut self, mutant: &mut Brush, _context: &mut MutationContext) -> MutationState {
         if self.steps == 0 {
             return MutationState::Finished;
         }
@@ -44,7 +45,6 @@ impl Mutator<Brush> for PulseBrushMutation {
                 + self.value_border as f32
         };
 
-        println!("[pulse]\tslope\t{}", slope);
         mutant.apply_scale(slope);
 
         self.steps -= 1;
@@ -0,0 +1,35 @@
+use crate::{
+    brush::Brush,
+    mutations::{MutationContext, MutationState, Mutator},
+};
+
+/// scales the brush from `value_from` at the start of the walk to `value_to`
+/// at its end, following the walker's overall progress, never finishes
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TaperBrushMutation {
+    pub value_from: usize,
+    pub value_to: usize,
+}
+
+impl TaperBrushMutation {
+    pub fn new(value_from: usize, value_to: usize) -> Self {
+        Self {
+            value_from,
+            value_to,
+        }
+    }
+}
+
+impl Mutator<Brush> for TaperBrushMutation {
+    fn mutate(&mut self, mutant: &mut Brush, context: &mut MutationContext) -> MutationState {
+        let from = self.value_from as f32;
+        let to = self.value_to as f32;
+
+        mutant.apply_scale(from + (to - from) * context.info.progress);
+
+        MutationState::Processing
+    }
+
+    fn reset(&mut self) {}
+}
@@ -1,13 +1,15 @@
 use crate::{
     brush::Brush,
-    mutations::{MutationState, Mutator},
+    mutations::{MutationContext, MutationState, Mutator},
 };
 
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransitionBrushMutation {
     pub value_from: usize,
     pub value_to: usize,
     pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     steps: usize,
 }
 
@@ -23,7 +25,7 @@ impl TransitionBrushMutation {
 }
 
 impl Mutator<Brush> for TransitionBrushMutation {
-    fn mutate(&mut self, mutant: &mut Brush) -> MutationState {
+    fn mutate(&mut self, mutant: &mut Brush, _context: &mut MutationContext) -> MutationState {
         if self.steps == 0 {
             return MutationState::Finished;
         }
@@ -32,7 +34,6 @@ impl Mutator<Brush> for TransitionBrushMutation {
         let current_step = self.overall_steps - self.steps;
         let slope = current_step as f32 / self.overall_steps as f32 * diff + self.value_from as f32;
 
-        println!("[trans]\tslope\t{}", slope);
         mutant.apply_scale(slope);
 
         self.steps -= 1;
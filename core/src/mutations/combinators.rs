@@ -0,0 +1,347 @@
+//! Mutations made of other mutations.
+//!
+//! Every mutator returns [`MutationState::Processing`] for a step it did work
+//! in and [`MutationState::Finished`] once it has nothing left to do, without
+//! doing anything in that step. The combinators keep that contract: when a
+//! child finishes, the next one is tried in the same step, so no step is lost
+//! between two mutations. `reset` brings a combinator and all of its children
+//! back to their initial state, `reseed` gives every child its own stream
+//! derived from the combinator's seed and the child's position.
+//!
+//! The children share the combinator's [`MutationContext`], including its
+//! random stream, which is also what [`Chance`] draws its picks from.
+
+use crate::random::{sub_seed, Seed};
+
+use super::{MutationContext, MutationState, Mutator};
+
+pub type BoxedMutator<T> = Box<dyn Mutator<T> + Send>;
+
+/// checked before every step of [`Until`]
+pub type Predicate<T> = Box<dyn Fn(&T) -> bool + Send>;
+
+fn reseed_all<T>(mutations: &mut [BoxedMutator<T>], seed: Seed) {
+    for (idx, mutation) in mutations.iter_mut().enumerate() {
+        mutation.reseed(sub_seed(seed, &idx.to_string()));
+    }
+}
+
+/// runs the mutations one after another, finished once the last one is
+pub struct Sequence<T> {
+    mutations: Vec<BoxedMutator<T>>,
+    current: usize,
+}
+
+impl<T> Sequence<T> {
+    pub fn new(mutations: Vec<BoxedMutator<T>>) -> Self {
+        Self {
+            mutations,
+            current: 0,
+        }
+    }
+}
+
+impl<T> Mutator<T> for Sequence<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        while let Some(mutation) = self.mutations.get_mut(self.current) {
+            if mutation.mutate(mutant, context) == MutationState::Processing {
+                return MutationState::Processing;
+            }
+
+            self.current += 1;
+        }
+
+        MutationState::Finished
+    }
+
+    fn reset(&mut self) {
+        self.current = 0;
+
+        for mutation in self.mutations.iter_mut() {
+            mutation.reset();
+        }
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        reseed_all(&mut self.mutations, seed);
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        for mutation in self.mutations.iter_mut() {
+            mutation.finish(mutant);
+        }
+    }
+}
+
+/// runs the mutation `count` times, it's reset whenever it finishes
+pub struct Repeat<T> {
+    pub count: usize,
+    mutation: BoxedMutator<T>,
+    remaining: usize,
+}
+
+impl<T> Repeat<T> {
+    pub fn new(count: usize, mutation: BoxedMutator<T>) -> Self {
+        Self {
+            count,
+            mutation,
+            remaining: count,
+        }
+    }
+}
+
+impl<T> Mutator<T> for Repeat<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        while self.remaining > 0 {
+            if self.mutation.mutate(mutant, context) == MutationState::Processing {
+                return MutationState::Processing;
+            }
+
+            self.remaining -= 1;
+
+            if self.remaining > 0 {
+                self.mutation.reset();
+            }
+        }
+
+        MutationState::Finished
+    }
+
+    fn reset(&mut self) {
+        self.remaining = self.count;
+        self.mutation.reset();
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        self.mutation.reseed(seed);
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        self.mutation.finish(mutant);
+    }
+}
+
+/// runs the mutation for `steps` steps, finished afterwards whether the
+/// mutation finished in between or not
+pub struct Limit<T> {
+    pub steps: usize,
+    mutation: BoxedMutator<T>,
+    remaining: usize,
+}
+
+impl<T> Limit<T> {
+    pub fn new(steps: usize, mutation: BoxedMutator<T>) -> Self {
+        Self {
+            steps,
+            mutation,
+            remaining: steps,
+        }
+    }
+}
+
+impl<T> Mutator<T> for Limit<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        if self.remaining == 0 {
+            return MutationState::Finished;
+        }
+
+        self.remaining -= 1;
+        self.mutation.mutate(mutant, context)
+    }
+
+    fn reset(&mut self) {
+        self.remaining = self.steps;
+        self.mutation.reset();
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        self.mutation.reseed(seed);
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        self.mutation.finish(mutant);
+    }
+}
+
+/// runs the mutation over and over, never finishes, a mutation that finishes
+/// right after its reset does nothing until the next step
+pub struct Forever<T> {
+    mutation: BoxedMutator<T>,
+}
+
+impl<T> Forever<T> {
+    pub fn new(mutation: BoxedMutator<T>) -> Self {
+        Self { mutation }
+    }
+}
+
+impl<T> Mutator<T> for Forever<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        if self.mutation.mutate(mutant, context) == MutationState::Finished {
+            self.mutation.reset();
+            self.mutation.mutate(mutant, context);
+        }
+
+        MutationState::Processing
+    }
+
+    fn reset(&mut self) {
+        self.mutation.reset();
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        self.mutation.reseed(seed);
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        self.mutation.finish(mutant);
+    }
+}
+
+/// runs `a` with the chance `probability`, `b` otherwise, the choice is
+/// drawn from the context's stream on the first step after every reset
+pub struct Chance<T> {
+    pub probability: f32,
+    a: BoxedMutator<T>,
+    b: BoxedMutator<T>,
+    /// whether `a` was picked
+    picked: Option<bool>,
+}
+
+impl<T> Chance<T> {
+    pub fn new(probability: f32, a: BoxedMutator<T>, b: BoxedMutator<T>) -> Self {
+        Self {
+            probability,
+            a,
+            b,
+            picked: None,
+        }
+    }
+}
+
+impl<T> Mutator<T> for Chance<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        let probability = self.probability;
+        let a = *self
+            .picked
+            .get_or_insert_with(|| context.rng.gen_bool(probability));
+
+        match a {
+            true => self.a.mutate(mutant, context),
+            false => self.b.mutate(mutant, context),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.picked = None;
+        self.a.reset();
+        self.b.reset();
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        self.a.reseed(sub_seed(seed, "0"));
+        self.b.reseed(sub_seed(seed, "1"));
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        self.a.finish(mutant);
+        self.b.finish(mutant);
+    }
+}
+
+/// runs the mutation until `predicate` holds for the mutant, it's checked
+/// before every step and once it held, the combinator stays finished
+pub struct Until<T> {
+    predicate: Predicate<T>,
+    mutation: BoxedMutator<T>,
+    done: bool,
+}
+
+impl<T> Until<T> {
+    pub fn new(predicate: impl Fn(&T) -> bool + Send + 'static, mutation: BoxedMutator<T>) -> Self {
+        Self {
+            predicate: Box::new(predicate),
+            mutation,
+            done: false,
+        }
+    }
+}
+
+impl<T> Mutator<T> for Until<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        self.done = self.done || (self.predicate)(mutant);
+
+        if self.done {
+            return MutationState::Finished;
+        }
+
+        self.mutation.mutate(mutant, context)
+    }
+
+    fn reset(&mut self) {
+        self.done = false;
+        self.mutation.reset();
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        self.mutation.reseed(seed);
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        self.mutation.finish(mutant);
+    }
+}
+
+/// runs all unfinished mutations every step in order, finished once all of them are
+pub struct Parallel<T> {
+    mutations: Vec<BoxedMutator<T>>,
+    finished: Vec<bool>,
+}
+
+impl<T> Parallel<T> {
+    pub fn new(mutations: Vec<BoxedMutator<T>>) -> Self {
+        let finished = vec![false; mutations.len()];
+
+        Self {
+            mutations,
+            finished,
+        }
+    }
+}
+
+impl<T> Mutator<T> for Parallel<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        let mut state = MutationState::Finished;
+
+        for (mutation, finished) in self.mutations.iter_mut().zip(self.finished.iter_mut()) {
+            if *finished {
+                continue;
+            }
+
+            match mutation.mutate(mutant, context) {
+                MutationState::Processing => state = MutationState::Processing,
+                MutationState::Finished => *finished = true,
+            }
+        }
+
+        state
+    }
+
+    fn reset(&mut self) {
+        self.finished.fill(false);
+
+        for mutation in self.mutations.iter_mut() {
+            mutation.reset();
+        }
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        reseed_all(&mut self.mutations, seed);
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        for mutation in self.mutations.iter_mut() {
+            mutation.finish(mutant);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use ndarray::Array2;
+use twmap::{GameTile, TileFlags};
+
+use crate::{
+    block::BlockType,
+    map::Map,
+    mutations::{MutationContext, MutationState, Mutator},
+};
+
+use super::neighbors;
+
+/// fills small enclosed pockets and removes small floating wall blobs
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlobFillMapMutation {
+    /// empty areas with less tiles are filled with walls, 0 disables it
+    pub max_empty_size: usize,
+    /// wall areas with less tiles are removed, 0 disables it
+    pub max_wall_size: usize,
+    /// apply once after generation instead of on every step
+    pub at_end: bool,
+    pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    steps: usize,
+}
+
+impl BlobFillMapMutation {
+    pub fn new(
+        max_empty_size: usize,
+        max_wall_size: usize,
+        at_end: bool,
+        overall_steps: usize,
+    ) -> Self {
+        Self {
+            max_empty_size,
+            max_wall_size,
+            at_end,
+            overall_steps,
+            steps: overall_steps,
+        }
+    }
+
+    pub fn apply(&self, map: &mut Map) {
+        let empty = map.blocks().tile_id(BlockType::Empty);
+        let hookable = map.blocks().tile_id(BlockType::Hookable);
+        let tiles = map.game_layer().tiles.unwrap_mut();
+
+        fill_blobs(tiles, empty, hookable, self.max_empty_size);
+        fill_blobs(tiles, hookable, empty, self.max_wall_size);
+    }
+}
+
+/// replaces every connected area of `from` tiles smaller than `max_size` by `to`
+fn fill_blobs(tiles: &mut Array2<GameTile>, from: u8, to: u8, max_size: usize) {
+    if max_size == 0 {
+        return;
+    }
+
+    let mut visited = Array2::from_elem(tiles.dim(), false);
+    let mut stack = Vec::new();
+    let mut blob = Vec::new();
+    let mut filled = Vec::new();
+
+    for ((x, y), &tile) in tiles.indexed_iter() {
+        if tile.id != from || visited[[x, y]] {
+            continue;
+        }
+
+        visited[[x, y]] = true;
+        stack.push((x, y));
+        blob.clear();
+
+        while let Some((x, y)) = stack.pop() {
+            blob.push((x, y));
+
+            for (nx, ny) in neighbors(tiles, x, y) {
+                if tiles[[nx, ny]].id == from && !visited[[nx, ny]] {
+                    visited[[nx, ny]] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        if blob.len() < max_size {
+            filled.extend_from_slice(&blob);
+        }
+    }
+
+    for pos in filled {
+        tiles[pos] = GameTile::new(to, TileFlags::empty());
+    }
+}
+
+impl Mutator<Map> for BlobFillMapMutation {
+    fn mutate(&mut self, mutant: &mut Map, _context: &mut MutationContext) -> MutationState {
+        if self.at_end || self.steps == 0 {
+            return MutationState::Finished;
+        }
+
+        self.apply(mutant);
+
+        self.steps -= 1;
+
+        MutationState::Processing
+    }
+
+    fn reset(&mut self) {
+        self.steps = self.overall_steps;
+    }
+
+    fn finish(&mut self, mutant: &mut Map) {
+        if self.at_end {
+            self.apply(mutant);
+        }
+    }
+}
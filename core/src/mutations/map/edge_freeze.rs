@@ -0,0 +1,62 @@
+use crate::{
+    map::{FreezeConfig, Map},
+    mutations::{MutationContext, MutationState, Mutator},
+};
+
+/// lines carved corridors with freeze
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeFreezeMapMutation {
+    /// max distance of freeze from a wall
+    pub thickness: usize,
+    /// apply once after generation instead of on every step
+    pub at_end: bool,
+    pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    steps: usize,
+}
+
+impl EdgeFreezeMapMutation {
+    pub fn new(thickness: usize, at_end: bool, overall_steps: usize) -> Self {
+        Self {
+            thickness,
+            at_end,
+            overall_steps,
+            steps: overall_steps,
+        }
+    }
+
+    pub fn apply(&self, map: &mut Map) {
+        let config = FreezeConfig {
+            thickness: self.thickness,
+            min_corridor: 0,
+            ..Default::default()
+        };
+
+        map.place_freeze(&config, 0);
+    }
+}
+
+impl Mutator<Map> for EdgeFreezeMapMutation {
+    fn mutate(&mut self, mutant: &mut Map, _context: &mut MutationContext) -> MutationState {
+        if self.at_end || self.steps == 0 {
+            return MutationState::Finished;
+        }
+
+        self.apply(mutant);
+
+        self.steps -= 1;
+
+        MutationState::Processing
+    }
+
+    fn reset(&mut self) {
+        self.steps = self.overall_steps;
+    }
+
+    fn finish(&mut self, mutant: &mut Map) {
+        if self.at_end {
+            self.apply(mutant);
+        }
+    }
+}
@@ -1 +1,20 @@
+pub mod blob_fill;
+pub mod edge_freeze;
+pub mod roughen;
 
+use ndarray::Array2;
+use twmap::GameTile;
+
+/// 4-neighborhood of a tile, clipped to the map
+fn neighbors(tiles: &Array2<GameTile>, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (width, height) = tiles.dim();
+
+    [
+        (x.wrapping_sub(1), y),
+        (x + 1, y),
+        (x, y.wrapping_sub(1)),
+        (x, y + 1),
+    ]
+    .into_iter()
+    .filter(move |&(x, y)| x < width && y < height)
+}
@@ -0,0 +1,100 @@
+use twmap::{GameTile, TileFlags};
+
+use crate::{
+    block::BlockType,
+    map::Map,
+    mutations::{MutationContext, MutationState, Mutator},
+    random::{value_noise, Seed},
+};
+
+use super::neighbors;
+
+/// erodes and grows walls along corridors following a noise pattern
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoughenMapMutation {
+    /// size of noise features in tiles
+    pub scale: f32,
+    /// 0 to 1, share of wall edges that are changed
+    pub strength: f32,
+    /// apply once after generation instead of on every step
+    pub at_end: bool,
+    pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    seed: Seed,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    steps: usize,
+}
+
+impl RoughenMapMutation {
+    pub fn new(scale: f32, strength: f32, at_end: bool, overall_steps: usize) -> Self {
+        Self {
+            scale,
+            strength,
+            at_end,
+            overall_steps,
+            seed: 0,
+            steps: overall_steps,
+        }
+    }
+
+    pub fn apply(&self, map: &mut Map) {
+        let empty = map.blocks().tile_id(BlockType::Empty);
+        let hookable = map.blocks().tile_id(BlockType::Hookable);
+        let tiles = map.game_layer().tiles.unwrap_mut();
+        let original = tiles.clone();
+        let threshold = self.strength.clamp(0.0, 1.0) / 2.0;
+
+        for ((x, y), tile) in tiles.indexed_iter_mut() {
+            let opposite = match tile.id {
+                id if id == empty => hookable,
+                id if id == hookable => empty,
+                _ => continue,
+            };
+
+            // only edges between walls and corridors are touched
+            if !neighbors(&original, x, y).any(|pos| original[pos].id == opposite) {
+                continue;
+            }
+
+            let noise = value_noise(self.seed, x as f32, y as f32, self.scale);
+
+            let flip = match tile.id == hookable {
+                true => noise > 1.0 - threshold,
+                false => noise < threshold,
+            };
+
+            if flip {
+                *tile = GameTile::new(opposite, TileFlags::empty());
+            }
+        }
+    }
+}
+
+impl Mutator<Map> for RoughenMapMutation {
+    fn mutate(&mut self, mutant: &mut Map, _context: &mut MutationContext) -> MutationState {
+        if self.at_end || self.steps == 0 {
+            return MutationState::Finished;
+        }
+
+        self.apply(mutant);
+
+        self.steps -= 1;
+
+        MutationState::Processing
+    }
+
+    fn reset(&mut self) {
+        self.steps = self.overall_steps;
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        self.seed = seed;
+    }
+
+    fn finish(&mut self, mutant: &mut Map) {
+        if self.at_end {
+            self.apply(mutant);
+        }
+    }
+}
@@ -1,14 +1,66 @@
 pub mod brush;
+pub mod combinators;
 pub mod map;
 pub mod walker;
 
+use crate::{
+    map::Map,
+    position::{as_index, Direction, VectorView2},
+    random::{Random, Seed},
+    walker::Walker,
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum MutationState {
     Processing,
     Finished,
 }
 
+/// where a walker is at the start of a step
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StepInfo {
+    pub position: [usize; 2],
+    /// direction the walker moves in with this step, `None` before the first one
+    pub direction: Option<Direction>,
+    /// steps the walker took so far
+    pub step: usize,
+    /// index of the waypoint the walker heads for
+    pub waypoint: usize,
+    /// see [`Walker::waypoint_progress`]
+    pub waypoint_progress: f32,
+    /// see [`Walker::progress`]
+    pub progress: f32,
+}
+
+impl StepInfo {
+    pub fn new(walker: &Walker, position: VectorView2, step: usize) -> Self {
+        Self {
+            position: as_index(position),
+            direction: walker.last_state().map(|state| state.direction),
+            step,
+            waypoint: walker.preferred_state().waypoint,
+            waypoint_progress: walker.waypoint_progress(position),
+            progress: walker.progress(position),
+        }
+    }
+}
+
+/// what a mutator gets to see besides its mutant
+pub struct MutationContext<'a> {
+    pub info: StepInfo,
+    /// the map being carved, `None` for map mutations, their mutant is the map
+    pub map: Option<&'a Map>,
+    /// random stream of the mutation, seeded like [`Mutator::reseed`]
+    pub rng: &'a mut Random,
+}
+
 pub trait Mutator<T> {
-    fn mutate(&mut self, mutant: &mut T) -> MutationState;
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState;
     fn reset(&mut self);
+
+    /// gives the mutator its own random stream, called before every generation
+    fn reseed(&mut self, _seed: Seed) {}
+
+    /// called once after the last step of every generation
+    fn finish(&mut self, _mutant: &mut T) {}
 }
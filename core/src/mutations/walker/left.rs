@@ -1,11 +1,13 @@
 use crate::{
-    mutations::{MutationState, Mutator},
+    mutations::{MutationContext, MutationState, Mutator},
     walker::Walker,
 };
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LeftWalkerMutation {
     pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     steps: usize,
 }
 
@@ -19,7 +21,7 @@ impl LeftWalkerMutation {
 }
 
 impl Mutator<Walker> for LeftWalkerMutation {
-    fn mutate(&mut self, mutant: &mut Walker) -> MutationState {
+    fn mutate(&mut self, mutant: &mut Walker, _context: &mut MutationContext) -> MutationState {
         if self.steps == 0 {
             return MutationState::Finished
         }
@@ -3,3 +3,4 @@ pub mod random;
 pub mod right;
 pub mod straight;
 pub mod backwards;
+pub mod weighted;
@@ -1,38 +1,35 @@
 use crate::{
-    mutations::{MutationState, Mutator},
+    mutations::{MutationContext, MutationState, Mutator},
     position::Direction,
-    random::{Random, Seed},
     walker::Walker,
 };
 
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RandomWalkerMutation {
-    pub seed: Seed,
     pub overall_steps: usize,
 
-    prng: Random,
+    #[cfg_attr(feature = "serde", serde(skip))]
     steps: usize
 }
 
 impl RandomWalkerMutation {
-    pub fn new(overall_steps: usize, seed: Seed) -> Self {
+    pub fn new(overall_steps: usize) -> Self {
         Self {
-            seed,
             overall_steps,
-            prng: Random::new(seed),
             steps: overall_steps
         }
     }
 }
 
 impl Mutator<Walker> for RandomWalkerMutation {
-    fn mutate(&mut self, mutant: &mut Walker) -> MutationState {
+    fn mutate(&mut self, mutant: &mut Walker, context: &mut MutationContext) -> MutationState {
         if self.steps == 0 {
             return MutationState::Finished;
         }
 
-        let random_direction = self.prng.gen_u64() as usize % 4;
-        let random_waypoint = self.prng.gen_u64() as usize % mutant.get_waypoints().len();
+        let random_direction = context.rng.gen_u64() as usize % 4;
+        let random_waypoint = context.rng.gen_u64() as usize % mutant.get_waypoints().len();
 
         let random_direction = Direction::from(random_direction);
 
@@ -46,7 +43,5 @@ impl Mutator<Walker> for RandomWalkerMutation {
 
     fn reset(&mut self) {
         self.steps = self.overall_steps;
-
-        self.prng.reset();
     }
 }
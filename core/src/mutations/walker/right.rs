@@ -1,17 +1,18 @@
 use crate::{
-    mutations::{MutationState, Mutator},
+    mutations::{MutationContext, MutationState, Mutator},
     walker::Walker,
 };
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RightWalkerMutation {
     pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     steps: usize,
 }
 
 impl RightWalkerMutation {
     pub fn new(overall_steps: usize) -> Self {
-        println!("right: steps: {}", overall_steps);
         Self {
             overall_steps,
             steps: overall_steps,
@@ -20,7 +21,7 @@ impl RightWalkerMutation {
 }
 
 impl Mutator<Walker> for RightWalkerMutation {
-    fn mutate(&mut self, mutant: &mut Walker) -> MutationState {
+    fn mutate(&mut self, mutant: &mut Walker, _context: &mut MutationContext) -> MutationState {
         if self.steps == 0 {
             return MutationState::Finished;
         }
@@ -1,11 +1,13 @@
 use crate::{
     walker::Walker,
-    mutations::{MutationState, Mutator},
+    mutations::{MutationContext, MutationState, Mutator},
 };
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StraightWalkerMutation{
     pub overall_steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     steps: usize,
 }
 
@@ -19,8 +21,7 @@ impl StraightWalkerMutation {
 }
 
 impl Mutator<Walker> for StraightWalkerMutation {
-    fn mutate(&mut self, mutant: &mut Walker) -> MutationState {
-        println!("straight: steps: {} {}", self.steps, self.overall_steps);
+    fn mutate(&mut self, mutant: &mut Walker, _context: &mut MutationContext) -> MutationState {
         if self.steps == 0 {
             return MutationState::Finished;
         }
@@ -0,0 +1,127 @@
+use crate::{
+    mutations::{MutationContext, MutationState, Mutator},
+    position::Direction,
+    random::{ProbableValue, RandomDist, RandomDistConfig},
+    walker::Walker,
+};
+
+/// turn relative to the preferred direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Turn {
+    Straight,
+    Left,
+    Right,
+    Backwards,
+}
+
+impl Turn {
+    pub const ALL: [Turn; 4] = [Turn::Straight, Turn::Left, Turn::Right, Turn::Backwards];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Turn::Straight => "straight",
+            Turn::Left => "left",
+            Turn::Right => "right",
+            Turn::Backwards => "backwards",
+        }
+    }
+
+    pub fn apply(&self, direction: Direction) -> Direction {
+        match self {
+            Turn::Straight => direction,
+            Turn::Left => direction.prev(),
+            Turn::Right => direction.next(),
+            Turn::Backwards => direction.backwards(),
+        }
+    }
+}
+
+/// turns sampled from `dist` every step, `momentum` is the chance to repeat
+/// the previous turn instead
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightedWalkerMutation {
+    pub overall_steps: usize,
+    pub dist: RandomDist<Turn>,
+    pub momentum: f32,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    steps: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    previous: Option<Turn>,
+}
+
+impl WeightedWalkerMutation {
+    pub fn new(overall_steps: usize, dist: RandomDist<Turn>, momentum: f32) -> Self {
+        Self {
+            overall_steps,
+            dist,
+            momentum,
+            steps: overall_steps,
+            previous: None,
+        }
+    }
+
+    /// weights in the order of [`Turn::ALL`]
+    pub fn from_weights(overall_steps: usize, weights: [f32; 4], momentum: f32) -> Self {
+        let values = Turn::ALL
+            .into_iter()
+            .zip(weights)
+            .map(|(turn, weight)| ProbableValue::new(weight, turn))
+            .collect();
+
+        Self::new(
+            overall_steps,
+            RandomDist::from_config(RandomDistConfig::from_values(values)),
+            momentum,
+        )
+    }
+
+    /// weight of `turn`, zero if the distribution lacks it
+    pub fn weight(&self, turn: Turn) -> f32 {
+        self.dist
+            .config
+            .values
+            .iter()
+            .filter(|&&ProbableValue(_, value)| value == turn)
+            .map(|&ProbableValue(p, _)| p)
+            .sum()
+    }
+}
+
+impl Default for WeightedWalkerMutation {
+    fn default() -> Self {
+        Self::from_weights(0, [0.6, 0.15, 0.15, 0.1], 0.5)
+    }
+}
+
+impl Mutator<Walker> for WeightedWalkerMutation {
+    fn mutate(&mut self, mutant: &mut Walker, context: &mut MutationContext) -> MutationState {
+        if self.steps == 0 {
+            return MutationState::Finished;
+        }
+
+        let repeat = context.rng.gen_bool(self.momentum);
+        let turn = match self.previous {
+            Some(previous) if repeat => previous,
+            _ if self.dist.is_valid() => context.rng.sample_value(&self.dist),
+            _ => Turn::Straight,
+        };
+
+        let needed_state = *mutant.preferred_state();
+
+        mutant.set_next_direction(turn.apply(needed_state.direction));
+        mutant.set_next_waypoint(needed_state.waypoint);
+
+        self.previous = Some(turn);
+        self.steps -= 1;
+
+        MutationState::Processing
+    }
+
+    fn reset(&mut self) {
+        self.steps = self.overall_steps;
+        self.previous = None;
+    }
+}
@@ -0,0 +1,709 @@
+//! Generation pipelines.
+//!
+//! A [`Pipeline`] describes everything that drives a [`Generator`]: its
+//! settings and the mutation loops applied to the brush, the map and the
+//! walker. With the `serde` feature pipelines can be stored as JSON or RON,
+//! the format is picked by the file extension:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "generator": {
+//!     "scale_factor": 200.0,
+//!     "seed": 0,
+//!     "freeze": { "thickness": 1, "thinning": 0.0, "noise_amplitude": 0, "noise_scale": 8.0, "min_corridor": 3 },
+//!     "rooms": { "width": 10, "height": 8, "shape": "Rect", "spawn_count": 4 },
+//!     "max_steps": 100000,
+//!     "padding": 32,
+//!     "blocks": { "Freeze": { "tile_id": 9, "design_layer": "Freeze" } },
+//!     "brush_shape": { "Circle": { "circularity": 1.0 } },
+//!     "brush_rims": [ { "block": "Freeze", "width": 1 }, { "block": "Unhookable", "width": 1 } ],
+//!     "brush_orientation": { "Rotate": { "smoothing": 0.5 } },
+//!     "branches": {
+//!       "config": { "probability": 0.01, "max_active": 2, "lifetime": 60, "rejoin_chance": 0.5, "brush_size": 3 },
+//!       "brush": [],
+//!       "walker": [ { "count": null, "mutations": [ { "Straight": { "overall_steps": 1 } } ] } ]
+//!     },
+//!     "waypoints": [ [0.0, 1.0], [0.5, 0.5], [1.0, 0.0] ]
+//!   },
+//!   "brush": [
+//!     {
+//!       "count": null,
+//!       "mutations": [
+//!         { "Pulse": { "value_border": 1, "value_climax": 20, "normal_peak": 0.5, "overall_steps": 200 } }
+//!       ]
+//!     }
+//!   ],
+//!   "map": [],
+//!   "walker": [
+//!     {
+//!       "count": null,
+//!       "mutations": [ { "Straight": { "overall_steps": 5 } }, { "Random": { "overall_steps": 3 } } ],
+//!       "ids": [ null, "detour" ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Every generation takes one master seed. Each mutation gets its own random
+//! stream derived from it, keyed by the section, the kind of the mutation and
+//! its id, e.g. `walker/random/@detour`. Ids are set per loop in `ids`, in the
+//! order of its mutations, and keep a stream no matter what is added or
+//! removed around the mutation. Mutations without an id are keyed by how many
+//! mutations of the same kind without an id precede them in the section
+//! instead, e.g. `walker/random/0`. Adding a mutation of another kind or one
+//! with an id never changes their streams, adding one of the same kind without
+//! an id in front of them does. A mutation draws from its stream through the
+//! [`MutationContext`] it's handed every step.
+//!
+//! Every loop is run once per step, in order, and is made of
+//! [combinators](crate::mutations::combinators). Its mutations form a
+//! [`Sequence`], they're applied one after another until one of them is still
+//! processing. A loop without a `count` repeats the sequence [`Forever`]: once
+//! the last mutation has finished, all of them are reset and the loop starts
+//! over. A loop with a `count` is [`Limit`]ed to that many steps and never
+//! starts over. After the last step every mutation gets a chance to finish its
+//! work, map mutations with `at_end` set only run then.
+//!
+//! Branches run their own brush and walker loops, every branch starts them
+//! anew with streams derived from the seed and the number of the branch.
+//!
+//! `version` is bumped on every incompatible change of the format, files
+//! newer than [`PIPELINE_VERSION`] are rejected. Missing sections default to
+//! empty ones.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    block::BlockMapping,
+    branch::BranchConfig,
+    brush::{Brush, BrushOrientation, BrushRim, BrushShape},
+    generator::{Generator, StepHandler, DEFAULT_MAX_STEPS, DEFAULT_PADDING},
+    map::{FreezeConfig, Map, RoomConfig},
+    mutations::{
+        brush::{
+            pulse::PulseBrushMutation, taper::TaperBrushMutation,
+            transition::TransitionBrushMutation,
+        },
+        combinators::{BoxedMutator, Forever, Limit, Sequence},
+        map::{
+            blob_fill::BlobFillMapMutation, edge_freeze::EdgeFreezeMapMutation,
+            roughen::RoughenMapMutation,
+        },
+        walker::{
+            backwards::BackwardsWalkerMutation, left::LeftWalkerMutation,
+            random::RandomWalkerMutation, right::RightWalkerMutation,
+            straight::StraightWalkerMutation, weighted::WeightedWalkerMutation,
+        },
+        MutationContext, MutationState, Mutator, StepInfo,
+    },
+    random::{sub_seed, Random, Seed},
+    walker::Walker,
+};
+
+/// current version of the pipeline format
+pub const PIPELINE_VERSION: u32 = 1;
+
+/// diagonal from the bottom left to the top right corner
+pub const DEFAULT_WAYPOINTS: [(f32, f32); 6] = [
+    (0.0, 1.0),
+    (0.2, 0.8),
+    (0.4, 0.6),
+    (0.6, 0.4),
+    (0.8, 0.2),
+    (1.0, 0.0),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BrushMutation {
+    Pulse(PulseBrushMutation),
+    Transition(TransitionBrushMutation),
+    Taper(TaperBrushMutation),
+}
+
+impl BrushMutation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushMutation::Pulse(_) => "pulse",
+            BrushMutation::Transition(_) => "transition",
+            BrushMutation::Taper(_) => "taper",
+        }
+    }
+
+    /// largest brush size the mutation can reach
+    pub fn max_size(&self) -> usize {
+        match self {
+            BrushMutation::Pulse(mutation) => mutation.value_border.max(mutation.value_climax),
+            BrushMutation::Transition(mutation) => mutation.value_from.max(mutation.value_to),
+            BrushMutation::Taper(mutation) => mutation.value_from.max(mutation.value_to),
+        }
+    }
+
+    pub fn to_mutator(&self) -> Box<dyn Mutator<Brush> + Send> {
+        match self {
+            BrushMutation::Pulse(mutation) => Box::new(mutation.clone()),
+            BrushMutation::Transition(mutation) => Box::new(mutation.clone()),
+            BrushMutation::Taper(mutation) => Box::new(mutation.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MapMutation {
+    EdgeFreeze(EdgeFreezeMapMutation),
+    BlobFill(BlobFillMapMutation),
+    Roughen(RoughenMapMutation),
+}
+
+impl MapMutation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapMutation::EdgeFreeze(_) => "edge_freeze",
+            MapMutation::BlobFill(_) => "blob_fill",
+            MapMutation::Roughen(_) => "roughen",
+        }
+    }
+
+    pub fn to_mutator(&self) -> Box<dyn Mutator<Map> + Send> {
+        match self {
+            MapMutation::EdgeFreeze(mutation) => Box::new(mutation.clone()),
+            MapMutation::BlobFill(mutation) => Box::new(mutation.clone()),
+            MapMutation::Roughen(mutation) => Box::new(mutation.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WalkerMutation {
+    Straight(StraightWalkerMutation),
+    Backwards(BackwardsWalkerMutation),
+    Left(LeftWalkerMutation),
+    Right(RightWalkerMutation),
+    Random(RandomWalkerMutation),
+    Weighted(WeightedWalkerMutation),
+}
+
+impl WalkerMutation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WalkerMutation::Straight(_) => "straight",
+            WalkerMutation::Backwards(_) => "backwards",
+            WalkerMutation::Left(_) => "left",
+            WalkerMutation::Right(_) => "right",
+            WalkerMutation::Random(_) => "random",
+            WalkerMutation::Weighted(_) => "weighted",
+        }
+    }
+
+    pub fn to_mutator(&self) -> Box<dyn Mutator<Walker> + Send> {
+        match self {
+            WalkerMutation::Straight(mutation) => Box::new(*mutation),
+            WalkerMutation::Backwards(mutation) => Box::new(*mutation),
+            WalkerMutation::Left(mutation) => Box::new(*mutation),
+            WalkerMutation::Right(mutation) => Box::new(*mutation),
+            WalkerMutation::Random(mutation) => Box::new(mutation.clone()),
+            WalkerMutation::Weighted(mutation) => Box::new(mutation.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MutationLoop<M> {
+    /// amount of steps the loop is active for, `None` keeps it active forever
+    pub count: Option<usize>,
+    pub mutations: Vec<M>,
+    /// ids naming the random streams of the mutations, in the order of
+    /// `mutations`, a missing or `None` id falls back to the occurrence
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub ids: Vec<Option<String>>,
+}
+
+impl<M> MutationLoop<M> {
+    pub fn new(count: Option<usize>, mutations: Vec<M>) -> Self {
+        Self {
+            count,
+            mutations,
+            ids: Vec::new(),
+        }
+    }
+
+    pub fn endless(mutations: Vec<M>) -> Self {
+        Self::new(None, mutations)
+    }
+
+    pub fn with_ids(mut self, ids: Vec<Option<String>>) -> Self {
+        self.ids = ids;
+
+        self
+    }
+
+    /// id of the `idx`th mutation
+    pub fn id(&self, idx: usize) -> Option<&str> {
+        self.ids.get(idx).and_then(Option::as_deref)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GeneratorSettings {
+    /// scale of normalized waypoints in tiles
+    pub scale_factor: f32,
+    /// master seed every mutation stream is derived from
+    pub seed: Seed,
+    /// freeze border placed after generation
+    pub freeze: Option<FreezeConfig>,
+    /// spawn and finish rooms carved after generation
+    pub rooms: Option<RoomConfig>,
+    /// walker steps after which generation fails
+    pub max_steps: usize,
+    /// free space for the walker beyond the waypoints, in tiles
+    pub padding: usize,
+    /// game tile ids and design layers of the blocks
+    pub blocks: BlockMapping,
+    /// shape of the brush, brush mutations only change its size
+    pub brush_shape: BrushShape,
+    /// rings of blocks drawn around the carved part of the brush, innermost first
+    pub brush_rims: Vec<BrushRim>,
+    /// whether the brush turns with the walker's heading
+    pub brush_orientation: BrushOrientation,
+    /// walkers branching off the main path
+    pub branches: Option<BranchSettings>,
+    /// normalized waypoints the pipeline was designed with, in order
+    pub waypoints: Vec<(f32, f32)>,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            scale_factor: 200.0,
+            seed: 0,
+            freeze: None,
+            rooms: None,
+            max_steps: DEFAULT_MAX_STEPS,
+            padding: DEFAULT_PADDING,
+            blocks: BlockMapping::default(),
+            brush_shape: BrushShape::default(),
+            brush_rims: Vec::new(),
+            brush_orientation: BrushOrientation::Fixed,
+            branches: None,
+            waypoints: DEFAULT_WAYPOINTS.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BranchSettings {
+    pub config: BranchConfig,
+    /// brush loops of every branch, its brush starts at `config.brush_size`
+    pub brush: Vec<MutationLoop<BrushMutation>>,
+    /// walker loops of every branch, without any a branch walks straight to its target
+    pub walker: Vec<MutationLoop<WalkerMutation>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pipeline {
+    pub version: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub generator: GeneratorSettings,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub brush: Vec<MutationLoop<BrushMutation>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub map: Vec<MutationLoop<MapMutation>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub walker: Vec<MutationLoop<WalkerMutation>>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            version: PIPELINE_VERSION,
+            generator: GeneratorSettings::default(),
+            brush: Vec::new(),
+            map: Vec::new(),
+            walker: Vec::new(),
+        }
+    }
+}
+
+impl Pipeline {
+    /// largest brush size any brush mutation or branch can reach, at least 1
+    pub fn max_brush_size(&self) -> usize {
+        let branches = self.generator.branches.as_ref();
+        let branch_size = branches.map_or(1, |branches| branches.config.brush_size);
+
+        self.brush
+            .iter()
+            .chain(branches.into_iter().flat_map(|branches| &branches.brush))
+            .flat_map(|mutation_loop| &mutation_loop.mutations)
+            .map(BrushMutation::max_size)
+            .fold(branch_size.max(1), usize::max)
+    }
+
+    /// applies the settings and installs the mutations as the step handler,
+    /// generate with `self.generator.seed` to reproduce the pipeline's map
+    pub fn setup(&self, generator: &mut Generator) {
+        generator.set_scale_factor(self.generator.scale_factor);
+        generator.set_freeze(self.generator.freeze.clone());
+        generator.set_rooms(self.generator.rooms.clone());
+        generator.set_max_steps(self.generator.max_steps);
+        generator.set_max_brush_size(self.max_brush_size());
+        generator.set_padding(self.generator.padding);
+        generator.set_blocks(self.generator.blocks.clone());
+        generator.set_brush_shape(self.generator.brush_shape.clone());
+        generator.set_brush_rims(self.generator.brush_rims.clone());
+        generator.set_brush_orientation(self.generator.brush_orientation.clone());
+        generator.set_branches(self.generator.branches.as_ref().map(|b| b.config));
+        generator.on_step(PipelineRunner::new(&self.brush, &self.map, &self.walker));
+
+        if let Some(branches) = self.generator.branches.clone() {
+            generator.on_branch_step(move || {
+                Box::new(PipelineRunner::new(&branches.brush, &[], &branches.walker))
+            });
+        }
+    }
+}
+
+/// runs the mutation loops of a pipeline, they're built anew for every
+/// generation, seeded by the generation's seed
+struct PipelineRunner {
+    brush_loops: Vec<MutationLoop<BrushMutation>>,
+    map_loops: Vec<MutationLoop<MapMutation>>,
+    walker_loops: Vec<MutationLoop<WalkerMutation>>,
+    brush: Vec<MutatorLoop<Brush>>,
+    map: Vec<MutatorLoop<Map>>,
+    walker: Vec<MutatorLoop<Walker>>,
+}
+
+impl PipelineRunner {
+    fn new(
+        brush: &[MutationLoop<BrushMutation>],
+        map: &[MutationLoop<MapMutation>],
+        walker: &[MutationLoop<WalkerMutation>],
+    ) -> Self {
+        Self {
+            brush_loops: brush.to_vec(),
+            map_loops: map.to_vec(),
+            walker_loops: walker.to_vec(),
+            brush: Vec::new(),
+            map: Vec::new(),
+            walker: Vec::new(),
+        }
+    }
+}
+
+impl StepHandler for PipelineRunner {
+    fn begin(&mut self, seed: Seed) {
+        self.brush = MutatorLoop::from_loops(
+            "brush",
+            &self.brush_loops,
+            BrushMutation::name,
+            BrushMutation::to_mutator,
+            seed,
+        );
+        self.map = MutatorLoop::from_loops(
+            "map",
+            &self.map_loops,
+            MapMutation::name,
+            MapMutation::to_mutator,
+            seed,
+        );
+        self.walker = MutatorLoop::from_loops(
+            "walker",
+            &self.walker_loops,
+            WalkerMutation::name,
+            WalkerMutation::to_mutator,
+            seed,
+        );
+    }
+
+    fn step(&mut self, walker: &mut Walker, map: &mut Map, brush: &mut Brush, info: StepInfo) {
+        MutatorLoop::mutate_all(brush, &mut self.brush, info, Some(map));
+        MutatorLoop::mutate_all(map, &mut self.map, info, None);
+        MutatorLoop::mutate_all(walker, &mut self.walker, info, Some(map));
+    }
+
+    fn end(&mut self, walker: &mut Walker, map: &mut Map, brush: &mut Brush) {
+        MutatorLoop::finish_all(brush, &mut self.brush);
+        MutatorLoop::finish_all(map, &mut self.map);
+        MutatorLoop::finish_all(walker, &mut self.walker);
+    }
+
+    fn active_mutations(&self) -> Vec<String> {
+        let brush = self.brush.iter().flat_map(MutatorLoop::active_streams);
+        let map = self.map.iter().flat_map(MutatorLoop::active_streams);
+        let walker = self.walker.iter().flat_map(MutatorLoop::active_streams);
+
+        brush.chain(map).chain(walker).cloned().collect()
+    }
+}
+
+/// a mutation loop made of combinators: an endless loop runs its mutations as
+/// a [`Forever`] repeated [`Sequence`], a counted one as a [`Sequence`]
+/// [`Limit`]ed to `count` steps
+struct MutatorLoop<T> {
+    mutation: BoxedMutator<T>,
+    /// names of the random streams, one per mutation
+    streams: Vec<String>,
+    /// whether the mutations ran during the last step, one per mutation
+    ran: Vec<Arc<AtomicBool>>,
+}
+
+impl<T: 'static> MutatorLoop<T> {
+    fn from_loops<M>(
+        section: &str,
+        loops: &[MutationLoop<M>],
+        name: impl Fn(&M) -> &'static str,
+        to_mutator: impl Fn(&M) -> BoxedMutator<T>,
+        seed: Seed,
+    ) -> Vec<Self> {
+        let mut occurrences = HashMap::new();
+
+        loops
+            .iter()
+            .map(|lp| {
+                let streams: Vec<String> = lp
+                    .mutations
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, mutation)| {
+                        if let Some(id) = lp.id(idx) {
+                            return format!("{}/{}/@{}", section, name(mutation), id);
+                        }
+
+                        let occurrence = occurrences.entry(name(mutation)).or_insert(0);
+                        let stream = format!("{}/{}/{}", section, name(mutation), occurrence);
+
+                        *occurrence += 1;
+
+                        stream
+                    })
+                    .collect();
+
+                let ran: Vec<_> = streams.iter().map(|_| Arc::default()).collect();
+                let mutations = lp
+                    .mutations
+                    .iter()
+                    .zip(streams.iter())
+                    .zip(ran.iter())
+                    .map(|((mutation, stream), ran)| {
+                        let mut mutation = LoopMutation {
+                            mutation: to_mutator(mutation),
+                            rng: Random::default(),
+                            ran: Arc::clone(ran),
+                        };
+
+                        // loaded mutations start without any steps left
+                        mutation.reset();
+                        mutation.reseed(sub_seed(seed, stream));
+
+                        Box::new(mutation) as BoxedMutator<T>
+                    })
+                    .collect();
+
+                let sequence = Box::new(Sequence::new(mutations));
+                let mutation: BoxedMutator<T> = match lp.count {
+                    Some(count) => Box::new(Limit::new(count, sequence)),
+                    None => Box::new(Forever::new(sequence)),
+                };
+
+                Self {
+                    mutation,
+                    streams,
+                    ran,
+                }
+            })
+            .collect()
+    }
+}
+
+impl<T> MutatorLoop<T> {
+    fn finish_all(mutant: &mut T, loops: &mut [Self]) {
+        for lp in loops.iter_mut() {
+            lp.mutation.finish(mutant);
+        }
+    }
+
+    fn mutate_all(mutant: &mut T, loops: &mut [Self], info: StepInfo, map: Option<&Map>) {
+        for lp in loops.iter_mut() {
+            for ran in lp.ran.iter() {
+                ran.store(false, Ordering::Relaxed);
+            }
+
+            // every mutation runs with a stream of its own, see `LoopMutation`
+            let mut rng = Random::default();
+            let mut context = MutationContext {
+                info,
+                map,
+                rng: &mut rng,
+            };
+
+            lp.mutation.mutate(mutant, &mut context);
+        }
+    }
+
+    fn active_streams(&self) -> impl Iterator<Item = &String> {
+        self.streams
+            .iter()
+            .zip(self.ran.iter())
+            .filter(|(_, ran)| ran.load(Ordering::Relaxed))
+            .map(|(stream, _)| stream)
+    }
+}
+
+/// a mutation inside a loop, it hands the mutation its random stream and
+/// flags every step it runs in
+struct LoopMutation<T> {
+    mutation: BoxedMutator<T>,
+    rng: Random,
+    ran: Arc<AtomicBool>,
+}
+
+impl<T> Mutator<T> for LoopMutation<T> {
+    fn mutate(&mut self, mutant: &mut T, context: &mut MutationContext) -> MutationState {
+        self.ran.store(true, Ordering::Relaxed);
+
+        let mut context = MutationContext {
+            info: context.info,
+            map: context.map,
+            rng: &mut self.rng,
+        };
+
+        self.mutation.mutate(mutant, &mut context)
+    }
+
+    fn reset(&mut self) {
+        self.mutation.reset();
+    }
+
+    fn reseed(&mut self, seed: Seed) {
+        self.mutation.reseed(seed);
+        self.rng = Random::new(seed);
+    }
+
+    fn finish(&mut self, mutant: &mut T) {
+        self.mutation.finish(mutant);
+    }
+}
+
+#[cfg(feature = "serde")]
+mod persistence {
+    use std::{
+        fmt::Display,
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::{Pipeline, PIPELINE_VERSION};
+
+    #[derive(Debug)]
+    pub enum PipelineError {
+        Io(std::io::Error),
+        Json(serde_json::Error),
+        RonParse(ron::error::SpannedError),
+        RonWrite(ron::Error),
+        /// file extension is neither `json` nor `ron`
+        UnknownFormat(PathBuf),
+        UnsupportedVersion(u32),
+    }
+
+    impl Display for PipelineError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                PipelineError::Io(err) => write!(f, "{}", err),
+                PipelineError::Json(err) => write!(f, "invalid json pipeline: {}", err),
+                PipelineError::RonParse(err) => write!(f, "invalid ron pipeline: {}", err),
+                PipelineError::RonWrite(err) => write!(f, "failed to write ron: {}", err),
+                PipelineError::UnknownFormat(path) => write!(
+                    f,
+                    "unknown pipeline format of '{}', expected .json or .ron",
+                    path.display()
+                ),
+                PipelineError::UnsupportedVersion(version) => write!(
+                    f,
+                    "unsupported pipeline version {}, expected 1..={}",
+                    version, PIPELINE_VERSION
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for PipelineError {}
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum PipelineFormat {
+        Json,
+        Ron,
+    }
+
+    impl PipelineFormat {
+        pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, PipelineError> {
+            let path = path.as_ref();
+
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => Ok(Self::Json),
+                Some("ron") => Ok(Self::Ron),
+                _ => Err(PipelineError::UnknownFormat(path.to_path_buf())),
+            }
+        }
+    }
+
+    impl Pipeline {
+        pub fn from_text(data: &str, format: PipelineFormat) -> Result<Self, PipelineError> {
+            let pipeline: Pipeline = match format {
+                PipelineFormat::Json => serde_json::from_str(data).map_err(PipelineError::Json)?,
+                PipelineFormat::Ron => ron::from_str(data).map_err(PipelineError::RonParse)?,
+            };
+
+            if pipeline.version == 0 || pipeline.version > PIPELINE_VERSION {
+                return Err(PipelineError::UnsupportedVersion(pipeline.version));
+            }
+
+            Ok(pipeline)
+        }
+
+        pub fn to_text(&self, format: PipelineFormat) -> Result<String, PipelineError> {
+            match format {
+                PipelineFormat::Json => {
+                    serde_json::to_string_pretty(self).map_err(PipelineError::Json)
+                }
+                PipelineFormat::Ron => {
+                    ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                        .map_err(PipelineError::RonWrite)
+                }
+            }
+        }
+
+        pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PipelineError> {
+            let format = PipelineFormat::from_path(&path)?;
+            let data = fs::read_to_string(path).map_err(PipelineError::Io)?;
+
+            Self::from_text(&data, format)
+        }
+
+        pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PipelineError> {
+            let format = PipelineFormat::from_path(&path)?;
+
+            fs::write(path, self.to_text(format)?).map_err(PipelineError::Io)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+pub use persistence::{PipelineError, PipelineFormat};
@@ -100,24 +100,25 @@ pub fn angle(value: VectorView2) -> f32 {
     f32::atan2(value[[0]], value[[1]])
 }
 
-pub fn angle_direction(angle: f32) -> Direction {
-    let angle = (angle + 2.0 * PI + PI / 2.0) % (PI * 2.0);
+/// `None` for angles which aren't finite
+pub fn angle_direction(angle: f32) -> Option<Direction> {
+    let angle = (angle + 2.0 * PI + PI / 2.0).rem_euclid(PI * 2.0);
 
     if (0.0..=PI / 2.0).contains(&angle) {
-        Direction::Right
+        Some(Direction::Right)
     } else if (PI / 2.0..=PI).contains(&angle) {
-        Direction::Up
+        Some(Direction::Up)
     } else if (PI..=3.0 * PI / 2.0).contains(&angle) {
-        Direction::Left
+        Some(Direction::Left)
     } else if (3.0 * PI / 2.0..=2.0 * PI).contains(&angle) {
-        Direction::Down
+        Some(Direction::Down)
     } else {
-        panic!()
+        None
     }
 }
 
 #[inline]
-pub fn direction(value: VectorView2) -> Direction {
+pub fn direction(value: VectorView2) -> Option<Direction> {
     angle_direction(angle(value))
 }
 
@@ -138,8 +139,8 @@ pub fn straight_neighbors(pos: VectorView2) -> Vec<Vector2> {
         cur.clone().into(),
         cur.into(),
     ];
-    for i in 0..4 {
-        shift_by_direction(&mut neighbors[i], 1.0, Direction::from(i));
+    for (i, neighbor) in neighbors.iter_mut().enumerate() {
+        shift_by_direction(neighbor, 1.0, Direction::from(i));
     }
 
     neighbors
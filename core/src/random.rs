@@ -72,6 +72,15 @@ impl<T: Copy> RandomDist<T> {
         Self { config }
     }
 
+    /// at least one positive and no negative or non-finite weight, [`Self::weights`]
+    /// panics otherwise
+    pub fn is_valid(&self) -> bool {
+        let values = &self.config.values;
+
+        values.iter().all(|&ProbableValue(p, _)| p.is_finite() && p >= 0.0)
+            && values.iter().any(|&ProbableValue(p, _)| p > 0.0)
+    }
+
     pub fn weights(&self) -> WeightedAliasIndex<f32> {
         // TODO: cache weights somehow, config can be changed middleway though
         // is it necessary though? ^
@@ -92,6 +101,30 @@ pub fn seed_from_str(seed: &str) -> Seed {
     hash(seed.as_bytes())
 }
 
+/// derives an independent seed for a named stream, stable across runs and platforms
+pub fn sub_seed(seed: Seed, stream: &str) -> Seed {
+    hash(&[&seed.to_le_bytes()[..], stream.as_bytes()].concat())
+}
+
+/// smooth 2d value noise in [0, 1), `scale` is the distance between lattice points
+pub fn value_noise(seed: Seed, x: f32, y: f32, scale: f32) -> f32 {
+    let lattice = |x: i64, y: i64| {
+        let bytes = [seed.to_le_bytes(), x.to_le_bytes(), y.to_le_bytes()].concat();
+        (hash(&bytes) >> 40) as f32 / (1u64 << 24) as f32
+    };
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+
+    let (x, y) = (x / scale.max(1.0), y / scale.max(1.0));
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let (x0, y0) = (x0 as i64, y0 as i64);
+
+    let top = lattice(x0, y0) * (1.0 - tx) + lattice(x0 + 1, y0) * tx;
+    let bottom = lattice(x0, y0 + 1) * (1.0 - tx) + lattice(x0 + 1, y0 + 1) * tx;
+
+    top * (1.0 - ty) + bottom * ty
+}
+
 pub fn random_seed() -> Seed {
     SmallRng::from_entropy().next_u64()
 }
@@ -142,7 +175,7 @@ impl Random {
         self.prng.next_u32() as f32 / f32::MAX
     }
 
-    pub fn pick<'a, T>(&'a mut self, values: &'a [T]) -> &T {
+    pub fn pick<'a, T>(&'a mut self, values: &'a [T]) -> &'a T {
         &values[self.in_range(0..values.len())]
     }
 
@@ -0,0 +1,201 @@
+//! Brush stamps drawn as images.
+//!
+//! A stamp is a PNG file, e.g. one of `data/stamps`, turned into a brush mask.
+//! Pixels are covered either if they are opaque enough or, for images without
+//! transparency, if they are dark enough. The mask is rotated and mirrored
+//! once when it's loaded and resampled for every size the brush takes.
+//! With the `serde` feature a stamp is stored as its [`StampConfig`] and
+//! loaded again on deserialization:
+//!
+//! ```json
+//! { "path": "data/stamps/wedge.png", "channel": "Alpha", "threshold": 128, "rotation": 1, "mirror_x": false, "mirror_y": false }
+//! ```
+
+use std::{fmt::Display, path::PathBuf};
+
+use image::GenericImageView;
+use ndarray::Array2;
+
+#[derive(Debug)]
+pub enum StampError {
+    Image(PathBuf, image::ImageError),
+    /// no pixel of the image passed the threshold
+    Empty(PathBuf),
+}
+
+impl Display for StampError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StampError::Image(path, err) => write!(f, "{}: {}", path.display(), err),
+            StampError::Empty(path) => write!(f, "{}: stamp covers no pixel", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for StampError {}
+
+/// pixel value compared against the threshold
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StampChannel {
+    /// covered if the alpha is at least the threshold
+    #[default]
+    Alpha,
+    /// covered if the luminance is below the threshold, for dark strokes on light images
+    Luminance,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct StampConfig {
+    pub path: PathBuf,
+    pub channel: StampChannel,
+    pub threshold: u8,
+    /// quarter turns clockwise
+    pub rotation: u8,
+    /// mirrored after rotating
+    pub mirror_x: bool,
+    pub mirror_y: bool,
+}
+
+impl Default for StampConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::new(),
+            channel: StampChannel::Alpha,
+            threshold: 128,
+            rotation: 0,
+            mirror_x: false,
+            mirror_y: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "StampConfig", into = "StampConfig")
+)]
+pub struct BrushStamp {
+    config: StampConfig,
+    /// oriented mask, indexed `[x, y]`
+    mask: Array2<bool>,
+}
+
+impl BrushStamp {
+    pub fn load(config: StampConfig) -> Result<Self, StampError> {
+        let image =
+            image::open(&config.path).map_err(|err| StampError::Image(config.path.clone(), err))?;
+        let (width, height) = image.dimensions();
+
+        let mask = Array2::from_shape_fn((width as usize, height as usize), |(x, y)| {
+            let pixel = image.get_pixel(x as u32, y as u32).0;
+
+            match config.channel {
+                StampChannel::Alpha => pixel[3] >= config.threshold,
+                StampChannel::Luminance => {
+                    let luminance =
+                        0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32;
+
+                    luminance < config.threshold as f32
+                }
+            }
+        });
+
+        if !mask.iter().any(|&covered| covered) {
+            return Err(StampError::Empty(config.path));
+        }
+
+        Ok(Self::from_mask(config, mask))
+    }
+
+    /// `mask` is oriented as given by `config`, its path isn't read
+    pub fn from_mask(config: StampConfig, mut mask: Array2<bool>) -> Self {
+        for _ in 0..config.rotation % 4 {
+            let (width, height) = mask.dim();
+
+            mask = Array2::from_shape_fn((height, width), |(x, y)| mask[[y, height - 1 - x]]);
+        }
+
+        let (width, height) = mask.dim();
+
+        if config.mirror_x {
+            mask = Array2::from_shape_fn((width, height), |(x, y)| mask[[width - 1 - x, y]]);
+        }
+
+        if config.mirror_y {
+            mask = Array2::from_shape_fn((width, height), |(x, y)| mask[[x, height - 1 - y]]);
+        }
+
+        Self { config, mask }
+    }
+
+    pub fn config(&self) -> &StampConfig {
+        &self.config
+    }
+
+    pub fn mask(&self) -> &Array2<bool> {
+        &self.mask
+    }
+
+    /// mask resampled so its longer side is `size` tiles, a tile is covered
+    /// if at least half of the pixels it spans are, or if it's the best covered
+    /// one of a stamp that would be empty otherwise
+    pub fn rasterize(&self, size: usize) -> Array2<bool> {
+        let (width, height) = self.mask.dim();
+        let scale = size.max(1) as f32 / width.max(height) as f32;
+        let dim = |side: usize| ((side as f32 * scale).round() as usize).max(1);
+        let (target_width, target_height) = (dim(width), dim(height));
+
+        // pixels spanned by a tile, at least the nearest one
+        let span = |tile: usize, side: usize, target: usize| {
+            let start = tile as f32 * side as f32 / target as f32;
+            let end = (tile + 1) as f32 * side as f32 / target as f32;
+            let first = (start.floor() as usize).min(side - 1);
+
+            first..(end.ceil() as usize).clamp(first + 1, side)
+        };
+
+        let coverage = Array2::from_shape_fn((target_width, target_height), |(x, y)| {
+            let xs = span(x, width, target_width);
+            let ys = span(y, height, target_height);
+            let total = xs.len() * ys.len();
+            let covered = xs
+                .flat_map(|px| ys.clone().map(move |py| (px, py)))
+                .filter(|&(px, py)| self.mask[[px, py]])
+                .count();
+
+            covered as f32 / total as f32
+        });
+
+        let mut texture = coverage.map(|&covered| covered >= 0.5);
+
+        if !texture.iter().any(|&covered| covered) {
+            let best = coverage
+                .indexed_iter()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(index, _)| index)
+                .unwrap();
+
+            texture[best] = true;
+        }
+
+        texture
+    }
+}
+
+impl TryFrom<StampConfig> for BrushStamp {
+    type Error = StampError;
+
+    fn try_from(config: StampConfig) -> Result<Self, Self::Error> {
+        Self::load(config)
+    }
+}
+
+impl From<BrushStamp> for StampConfig {
+    fn from(stamp: BrushStamp) -> Self {
+        stamp.config
+    }
+}
@@ -0,0 +1,90 @@
+//! Recorded walks.
+//!
+//! Every generation records each step of the main walker and its branches:
+//! where the walker went, which waypoint it headed for, how big its brush was
+//! and which mutations ran. A [`GenerationTrace`] records the path for
+//! analysis and overlays. It doesn't hold the pipeline, scale factor, block
+//! mapping or post-processing settings, so it only regenerates the same map
+//! when its seed and waypoints are run through the pipeline it came from.
+
+use crate::{branch::BranchEnd, position::Direction, random::Seed};
+
+/// walker that left a trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WalkerRole {
+    Main,
+    /// spawned at the given step of the main walker
+    Branch {
+        origin: usize,
+        end: BranchEnd,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceStep {
+    /// carved position
+    pub position: [usize; 2],
+    /// direction the position was reached in
+    pub direction: Direction,
+    /// index of the waypoint the walker headed for
+    pub waypoint: usize,
+    /// size of the brush before it was turned to the heading
+    pub brush_size: usize,
+    /// random streams of the mutations that ran during the step, e.g. `walker/straight/0`
+    pub mutations: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WalkerTrace {
+    pub role: WalkerRole,
+    pub steps: Vec<TraceStep>,
+}
+
+impl WalkerTrace {
+    pub fn new(role: WalkerRole) -> Self {
+        Self {
+            role,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = [usize; 2]> + '_ {
+        self.steps.iter().map(|step| step.position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationTrace {
+    pub seed: Seed,
+    /// normalized waypoints of the main walker
+    pub waypoints: Vec<(f32, f32)>,
+    /// main walker first, then the branches in the order they ended
+    pub walkers: Vec<WalkerTrace>,
+}
+
+impl GenerationTrace {
+    pub fn main(&self) -> Option<&WalkerTrace> {
+        self.walkers
+            .iter()
+            .find(|trace| trace.role == WalkerRole::Main)
+    }
+
+    pub fn branches(&self) -> impl Iterator<Item = &WalkerTrace> {
+        self.walkers
+            .iter()
+            .filter(|trace| trace.role != WalkerRole::Main)
+    }
+
+    /// steps of all walkers together
+    pub fn len(&self) -> usize {
+        self.walkers.iter().map(|trace| trace.steps.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -1,6 +1,8 @@
+pub mod waypoints;
+
 use crate::position::{euclidian, from_raw, straight_neighbors, Direction, Vector2, VectorView2};
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NormalWaypoints {
     pub waypoints: Vec<(f32, f32)>,
@@ -22,8 +24,12 @@ pub struct Walker {
 
     current_step: usize,
     scale_factor: f32,
+    /// distance of scaled waypoints from the map's top left corner
+    offset: f32,
 
     raw_waypoints: Vec<(f32, f32)>,
+    /// position the walker started heading for its preferred waypoint at
+    segment_start: Option<Vector2>,
 }
 
 impl Walker {
@@ -34,7 +40,9 @@ impl Walker {
             next_state: None,
             current_step: 0,
             scale_factor,
+            offset: 0.0,
             raw_waypoints: Vec::new(),
+            segment_start: None,
         }
     }
 
@@ -42,6 +50,7 @@ impl Walker {
         self.states.clear();
         self.preferred_state = WalkerState::default();
         self.next_state = None;
+        self.segment_start = None;
     }
 
     pub fn set_waypoints(&mut self, raw_waypoints: Vec<(f32, f32)>) -> &mut Self {
@@ -56,6 +65,12 @@ impl Walker {
         self
     }
 
+    pub fn set_offset(&mut self, offset: f32) -> &mut Self {
+        self.offset = offset;
+
+        self
+    }
+
     pub fn get_waypoints(&self) -> &Vec<(f32, f32)> {
         &self.raw_waypoints
     }
@@ -98,10 +113,54 @@ impl Walker {
         self.states.last().unwrap()
     }
 
+    /// like [`Walker::current_state`], `None` before the first step
+    pub fn last_state(&self) -> Option<&WalkerState> {
+        self.states.last()
+    }
+
     pub fn preferred_state(&self) -> &WalkerState {
         &self.preferred_state
     }
 
+    /// state set by the mutators for the upcoming step
+    pub fn next_state(&self) -> Option<&WalkerState> {
+        self.next_state.as_ref()
+    }
+
+    /// 0 where the walker started heading for its preferred waypoint, 1 once it's there
+    pub fn waypoint_progress(&self, position: VectorView2) -> f32 {
+        let Some(start) = &self.segment_start else {
+            return 0.0;
+        };
+
+        let waypoint_pos = self.waypoint_position(self.preferred_state.waypoint);
+        let length = euclidian(start.view(), waypoint_pos.view());
+
+        if length <= f32::EPSILON {
+            return 1.0;
+        }
+
+        (1.0 - euclidian(position, waypoint_pos.view()) / length).clamp(0.0, 1.0)
+    }
+
+    /// 0 at the first waypoint, 1 at the last one, every segment between two
+    /// waypoints counts the same
+    pub fn progress(&self, position: VectorView2) -> f32 {
+        let waypoint_progress = self.waypoint_progress(position);
+
+        match (self.raw_waypoints.len(), self.preferred_state.waypoint) {
+            (0..=1, _) => waypoint_progress,
+            (_, 0) => 0.0,
+            (len, waypoint) => ((waypoint - 1) as f32 + waypoint_progress) / (len - 1) as f32,
+        }
+    }
+
+    /// waypoint in map coordinates
+    fn waypoint_position(&self, waypoint: usize) -> Vector2 {
+        from_raw(self.raw_waypoints[waypoint], self.scale_factor)
+            + Vector2::from(vec![self.offset, self.offset])
+    }
+
     pub fn step(&mut self, current_pos: VectorView2) -> usize {
         if self.next_state.is_none() {
             return 0;
@@ -113,28 +172,31 @@ impl Walker {
 
         self.states.push(self.next_state.take().unwrap());
 
+        let preferred_waypoint = self.preferred_state.waypoint;
         let current_state = self.states.last().unwrap();
 
-        if self.raw_waypoints.len() == current_state.waypoint + 1 {
-            // we reached last waypoint, halt
-            return 0;
-        }
-
         // check if we reached waypoint
-        let waypoint_pos = from_raw(
-            self.raw_waypoints[current_state.waypoint],
-            self.scale_factor,
-        ) + Vector2::from(vec![200.0, 200.0]);
-
-        println!("{}\t->\t{}", current_pos, waypoint_pos);
+        let waypoint_pos = self.waypoint_position(current_state.waypoint);
 
         let current_distance = euclidian(waypoint_pos.view(), current_pos.view());
 
         // TODO: make it configurable(?)
         if current_distance < 2.0 {
+            if self.raw_waypoints.len() == current_state.waypoint + 1 {
+                // we reached last waypoint, halt
+                return 0;
+            }
+
             // we reached waypoint, choose next
 
-            self.preferred_state.waypoint += 1;
+            self.preferred_state.waypoint = self
+                .preferred_state
+                .waypoint
+                .max(current_state.waypoint + 1);
+        }
+
+        if self.segment_start.is_none() || self.preferred_state.waypoint != preferred_waypoint {
+            self.segment_start = Some(current_pos.to_owned());
         }
 
         // calculate directions
@@ -0,0 +1,196 @@
+//! Seeded waypoint generators.
+//!
+//! A [`WaypointGenerator`] lays out [`NormalWaypoints`] inside the unit square
+//! following a [`WaypointPattern`]. Every pattern only proposes the heading and
+//! length of the next segment, the generator then enforces the limits shared
+//! by all of them: lengths are kept within `min_segment..=max_segment` and
+//! headings turn by at most `max_turn` from the previous segment. Segments
+//! that would leave the square are mirrored at its border, which wins over the
+//! turn limit, and shortened if they still don't fit.
+
+use std::f32::consts::{PI, TAU};
+
+use crate::random::{Random, Seed};
+
+use super::NormalWaypoints;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WaypointPattern {
+    /// turns by a random angle within the turn limit after every segment
+    RandomWalk,
+    /// follows `heading`, every segment deviates by up to `jitter` radians from it
+    Directed { jitter: f32 },
+    /// crosses the line along `heading` back and forth, reaching `amplitude` on
+    /// both sides `frequency` times per unit of length
+    ZigZag { amplitude: f32, frequency: f32 },
+    /// winds outwards from the center of the square in `turns` rotations
+    Spiral { turns: f32 },
+}
+
+impl WaypointPattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WaypointPattern::RandomWalk => "random_walk",
+            WaypointPattern::Directed { .. } => "directed",
+            WaypointPattern::ZigZag { .. } => "zigzag",
+            WaypointPattern::Spiral { .. } => "spiral",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WaypointGenerator {
+    pub pattern: WaypointPattern,
+    /// waypoints including the first one, at least 2
+    pub count: usize,
+    /// first waypoint, spirals start in the center instead
+    pub start: (f32, f32),
+    /// heading of the first segment in radians, 0 is right and y points down
+    pub heading: f32,
+    pub min_segment: f32,
+    pub max_segment: f32,
+    /// largest turn between two segments in radians
+    pub max_turn: f32,
+}
+
+impl Default for WaypointGenerator {
+    fn default() -> Self {
+        Self {
+            pattern: WaypointPattern::RandomWalk,
+            count: 6,
+            start: (0.0, 1.0),
+            heading: -PI / 4.0,
+            min_segment: 0.15,
+            max_segment: 0.35,
+            max_turn: PI / 2.0,
+        }
+    }
+}
+
+impl WaypointGenerator {
+    pub fn new(pattern: WaypointPattern) -> Self {
+        Self {
+            pattern,
+            ..Default::default()
+        }
+    }
+
+    /// same seed and settings always give the same waypoints
+    pub fn generate(&self, seed: Seed) -> NormalWaypoints {
+        let mut prng = Random::new(seed);
+        let count = self.count.max(2);
+        let (min_segment, max_segment) = (
+            self.min_segment.max(0.0),
+            self.max_segment.max(self.min_segment).max(0.0),
+        );
+
+        let start = match self.pattern {
+            WaypointPattern::Spiral { .. } => (0.5, 0.5),
+            _ => (self.start.0.clamp(0.0, 1.0), self.start.1.clamp(0.0, 1.0)),
+        };
+
+        let mut waypoints = vec![start];
+        let mut previous: Option<f32> = None;
+
+        for segment in 0..count - 1 {
+            let (heading, length) = self.propose(segment, count, previous, &mut prng);
+
+            let heading = match previous {
+                Some(previous) => {
+                    let max_turn = self.max_turn.abs();
+                    previous + wrap(heading - previous).clamp(-max_turn, max_turn)
+                }
+                None => heading,
+            };
+
+            let length = length.clamp(min_segment, max_segment);
+            let (x, y) = *waypoints.last().unwrap();
+            let (mut dx, mut dy) = (heading.cos() * length, heading.sin() * length);
+
+            // mirror at the border of the square
+            if !(0.0..=1.0).contains(&(x + dx)) {
+                dx = -dx;
+            }
+
+            if !(0.0..=1.0).contains(&(y + dy)) {
+                dy = -dy;
+            }
+
+            let next = ((x + dx).clamp(0.0, 1.0), (y + dy).clamp(0.0, 1.0));
+
+            previous = Some((next.1 - y).atan2(next.0 - x));
+            waypoints.push(next);
+        }
+
+        NormalWaypoints { waypoints }
+    }
+
+    /// heading and length of the `segment`th segment, before the limits apply
+    fn propose(
+        &self,
+        segment: usize,
+        count: usize,
+        previous: Option<f32>,
+        prng: &mut Random,
+    ) -> (f32, f32) {
+        let random_length = |prng: &mut Random| {
+            let min = self.min_segment.max(0.0);
+            prng.in_range(min..=self.max_segment.max(min))
+        };
+
+        match self.pattern {
+            WaypointPattern::RandomWalk => {
+                let max_turn = self.max_turn.abs();
+                let turn = prng.in_range(-max_turn..=max_turn);
+
+                (previous.unwrap_or(self.heading) + turn, random_length(prng))
+            }
+            WaypointPattern::Directed { jitter } => {
+                let jitter = jitter.abs();
+
+                (
+                    self.heading + prng.in_range(-jitter..=jitter),
+                    random_length(prng),
+                )
+            }
+            WaypointPattern::ZigZag {
+                amplitude,
+                frequency,
+            } => {
+                // from one side to the other is half a period, the first segment
+                // starts on the line and only covers half of it
+                let half_period = 0.5 / frequency.max(f32::EPSILON);
+                let (along, across) = match segment {
+                    0 => (half_period / 2.0, amplitude),
+                    _ => (half_period, 2.0 * amplitude),
+                };
+                let side = if segment.is_multiple_of(2) { 1.0 } else { -1.0 };
+
+                (
+                    self.heading + side * across.atan2(along),
+                    along.hypot(across),
+                )
+            }
+            WaypointPattern::Spiral { turns } => {
+                let point = |index: usize| {
+                    let t = index as f32 / (count - 1) as f32;
+                    let (angle, radius) = (self.heading + TAU * turns * t, 0.5 * t);
+
+                    (radius * angle.cos(), radius * angle.sin())
+                };
+
+                let ((fx, fy), (tx, ty)) = (point(segment), point(segment + 1));
+
+                ((ty - fy).atan2(tx - fx), (tx - fx).hypot(ty - fy))
+            }
+        }
+    }
+}
+
+/// angle in `-PI..PI`
+fn wrap(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
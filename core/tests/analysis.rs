@@ -0,0 +1,146 @@
+use mapgen_core::{
+    analysis::{BoundingBox, MapReport},
+    block::{BlockMapping, BlockType},
+    generator::Generator,
+    map::RoomConfig,
+    mutations::walker::straight::StraightWalkerMutation,
+    pipeline::{MutationLoop, Pipeline, WalkerMutation},
+};
+use ndarray::Array2;
+use twmap::GameTile;
+
+/// `#` hookable, `.` empty, `*` freeze, `S` spawn and `F` finish, one string per row
+fn tiles(rows: &[&str]) -> Array2<GameTile> {
+    let blocks = BlockMapping::default();
+    let mut tiles = Array2::from_elem(
+        (rows[0].len(), rows.len()),
+        blocks.tile(BlockType::Hookable),
+    );
+
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let block = match c {
+                '.' => BlockType::Empty,
+                '*' => BlockType::Freeze,
+                'S' => BlockType::Spawn,
+                'F' => BlockType::Finish,
+                _ => BlockType::Hookable,
+            };
+
+            tiles[[x, y]] = blocks.tile(block);
+        }
+    }
+
+    tiles
+}
+
+#[test]
+fn corridor_with_dead_end() {
+    let report = MapReport::from_tiles(
+        &tiles(&[
+            "##############",
+            "#S..........*#",
+            "#######.####.#",
+            "#######.####.#",
+            "#######.####.#",
+            "#######.####.#",
+            "############F#",
+            "##############",
+        ]),
+        &BlockMapping::default(),
+        None,
+    );
+
+    assert_eq!(report.path_length, Some(16));
+    assert_eq!(report.turns, Some(1));
+    assert_eq!(report.dead_ends, Some(1));
+    assert_eq!(report.corridor_widths, vec![0, 21]);
+    assert_eq!(report.mean_corridor_width(), 1.0);
+    assert_eq!(
+        report.bounding_box,
+        Some(BoundingBox {
+            x: 1,
+            y: 1,
+            width: 12,
+            height: 6
+        })
+    );
+    assert_eq!(report.freeze_ratio, 1.0 / 72.0);
+    assert_eq!(report.empty_ratio, 18.0 / 72.0);
+    assert_eq!(report.hookable_ratio, 51.0 / 72.0);
+}
+
+#[test]
+fn without_spawn() {
+    let report = MapReport::from_tiles(
+        &tiles(&["###", "#.#", "###"]),
+        &BlockMapping::default(),
+        None,
+    );
+
+    assert_eq!(report.path_length, None);
+    assert_eq!(report.turns, None);
+    assert_eq!(report.dead_ends, None);
+    assert_eq!(report.corridor_widths, vec![0, 1]);
+}
+
+#[test]
+fn ends_stand_in_for_spawn_and_finish() {
+    let map = tiles(&["#######", "#.....#", "#####.#", "#######"]);
+    let blocks = BlockMapping::default();
+
+    let report = MapReport::from_tiles(&map, &blocks, Some([(1, 1), (5, 2)]));
+    assert_eq!(report.path_length, Some(5));
+    assert_eq!(report.dead_ends, Some(0));
+
+    // ends inside walls are ignored
+    let report = MapReport::from_tiles(&map, &blocks, Some([(0, 0), (5, 2)]));
+    assert_eq!(report.path_length, None);
+
+    // a spawn tile wins over the start
+    let map = tiles(&["#######", "#..S..#", "#####.#", "#######"]);
+    let report = MapReport::from_tiles(&map, &blocks, Some([(1, 1), (5, 2)]));
+    assert_eq!(report.path_length, Some(3));
+}
+
+#[test]
+fn generated_map_without_rooms_has_way() {
+    let pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    let (_, report, trace) = generator.generate(vec![(0.0, 1.0), (1.0, 0.0)], 0).unwrap();
+    let length = report.path_length.unwrap();
+
+    assert!(report.turns.is_some() && report.dead_ends.is_some());
+
+    // the way can only be shorter than the walk along it
+    assert!(length > 0 && length <= trace.main().unwrap().steps.len());
+}
+
+#[test]
+fn generated_map_has_way() {
+    let mut pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 40.0;
+    pipeline.generator.rooms = Some(RoomConfig::default());
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    let (_, report, _) = generator.generate(vec![(0.0, 1.0), (1.0, 0.0)], 0).unwrap();
+
+    assert!(report.path_length.is_some());
+    assert!(report.bounding_box.is_some());
+}
@@ -0,0 +1,165 @@
+use mapgen_core::automapper::{Automapper, AutomapperError};
+use ndarray::Array2;
+use twmap::{Tile, TileFlags};
+
+/// `#` is tile 1, everything else is empty, one string per row
+fn layer(rows: &[&str]) -> Array2<Tile> {
+    Array2::from_shape_fn((rows.len(), rows[0].len()), |(y, x)| {
+        let id = (rows[y].as_bytes()[x] == b'#') as u8;
+
+        Tile::new(id, TileFlags::empty())
+    })
+}
+
+fn ids(tiles: &Array2<Tile>) -> Vec<Vec<u8>> {
+    tiles
+        .rows()
+        .into_iter()
+        .map(|row| row.iter().map(|tile| tile.id).collect())
+        .collect()
+}
+
+#[test]
+fn shipped_rules_parse() {
+    for entry in std::fs::read_dir("../data/rules").unwrap() {
+        let path = entry.unwrap().path();
+        let automapper = Automapper::load(&path).unwrap();
+
+        assert!(!automapper.configs().is_empty(), "{}", path.display());
+    }
+}
+
+#[test]
+fn default_rule_keeps_empty_tiles() {
+    let automapper = Automapper::parse("[Fill]\nIndex 5\n").unwrap();
+    let mut tiles = layer(&["#.", ".#"]);
+
+    automapper.config(0).unwrap().apply(&mut tiles, 0);
+
+    assert_eq!(ids(&tiles), vec![vec![5, 0], vec![0, 5]]);
+}
+
+#[test]
+fn pos_rules() {
+    let rules = "
+        [Edges]
+        Index 1
+
+        # top
+        Index 2
+        Pos 0 -1 EMPTY
+
+        # left, flipped
+        Index 3 XFLIP
+        Pos -1 0 EMPTY
+        Pos 0 -1 INDEX 1 OR 2
+    ";
+
+    let automapper = Automapper::parse(rules).unwrap();
+    let mut tiles = layer(&["....", ".###", ".###"]);
+
+    automapper
+        .find_config("Edges")
+        .unwrap()
+        .apply(&mut tiles, 0);
+
+    assert_eq!(
+        ids(&tiles),
+        vec![vec![0, 0, 0, 0], vec![0, 2, 2, 2], vec![0, 3, 1, 1]]
+    );
+    assert_eq!(tiles[[2, 1]].flags, TileFlags::FLIP_X);
+    assert_eq!(tiles[[1, 1]].flags, TileFlags::empty());
+}
+
+#[test]
+fn runs_and_layer_copy() {
+    // with a layer copy rules only see the tiles from before the run
+    let copied = "[A]\nIndex 2\nPos -1 0 FULL\n";
+    let mut tiles = layer(&[".###"]);
+
+    Automapper::parse(copied).unwrap().configs()[0].apply(&mut tiles, 0);
+    assert_eq!(ids(&tiles), vec![vec![0, 1, 2, 2]]);
+
+    // without it every change is visible right away
+    let in_place = "[A]\nNewRun\nNoLayerCopy\nIndex 2\nPos -1 0 NOTINDEX 2\nPos -1 0 FULL\n";
+    let mut tiles = layer(&[".###"]);
+
+    Automapper::parse(in_place).unwrap().configs()[0].apply(&mut tiles, 0);
+    assert_eq!(ids(&tiles), vec![vec![0, 1, 2, 1]]);
+}
+
+#[test]
+fn no_default_rule() {
+    let automapper = Automapper::parse("[A]\nIndex 7\nPos 1 0 FULL\nNoDefaultRule\n").unwrap();
+    let mut tiles = layer(&[".#.."]);
+
+    automapper.configs()[0].apply(&mut tiles, 0);
+
+    // outside of the layer isn't empty
+    assert_eq!(ids(&tiles), vec![vec![7, 1, 0, 7]]);
+
+    let automapper = Automapper::parse("[A]\nIndex 7\nPos 1 0 NOTINDEX -1\n").unwrap();
+    let mut tiles = layer(&["##"]);
+
+    automapper.configs()[0].apply(&mut tiles, 0);
+
+    assert_eq!(ids(&tiles), vec![vec![7, 1]]);
+}
+
+#[test]
+fn rotation_is_compared() {
+    let automapper =
+        Automapper::parse("[A]\nIndex 4 ROTATE\n\nIndex 6\nPos -1 0 INDEX 4 ROTATE\n").unwrap();
+    let mut tiles = layer(&["##"]);
+
+    automapper.configs()[0].apply(&mut tiles, 0);
+
+    // rules of the same run see the layer from before it, so nothing is rotated yet
+    assert_eq!(ids(&tiles), vec![vec![4, 4]]);
+
+    let automapper =
+        Automapper::parse("[A]\nIndex 4 ROTATE\nNewRun\nIndex 6\nPos -1 0 INDEX 4 ROTATE\n")
+            .unwrap();
+    let mut tiles = layer(&["##"]);
+
+    automapper.configs()[0].apply(&mut tiles, 0);
+
+    assert_eq!(ids(&tiles), vec![vec![4, 6]]);
+    assert_eq!(tiles[[0, 0]].flags, TileFlags::ROTATE);
+}
+
+#[test]
+fn random_depends_on_seed() {
+    let automapper = Automapper::parse("[A]\nIndex 1\nIndex 2\nRandom 50%\n").unwrap();
+    let apply = |seed| {
+        let mut tiles = layer(&["################"; 16]);
+        automapper.configs()[0].apply(&mut tiles, seed);
+        ids(&tiles)
+    };
+
+    let twos = apply(0).iter().flatten().filter(|&&id| id == 2).count();
+
+    assert_eq!(apply(0), apply(0));
+    assert_ne!(apply(0), apply(1));
+    assert!((64..192).contains(&twos), "{}", twos);
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(
+        Automapper::parse("Index 1"),
+        Err(AutomapperError::Parse { line: 1, .. })
+    ));
+    assert!(matches!(
+        Automapper::parse("[A]\nPos 0 0 FULL"),
+        Err(AutomapperError::Parse { line: 2, .. })
+    ));
+    assert!(matches!(
+        Automapper::parse("[A]\nIndex 1\nPos x 0 FULL"),
+        Err(AutomapperError::Parse { line: 3, .. })
+    ));
+    assert!(matches!(
+        Automapper::parse("[A]\nIndex 1\nRandom 0"),
+        Err(AutomapperError::Parse { line: 3, .. })
+    ));
+}
@@ -0,0 +1,75 @@
+use mapgen_core::{
+    block::{BlockInfo, BlockMapping, BlockType, DesignLayer},
+    generator::Generator,
+    mutations::walker::straight::StraightWalkerMutation,
+    pipeline::{MutationLoop, Pipeline, WalkerMutation},
+};
+use twmap::GameLayer;
+
+#[test]
+fn default_mapping() {
+    let blocks = BlockMapping::default();
+
+    assert_eq!(blocks.tile_id(BlockType::Freeze), 9);
+    assert_eq!(blocks.block_type(11), Some(BlockType::DeepFreeze));
+    assert_eq!(blocks.block_type(2), None);
+
+    // empty tiles aren't part of any design layer
+    assert_eq!(blocks.design_layer(0), None);
+    assert_eq!(blocks.design_layer(9), Some(DesignLayer::Freeze));
+    assert_eq!(blocks.design_layer(11), Some(DesignLayer::Freeze));
+    assert_eq!(blocks.design_layer(3), Some(DesignLayer::Unhookable));
+
+    assert!(blocks.is_solid(1) && blocks.is_solid(3));
+    assert!(!blocks.is_solid(9) && !blocks.is_solid(2));
+}
+
+#[test]
+fn overrides() {
+    let mut blocks = BlockMapping::default();
+    let unhookable_walls = BlockInfo {
+        tile_id: 3,
+        design_layer: Some(DesignLayer::Unhookable),
+    };
+
+    blocks.set_info(BlockType::Hookable, unhookable_walls);
+
+    assert_eq!(blocks.tile_id(BlockType::Hookable), 3);
+    assert_eq!(blocks.design_layer(3), Some(DesignLayer::Unhookable));
+
+    blocks.set_info(BlockType::Hookable, BlockType::Hookable.default_info());
+
+    assert_eq!(blocks, BlockMapping::default());
+}
+
+#[test]
+fn generator_uses_mapping() {
+    let mut pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 40.0;
+    pipeline.generator.blocks.set_info(
+        BlockType::Hookable,
+        BlockInfo {
+            tile_id: 3,
+            design_layer: Some(DesignLayer::Unhookable),
+        },
+    );
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    let (map, _, _) = generator.generate(vec![(0.0, 1.0), (1.0, 0.0)], 0).unwrap();
+    let tiles = map
+        .find_physics_layer::<GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref();
+
+    assert!(tiles.iter().all(|tile| tile.id == 0 || tile.id == 3));
+    assert!(tiles.iter().any(|tile| tile.id == 3));
+}
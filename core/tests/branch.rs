@@ -0,0 +1,247 @@
+use mapgen_core::{
+    block::{BlockMapping, BlockType},
+    branch::{BranchConfig, BranchEnd},
+    generator::Generator,
+    mutations::walker::{straight::StraightWalkerMutation, weighted::WeightedWalkerMutation},
+    pipeline::{BranchSettings, MutationLoop, Pipeline, WalkerMutation},
+    random::Seed,
+    trace::{GenerationTrace, WalkerRole},
+};
+use twmap::{GameLayer, TwMap};
+
+const WAYPOINTS: [(f32, f32); 3] = [(0.0, 1.0), (0.5, 0.5), (1.0, 0.0)];
+
+fn pipeline(config: Option<BranchConfig>) -> Pipeline {
+    let mut pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 60.0;
+    pipeline.generator.branches = config.map(|config| BranchSettings {
+        config,
+        ..Default::default()
+    });
+
+    pipeline
+}
+
+fn generate(pipeline: &Pipeline, seed: Seed) -> (TwMap, GenerationTrace) {
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    let (map, _, trace) = generator.generate(WAYPOINTS.to_vec(), seed).unwrap();
+
+    (map, trace)
+}
+
+fn branch_ends(trace: &GenerationTrace) -> Vec<BranchEnd> {
+    trace
+        .branches()
+        .filter_map(|branch| match branch.role {
+            WalkerRole::Branch { end, .. } => Some(end),
+            WalkerRole::Main => None,
+        })
+        .collect()
+}
+
+fn count(map: &TwMap, block: BlockType) -> usize {
+    let id = BlockMapping::default().tile_id(block);
+
+    map.find_physics_layer::<GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref()
+        .iter()
+        .filter(|tile| tile.id == id)
+        .count()
+}
+
+#[test]
+fn main_walker_only() {
+    let (_, trace) = generate(&pipeline(None), 0);
+
+    assert_eq!(trace.walkers.len(), 1);
+    assert_eq!(trace.walkers[0].role, WalkerRole::Main);
+    assert!(!trace.walkers[0].steps.is_empty());
+}
+
+#[test]
+fn dead_ends() {
+    let config = BranchConfig {
+        probability: 0.2,
+        max_active: 2,
+        lifetime: 8,
+        rejoin_chance: 0.0,
+        brush_size: 3,
+    };
+
+    let (map, trace) = generate(&pipeline(Some(config)), 0);
+    let ends = branch_ends(&trace);
+    let main = trace.main().unwrap();
+
+    assert_eq!(trace.walkers[0].role, WalkerRole::Main);
+    assert!(!ends.is_empty());
+    assert!(ends.iter().all(|&end| end == BranchEnd::DeadEnd));
+
+    for branch in trace.branches() {
+        let WalkerRole::Branch { origin, .. } = branch.role else {
+            panic!("{:?}", branch.role);
+        };
+
+        assert!(branch.steps.len() <= config.lifetime);
+        assert!(origin > 0 && origin <= main.steps.len());
+    }
+
+    // dead ends are capped with freeze
+    assert!(count(&map, BlockType::Freeze) > 0);
+    assert_eq!(count(&generate(&pipeline(None), 0).0, BlockType::Freeze), 0);
+}
+
+#[test]
+fn rejoining_branches() {
+    let config = BranchConfig {
+        probability: 0.05,
+        max_active: 1,
+        lifetime: 200,
+        rejoin_chance: 1.0,
+        brush_size: 2,
+    };
+
+    let mut generator = Generator::new();
+    pipeline(Some(config)).setup(&mut generator);
+
+    let (_, _, trace) = generator.generate(WAYPOINTS.to_vec(), 3).unwrap();
+    assert!(branch_ends(&trace).contains(&BranchEnd::Rejoin));
+
+    // rejoining branches arrive at a later waypoint of the main walker
+    let margin = generator.margin() as f32;
+    let waypoints: Vec<_> = WAYPOINTS[1..]
+        .iter()
+        .map(|&(x, y)| (x * 60.0 + margin, y * 60.0 + margin))
+        .collect();
+
+    for branch in trace.branches().filter(|branch| {
+        matches!(
+            branch.role,
+            WalkerRole::Branch {
+                end: BranchEnd::Rejoin,
+                ..
+            }
+        )
+    }) {
+        let [x, y] = branch.steps.last().unwrap().position;
+
+        assert!(waypoints
+            .iter()
+            .any(|&(wx, wy)| (x as f32 - wx).hypot(y as f32 - wy) < 3.0));
+    }
+}
+
+#[test]
+fn branches_with_own_mutations() {
+    let mut pipeline = pipeline(Some(BranchConfig {
+        probability: 0.1,
+        ..Default::default()
+    }));
+
+    pipeline.generator.branches.as_mut().unwrap().walker =
+        vec![MutationLoop::endless(vec![WalkerMutation::Weighted(
+            WeightedWalkerMutation::from_weights(1, [0.6, 0.2, 0.2, 0.0], 0.5),
+        )])];
+
+    let (first, first_trace) = generate(&pipeline, 5);
+    let (second, second_trace) = generate(&pipeline, 5);
+
+    assert!(!branch_ends(&first_trace).is_empty());
+    assert_eq!(first_trace, second_trace);
+    assert_eq!(first, second);
+
+    // branch steps name the mutations of the branch's own pipeline
+    let branch = first_trace.branches().next().unwrap();
+    assert!(branch
+        .steps
+        .iter()
+        .all(|step| step.mutations == ["walker/weighted/0"]));
+}
+
+#[test]
+fn dead_ends_stay_inside_non_square_maps() {
+    // four times as wide as high, twice with the margin
+    let waypoints = vec![(0.0, 0.25), (0.5, 0.0), (1.0, 0.25)];
+    let mut pipeline = pipeline(Some(BranchConfig {
+        probability: 0.2,
+        max_active: 4,
+        lifetime: 200,
+        rejoin_chance: 0.0,
+        ..Default::default()
+    }));
+    pipeline.generator.scale_factor = 200.0;
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    let margin = generator.margin();
+    let (map, _, trace) = generator.generate(waypoints, 11).unwrap();
+    let (width, height) = map
+        .find_physics_layer::<GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref()
+        .dim();
+
+    assert!(width > 2 * height);
+    assert!(trace.branches().count() > 0);
+
+    // dead ends head for targets inside the waypoint area and stop within
+    // reach of them
+    for branch in trace.branches() {
+        let Some(last) = branch.steps.last() else {
+            continue;
+        };
+
+        let [x, y] = last.position;
+
+        assert!((margin - 2..=width - margin + 2).contains(&x), "{:?}", last);
+        assert!(
+            (margin - 2..=height - margin + 2).contains(&y),
+            "{:?}",
+            last
+        );
+    }
+}
+
+#[test]
+fn dead_ends_at_the_border_keep_the_main_path_open() {
+    // the main walker runs along the top border, branches heading up fail
+    // right away
+    let mut pipeline = pipeline(Some(BranchConfig {
+        probability: 0.2,
+        max_active: 4,
+        lifetime: 40,
+        rejoin_chance: 0.0,
+        brush_size: 5,
+    }));
+    pipeline.generator.padding = 0;
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+    generator.begin(vec![(0.0, 0.0), (1.0, 0.0)], 3).unwrap();
+
+    while generator.step().unwrap() {}
+
+    let session = generator.session().unwrap();
+    let freeze = BlockMapping::default().tile_id(BlockType::Freeze);
+    let stuck = session
+        .ended_branches()
+        .filter(|branch| branch.steps.len() < 3)
+        .count();
+
+    assert!(stuck > 0);
+    assert!(session
+        .main()
+        .positions()
+        .all(|[x, y]| { session.map().game_tile((x, y)).unwrap().id != freeze }));
+}
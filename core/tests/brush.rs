@@ -0,0 +1,304 @@
+use mapgen_core::{
+    block::{BlockMapping, BlockType},
+    brush::{Brush, BrushOrientation, BrushRim, BrushShape},
+    generator::Generator,
+    mutations::walker::straight::StraightWalkerMutation,
+    pipeline::{MutationLoop, Pipeline, WalkerMutation},
+    position::Direction,
+};
+use ndarray::{arr1, Array2};
+use twmap::{GameTile, TileFlags};
+
+/// `#` for covered tiles, one string per row
+fn rows(texture: &Array2<bool>) -> Vec<String> {
+    let (width, height) = texture.dim();
+
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| if texture[[x, y]] { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn scaled_circle_is_round() {
+    let mut brush = Brush::new();
+    let mut tiles = Array2::from_elem((20, 20), GameTile::new(0, TileFlags::empty()));
+
+    brush.apply_scale(20.0);
+    brush
+        .apply(
+            &mut tiles,
+            arr1(&[10.0, 10.0]),
+            GameTile::new(1, TileFlags::empty()),
+        )
+        .unwrap();
+
+    let carved = tiles.map(|tile| tile.id == 1);
+
+    assert_eq!(carved.iter().filter(|&&tile| tile).count(), 316);
+    assert!(!carved[[0, 0]] && !carved[[19, 19]]);
+    assert!(carved[[0, 10]] && carved[[10, 0]] && carved[[19, 10]]);
+}
+
+#[test]
+fn shapes() {
+    assert_eq!(
+        rows(&BrushShape::default().rasterize(5)),
+        vec![".###.", "#####", "#####", "#####", ".###."]
+    );
+    assert_eq!(
+        rows(&BrushShape::Circle { circularity: 0.0 }.rasterize(5)),
+        vec!["#####"; 5]
+    );
+    assert_eq!(
+        rows(&BrushShape::Rectangle { aspect: 0.4 }.rasterize(5)),
+        vec!["#####"; 2]
+    );
+    assert_eq!(
+        rows(&BrushShape::Diamond.rasterize(5)),
+        vec!["..#..", ".###.", "#####", ".###.", "..#.."]
+    );
+    assert_eq!(
+        rows(&BrushShape::Superellipse { exponent: 1.0 }.rasterize(5)),
+        rows(&BrushShape::Diamond.rasterize(5))
+    );
+
+    // every shape covers its center and stays within the size
+    let blob = BrushShape::Blob {
+        seed: 3,
+        roughness: 0.5,
+    };
+
+    for shape in [
+        BrushShape::default(),
+        BrushShape::Superellipse { exponent: 4.0 },
+        blob.clone(),
+    ] {
+        for size in 1..30 {
+            let texture = shape.rasterize(size);
+
+            assert_eq!(texture.dim(), (size, size), "{:?}", shape);
+            assert!(texture[[size / 2, size / 2]], "{:?} {}", shape, size);
+        }
+    }
+
+    let full = BrushShape::default().rasterize(21);
+    let count = |texture: &Array2<bool>| texture.iter().filter(|&&tile| tile).count();
+
+    assert!(count(&blob.rasterize(21)) < count(&full));
+    assert_ne!(
+        blob.rasterize(21),
+        BrushShape::Blob {
+            seed: 4,
+            roughness: 0.5
+        }
+        .rasterize(21)
+    );
+}
+
+/// `.` empty, `*` freeze, `U` unhookable and `#` hookable, one string per row
+fn blocks(tiles: &Array2<GameTile>) -> Vec<String> {
+    let mapping = BlockMapping::default();
+    let (width, height) = tiles.dim();
+
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| match mapping.block_type(tiles[[x, y]].id) {
+                    Some(BlockType::Empty) => '.',
+                    Some(BlockType::Freeze) => '*',
+                    Some(BlockType::Unhookable) => 'U',
+                    _ => '#',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn rims() {
+    let mapping = BlockMapping::default();
+    let mut brush = Brush::from_shape(BrushShape::Rectangle { aspect: 1.0 });
+    let mut tiles = Array2::from_elem((9, 7), mapping.tile(BlockType::Hookable));
+
+    brush.apply_scale(1.0);
+    brush.set_rims(vec![
+        BrushRim {
+            block: BlockType::Freeze,
+            width: 1,
+        },
+        BrushRim {
+            block: BlockType::Unhookable,
+            width: 1,
+        },
+    ]);
+
+    assert_eq!(brush.rim_width(), 2);
+    assert_eq!(brush.materials().dim(), (5, 5));
+
+    brush
+        .apply_materials(&mut tiles, arr1(&[3.0, 3.0]), &mapping)
+        .unwrap();
+
+    assert_eq!(
+        blocks(&tiles),
+        vec![
+            "#########",
+            "#UUUUU###",
+            "#U***U###",
+            "#U*.*U###",
+            "#U***U###",
+            "#UUUUU###",
+            "#########",
+        ]
+    );
+
+    // moving on carves through the rims, but they never refill the corridor
+    brush
+        .apply_materials(&mut tiles, arr1(&[4.0, 3.0]), &mapping)
+        .unwrap();
+
+    assert_eq!(
+        blocks(&tiles),
+        vec![
+            "#########",
+            "#UUUUUU##",
+            "#U****U##",
+            "#U*..*U##",
+            "#U****U##",
+            "#UUUUUU##",
+            "#########",
+        ]
+    );
+}
+
+#[test]
+fn generated_with_rims() {
+    let mut pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 30.0;
+    pipeline.generator.padding = 0;
+    pipeline.generator.brush_rims = vec![BrushRim {
+        block: BlockType::Freeze,
+        width: 2,
+    }];
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    assert_eq!(generator.margin(), 3);
+
+    let (map, _, _) = generator.generate(vec![(0.0, 1.0), (1.0, 0.0)], 0).unwrap();
+    let game = map
+        .find_physics_layer::<twmap::GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref();
+
+    assert!(game.iter().any(|tile| tile.id == 9));
+}
+
+/// wedge facing right
+fn wedge() -> Brush {
+    let texture = Array2::from_shape_fn((3, 3), |(x, y)| x == 0 || y == 1);
+
+    Brush::from_texture(texture)
+}
+
+#[test]
+fn mirror_orientation() {
+    let mut brush = wedge();
+
+    brush.set_orientation(BrushOrientation::Mirror);
+    assert_eq!(rows(brush.used_texture()), vec!["#..", "###", "#.."]);
+
+    brush.follow(Direction::Left);
+    assert_eq!(rows(brush.used_texture()), vec!["..#", "###", "..#"]);
+
+    brush.follow(Direction::Down);
+    assert_eq!(rows(brush.used_texture()), vec!["#..", "###", "#.."]);
+}
+
+#[test]
+fn rotate_orientation() {
+    let mut brush = wedge();
+
+    brush.set_orientation(BrushOrientation::Rotate { smoothing: 0.0 });
+
+    brush.follow(Direction::Down);
+    assert_eq!(rows(brush.used_texture()), vec!["###", ".#.", ".#."]);
+
+    brush.follow(Direction::Up);
+    assert_eq!(rows(brush.used_texture()), vec![".#.", ".#.", "###"]);
+
+    brush.follow(Direction::Left);
+    assert_eq!(rows(brush.used_texture()), vec!["..#", "###", "..#"]);
+
+    // a scaled brush keeps its heading
+    brush.apply_scale(2.0);
+    assert_eq!(brush.used_texture().dim(), (6, 6));
+    assert!(brush.used_texture()[[5, 0]] && !brush.used_texture()[[0, 0]]);
+}
+
+#[test]
+fn smooth_rotation() {
+    let mut brush = Brush::from_shape(BrushShape::Rectangle { aspect: 0.2 });
+
+    brush.set_orientation(BrushOrientation::Rotate { smoothing: 0.5 });
+    brush.apply_scale(10.0);
+
+    brush.follow(Direction::Right);
+    assert_eq!(brush.heading(), Some(0.0));
+    assert_eq!(brush.used_texture().dim(), (10, 2));
+
+    // half of the way to down on every step
+    brush.follow(Direction::Down);
+    assert!((brush.heading().unwrap() - std::f32::consts::FRAC_PI_4).abs() < 1e-5);
+    assert_eq!(brush.used_texture().dim(), (9, 9));
+
+    brush.follow(Direction::Down);
+    brush.follow(Direction::Down);
+    assert!(brush.used_texture().dim().0 < brush.used_texture().dim().1);
+
+    // the shortest way from right to up is counterclockwise
+    let mut brush = Brush::new();
+
+    brush.set_orientation(BrushOrientation::Rotate { smoothing: 0.5 });
+    brush.follow(Direction::Right);
+    brush.follow(Direction::Up);
+    assert!(brush.heading().unwrap() < 0.0);
+}
+
+#[test]
+fn rotated_brush_fits() {
+    let mut pipeline = Pipeline {
+        brush: vec![MutationLoop::endless(vec![
+            mapgen_core::pipeline::BrushMutation::Transition(
+                mapgen_core::mutations::brush::transition::TransitionBrushMutation::new(9, 9, 1),
+            ),
+        ])],
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 30.0;
+    pipeline.generator.padding = 0;
+    pipeline.generator.brush_shape = BrushShape::Rectangle { aspect: 0.4 };
+    pipeline.generator.brush_orientation = BrushOrientation::Rotate { smoothing: 0.8 };
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    assert_eq!(generator.margin(), 7);
+    assert!(generator.generate(vec![(0.0, 1.0), (1.0, 0.0)], 0).is_ok());
+}
@@ -0,0 +1,395 @@
+use mapgen_core::{
+    brush::Brush,
+    generator::{Generator, StepHandler},
+    map::Map,
+    mutations::{
+        brush::pulse::PulseBrushMutation,
+        combinators::{BoxedMutator, Chance, Forever, Limit, Parallel, Repeat, Sequence, Until},
+        walker::{random::RandomWalkerMutation, straight::StraightWalkerMutation},
+        MutationContext, MutationState, Mutator, StepInfo,
+    },
+    pipeline::{BrushMutation, MutationLoop, Pipeline, WalkerMutation},
+    random::{sub_seed, Random, Seed},
+    walker::Walker,
+};
+use twmap::GameLayer;
+
+/// writes its name into the log for `overall` steps
+struct Steps {
+    name: &'static str,
+    overall: usize,
+    steps: usize,
+}
+
+impl Mutator<Vec<&'static str>> for Steps {
+    fn mutate(
+        &mut self,
+        mutant: &mut Vec<&'static str>,
+        _context: &mut MutationContext,
+    ) -> MutationState {
+        if self.steps == 0 {
+            return MutationState::Finished;
+        }
+
+        mutant.push(self.name);
+        self.steps -= 1;
+
+        MutationState::Processing
+    }
+
+    fn reset(&mut self) {
+        self.steps = self.overall;
+    }
+}
+
+fn steps(name: &'static str, overall: usize) -> BoxedMutator<Vec<&'static str>> {
+    Box::new(Steps {
+        name,
+        overall,
+        steps: overall,
+    })
+}
+
+/// log of `n` steps and the state of the last one
+fn run(
+    mutator: &mut impl Mutator<Vec<&'static str>>,
+    n: usize,
+) -> (Vec<&'static str>, MutationState) {
+    let mut log = Vec::new();
+    let mut state = MutationState::Processing;
+    let mut rng = Random::default();
+    let mut context = MutationContext {
+        info: StepInfo::default(),
+        map: None,
+        rng: &mut rng,
+    };
+
+    for _ in 0..n {
+        state = mutator.mutate(&mut log, &mut context);
+    }
+
+    (log, state)
+}
+
+#[test]
+fn sequence() {
+    let mut sequence = Sequence::new(vec![steps("a", 2), steps("b", 0), steps("c", 1)]);
+
+    // the empty mutation doesn't take a step
+    assert_eq!(
+        run(&mut sequence, 3),
+        (vec!["a", "a", "c"], MutationState::Processing)
+    );
+    assert_eq!(run(&mut sequence, 2), (vec![], MutationState::Finished));
+
+    sequence.reset();
+
+    assert_eq!(
+        run(&mut sequence, 1),
+        (vec!["a"], MutationState::Processing)
+    );
+    assert_eq!(
+        run(&mut Sequence::new(vec![]), 1),
+        (vec![], MutationState::Finished)
+    );
+}
+
+#[test]
+fn repeat() {
+    let mut repeat = Repeat::new(2, steps("a", 2));
+
+    assert_eq!(
+        run(&mut repeat, 4),
+        (vec!["a"; 4], MutationState::Processing)
+    );
+    assert_eq!(run(&mut repeat, 1), (vec![], MutationState::Finished));
+
+    repeat.reset();
+
+    assert_eq!(run(&mut repeat, 5).0.len(), 4);
+    assert_eq!(
+        run(&mut Repeat::new(0, steps("a", 2)), 1),
+        (vec![], MutationState::Finished)
+    );
+    assert_eq!(
+        run(&mut Repeat::new(3, steps("a", 0)), 1),
+        (vec![], MutationState::Finished)
+    );
+}
+
+#[test]
+fn forever() {
+    let mut forever = Forever::new(steps("a", 3));
+
+    assert_eq!(
+        run(&mut forever, 10),
+        (vec!["a"; 10], MutationState::Processing)
+    );
+
+    // never does anything, but doesn't finish either
+    assert_eq!(
+        run(&mut Forever::new(steps("a", 0)), 3),
+        (vec![], MutationState::Processing)
+    );
+}
+
+#[test]
+fn limit() {
+    let mut limit = Limit::new(3, Box::new(Forever::new(steps("a", 2))));
+
+    assert_eq!(
+        run(&mut limit, 3),
+        (vec!["a"; 3], MutationState::Processing)
+    );
+    assert_eq!(run(&mut limit, 1), (vec![], MutationState::Finished));
+
+    limit.reset();
+
+    assert_eq!(run(&mut limit, 5).0.len(), 3);
+
+    // the steps run out although the mutation finished before
+    assert_eq!(
+        run(&mut Limit::new(3, steps("a", 1)), 2),
+        (vec!["a"], MutationState::Finished)
+    );
+}
+
+#[test]
+fn chance() {
+    let picks = |probability: f32, seed: Seed| {
+        let mut chance = Chance::new(probability, steps("a", 2), steps("b", 2));
+        let mut rng = Random::new(seed);
+        let mut context = MutationContext {
+            info: StepInfo::default(),
+            map: None,
+            rng: &mut rng,
+        };
+
+        let mut picks = Vec::new();
+
+        for _ in 0..20 {
+            let mut log = Vec::new();
+
+            for _ in 0..2 {
+                assert_eq!(
+                    chance.mutate(&mut log, &mut context),
+                    MutationState::Processing
+                );
+            }
+
+            // the pick holds until the next reset
+            assert!(log.iter().all(|&name| name == log[0]));
+            assert_eq!(
+                chance.mutate(&mut log, &mut context),
+                MutationState::Finished
+            );
+
+            picks.push(log[0]);
+            chance.reset();
+        }
+
+        picks
+    };
+
+    assert_eq!(picks(1.0, 0), vec!["a"; 20]);
+    assert_eq!(picks(0.0, 0), vec!["b"; 20]);
+    assert_eq!(picks(0.5, 7), picks(0.5, 7));
+
+    // the picks follow the stream of the context
+    let mixed = picks(0.5, 7);
+    assert!(mixed.contains(&"a") && mixed.contains(&"b"));
+    assert_ne!(mixed, picks(0.5, 8));
+
+    // combinators nest like any other mutation
+    let _: BoxedMutator<Vec<&str>> = Box::new(Chance::new(0.5, steps("a", 1), steps("b", 1)));
+}
+
+#[test]
+fn until() {
+    let mut until = Until::new(
+        |log: &Vec<&str>| log.len() >= 3,
+        Box::new(Forever::new(steps("a", 1))),
+    );
+
+    assert_eq!(run(&mut until, 5), (vec!["a"; 3], MutationState::Finished));
+
+    // stays finished although the predicate doesn't hold for a new mutant
+    assert_eq!(run(&mut until, 1), (vec![], MutationState::Finished));
+
+    until.reset();
+
+    assert_eq!(run(&mut until, 1), (vec!["a"], MutationState::Processing));
+
+    // finishes along with its mutation
+    assert_eq!(
+        run(&mut Until::new(|_: &Vec<&str>| false, steps("a", 2)), 3),
+        (vec!["a"; 2], MutationState::Finished)
+    );
+}
+
+#[test]
+fn parallel() {
+    let mut parallel = Parallel::new(vec![steps("a", 1), steps("b", 2)]);
+
+    assert_eq!(
+        run(&mut parallel, 2),
+        (vec!["a", "b", "b"], MutationState::Processing)
+    );
+    assert_eq!(run(&mut parallel, 1), (vec![], MutationState::Finished));
+
+    parallel.reset();
+
+    assert_eq!(
+        run(&mut parallel, 1),
+        (vec!["a", "b"], MutationState::Processing)
+    );
+}
+
+#[test]
+fn nested() {
+    // a-b-b twice, then c forever
+    let mut nested = Sequence::new(vec![
+        Box::new(Repeat::new(
+            2,
+            Box::new(Sequence::new(vec![steps("a", 1), steps("b", 2)])),
+        )),
+        Box::new(Forever::new(steps("c", 1))),
+    ]);
+
+    assert_eq!(
+        run(&mut nested, 8),
+        (
+            vec!["a", "b", "b", "a", "b", "b", "c", "c"],
+            MutationState::Processing
+        )
+    );
+}
+
+const WAYPOINTS: [(f32, f32); 3] = [(0.0, 1.0), (0.5, 0.5), (1.0, 0.0)];
+
+fn pulse(from: usize, to: usize) -> PulseBrushMutation {
+    PulseBrushMutation::new(from, to, 40, 0.5)
+}
+
+/// the loops of `pipeline` built by hand from combinators
+#[derive(Default)]
+struct Combinators {
+    brush: Option<BoxedMutator<Brush>>,
+    walker: Option<BoxedMutator<Walker>>,
+    /// context stream of the random mutation, the only one drawing from it
+    rng: Random,
+}
+
+impl StepHandler for Combinators {
+    fn begin(&mut self, seed: Seed) {
+        self.brush = Some(Box::new(Limit::new(
+            60,
+            Box::new(Sequence::new(vec![
+                Box::new(pulse(1, 4)),
+                Box::new(pulse(4, 2)),
+            ])),
+        )));
+        self.walker = Some(Box::new(Forever::new(Box::new(Sequence::new(vec![
+            Box::new(StraightWalkerMutation::new(5)),
+            Box::new(RandomWalkerMutation::new(3)),
+        ])))));
+        self.rng = Random::new(sub_seed(seed, "walker/random/0"));
+    }
+
+    fn step(&mut self, walker: &mut Walker, map: &mut Map, brush: &mut Brush, info: StepInfo) {
+        let mut context = MutationContext {
+            info,
+            map: Some(map),
+            rng: &mut self.rng,
+        };
+
+        self.brush.as_mut().unwrap().mutate(brush, &mut context);
+        self.walker.as_mut().unwrap().mutate(walker, &mut context);
+    }
+
+    fn end(&mut self, walker: &mut Walker, _map: &mut Map, brush: &mut Brush) {
+        self.brush.as_mut().unwrap().finish(brush);
+        self.walker.as_mut().unwrap().finish(walker);
+    }
+}
+
+#[test]
+fn pipeline_loops_are_combinators() {
+    let mut pipeline = Pipeline {
+        brush: vec![MutationLoop::new(
+            Some(60),
+            vec![
+                BrushMutation::Pulse(pulse(1, 4)),
+                BrushMutation::Pulse(pulse(4, 2)),
+            ],
+        )],
+        walker: vec![MutationLoop::endless(vec![
+            WalkerMutation::Straight(StraightWalkerMutation::new(5)),
+            WalkerMutation::Random(RandomWalkerMutation::new(3)),
+        ])],
+        ..Default::default()
+    };
+    pipeline.generator.scale_factor = 60.0;
+
+    let mut by_pipeline = Generator::new();
+    pipeline.setup(&mut by_pipeline);
+
+    let mut by_hand = Generator::new();
+    pipeline.setup(&mut by_hand);
+    by_hand.on_step(Combinators::default());
+
+    for seed in [0, 42] {
+        let (a, ..) = by_pipeline.generate(WAYPOINTS.to_vec(), seed).unwrap();
+        let (b, ..) = by_hand.generate(WAYPOINTS.to_vec(), seed).unwrap();
+
+        let tiles = |map: &twmap::TwMap| {
+            map.find_physics_layer::<GameLayer>()
+                .unwrap()
+                .tiles
+                .unwrap_ref()
+                .clone()
+        };
+
+        assert_eq!(tiles(&a), tiles(&b));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn counted_loops_run_when_loaded() {
+    use mapgen_core::pipeline::PipelineFormat;
+
+    let data = r#"{
+        "version": 1,
+        "brush": [
+            {
+                "count": 1000,
+                "mutations": [
+                    { "Pulse": { "value_border": 2, "value_climax": 8, "normal_peak": 0.5, "overall_steps": 1000 } }
+                ]
+            }
+        ],
+        "walker": [ { "count": 100000, "mutations": [ { "Straight": { "overall_steps": 100000 } } ] } ]
+    }"#;
+
+    let mut pipeline = Pipeline::from_text(data, PipelineFormat::Json).unwrap();
+    pipeline.generator.scale_factor = 60.0;
+
+    let tiles = |pipeline: &Pipeline| {
+        let mut generator = Generator::new();
+        pipeline.setup(&mut generator);
+
+        let (map, ..) = generator.generate(WAYPOINTS.to_vec(), 0).unwrap();
+
+        map.find_physics_layer::<GameLayer>()
+            .unwrap()
+            .tiles
+            .unwrap_ref()
+            .clone()
+    };
+
+    let pulsed = tiles(&pipeline);
+    pipeline.brush.clear();
+
+    assert_ne!(pulsed, tiles(&pipeline));
+}
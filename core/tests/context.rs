@@ -0,0 +1,160 @@
+use std::sync::{Arc, Mutex};
+
+use mapgen_core::{
+    block::{BlockMapping, BlockType},
+    brush::Brush,
+    generator::Generator,
+    map::Map,
+    mutations::{brush::taper::TaperBrushMutation, MutationContext, Mutator, StepInfo},
+    position::Direction,
+    random::Random,
+    walker::Walker,
+};
+use ndarray::arr1;
+
+const WAYPOINTS: [(f32, f32); 3] = [(0.0, 1.0), (0.5, 0.5), (1.0, 0.0)];
+
+/// heads for the preferred waypoint and records what every step was told
+fn recorded_generation(seed: u64) -> (Vec<StepInfo>, Vec<[usize; 2]>) {
+    let infos = Arc::new(Mutex::new(Vec::new()));
+    let recorded = infos.clone();
+
+    let mut generator = Generator::new();
+    generator.set_scale_factor(40.0);
+    generator.on_step(
+        move |walker: &mut Walker, _map: &mut Map, _brush: &mut Brush, info: StepInfo| {
+            let preferred = *walker.preferred_state();
+            walker
+                .set_next_direction(preferred.direction)
+                .set_next_waypoint(preferred.waypoint);
+
+            recorded.lock().unwrap().push(info);
+        },
+    );
+
+    let (_, _, trace) = generator.generate(WAYPOINTS.to_vec(), seed).unwrap();
+    let positions = trace.main().unwrap().positions().collect();
+
+    let infos = infos.lock().unwrap().clone();
+
+    (infos, positions)
+}
+
+#[test]
+fn step_info_follows_the_walk() {
+    let (infos, positions) = recorded_generation(1);
+
+    // one call in `begin`, then one per step
+    assert_eq!(infos.len(), positions.len() + 1);
+    assert_eq!(infos[0].step, 0);
+    assert_eq!(infos[0].progress, 0.0);
+
+    for (step, info) in infos.iter().skip(1).enumerate() {
+        assert_eq!(info.step, step);
+
+        if step > 0 {
+            assert_eq!(info.position, positions[step - 1]);
+        }
+
+        assert!((0.0..=1.0).contains(&info.waypoint_progress));
+        assert!((0.0..=1.0).contains(&info.progress));
+    }
+
+    assert!(infos.windows(2).all(|w| w[0].waypoint <= w[1].waypoint));
+    assert_eq!(infos.last().unwrap().waypoint, WAYPOINTS.len() - 1);
+    assert!(infos.last().unwrap().progress > 0.9);
+}
+
+#[test]
+fn step_info_knows_the_direction() {
+    let (infos, _) = recorded_generation(1);
+
+    // nothing is known before the first step
+    assert_eq!(infos[0].direction, None);
+
+    for pair in infos[1..].windows(2) {
+        let [x0, y0] = pair[0].position.map(|v| v as isize);
+        let [x1, y1] = pair[1].position.map(|v| v as isize);
+
+        let moved = match (x1 - x0, y1 - y0) {
+            (0, -1) => Direction::Up,
+            (1, 0) => Direction::Right,
+            (0, 1) => Direction::Down,
+            (-1, 0) => Direction::Left,
+            delta => panic!("unexpected step {:?}", delta),
+        };
+
+        assert_eq!(pair[0].direction, Some(moved));
+    }
+}
+
+#[test]
+fn taper_follows_progress() {
+    let sizes = Arc::new(Mutex::new(Vec::new()));
+    let recorded = sizes.clone();
+    let mut taper = TaperBrushMutation::new(2, 12);
+    let mut rng = Random::default();
+
+    let mut generator = Generator::new();
+    generator.set_scale_factor(40.0);
+    generator.on_step(
+        move |walker: &mut Walker, map: &mut Map, brush: &mut Brush, info: StepInfo| {
+            let preferred = *walker.preferred_state();
+            walker
+                .set_next_direction(preferred.direction)
+                .set_next_waypoint(preferred.waypoint);
+
+            let mut context = MutationContext {
+                info,
+                map: Some(map),
+                rng: &mut rng,
+            };
+            taper.mutate(brush, &mut context);
+
+            recorded.lock().unwrap().push((info.progress, brush.size()));
+        },
+    );
+
+    generator.generate(WAYPOINTS.to_vec(), 1).unwrap();
+
+    let sizes = sizes.lock().unwrap();
+
+    assert_eq!(sizes[0], (0.0, 2));
+    assert!(sizes.last().unwrap().1 >= 11);
+
+    for &(progress, size) in sizes.iter() {
+        assert_eq!(size, (2.0 + 10.0 * progress).round() as usize);
+    }
+}
+
+#[test]
+fn waypoint_progress() {
+    let mut walker = Walker::new(10.0);
+    walker.set_waypoints(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+
+    // nothing is known before the first step
+    assert_eq!(walker.waypoint_progress(arr1(&[0.0, 0.0]).view()), 0.0);
+
+    walker.set_next_waypoint(0);
+    walker.step(arr1(&[0.0, 0.0]).view());
+
+    // the first waypoint is reached right away
+    assert_eq!(walker.preferred_state().waypoint, 1);
+    assert_eq!(walker.waypoint_progress(arr1(&[0.0, 0.0]).view()), 0.0);
+    assert_eq!(walker.waypoint_progress(arr1(&[5.0, 0.0]).view()), 0.5);
+    assert_eq!(walker.progress(arr1(&[5.0, 0.0]).view()), 0.25);
+    assert_eq!(walker.progress(arr1(&[10.0, 0.0]).view()), 0.5);
+}
+
+#[test]
+fn game_tiles() {
+    let mut map = Map::new();
+    map.reshape(4, 3);
+    map.fill_game(BlockMapping::default().tile(BlockType::Hookable));
+
+    let hookable = BlockMapping::default().tile_id(BlockType::Hookable);
+
+    assert_eq!(map.game_tile((3, 2)).map(|tile| tile.id), Some(hookable));
+    assert!(map.game_tile((4, 0)).is_none());
+    assert!(map.game_tile((0, 3)).is_none());
+}
@@ -0,0 +1,146 @@
+use mapgen_core::{
+    generator::Generator,
+    map::{FreezeConfig, RoomConfig},
+    mutations::{
+        brush::pulse::PulseBrushMutation,
+        walker::{
+            random::RandomWalkerMutation, straight::StraightWalkerMutation,
+            weighted::WeightedWalkerMutation,
+        },
+    },
+    pipeline::{BrushMutation, MutationLoop, Pipeline, WalkerMutation},
+    random::Seed,
+};
+use twmap::{GameLayer, TwMap};
+
+const WAYPOINTS: [(f32, f32); 3] = [(0.0, 1.0), (0.5, 0.5), (1.0, 0.0)];
+
+fn pipeline() -> Pipeline {
+    let mut pipeline = Pipeline {
+        brush: vec![MutationLoop::endless(vec![BrushMutation::Pulse(
+            PulseBrushMutation::new(1, 4, 50, 0.5),
+        )])],
+        walker: vec![MutationLoop::endless(vec![
+            WalkerMutation::Straight(StraightWalkerMutation::new(5)),
+            WalkerMutation::Random(RandomWalkerMutation::new(3)),
+        ])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 60.0;
+    pipeline.generator.freeze = Some(FreezeConfig::default());
+    pipeline.generator.rooms = Some(RoomConfig::default());
+
+    pipeline
+}
+
+fn map_hash(map: &TwMap) -> u64 {
+    let tiles = map
+        .find_physics_layer::<GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref();
+
+    let mut bytes = Vec::with_capacity(tiles.len() * 2 + 16);
+    bytes.extend_from_slice(&(tiles.ncols() as u64).to_le_bytes());
+    bytes.extend_from_slice(&(tiles.nrows() as u64).to_le_bytes());
+
+    for tile in tiles.iter() {
+        bytes.push(tile.id);
+        bytes.push(tile.flags.bits());
+    }
+
+    seahash::hash(&bytes)
+}
+
+fn generate(pipeline: &Pipeline, seed: Seed) -> u64 {
+    let mut generator = Generator::new();
+
+    pipeline.setup(&mut generator);
+
+    map_hash(&generator.generate(WAYPOINTS.to_vec(), seed).unwrap().0)
+}
+
+#[test]
+fn same_seed_gives_same_map() {
+    assert_eq!(generate(&pipeline(), 42), generate(&pipeline(), 42));
+}
+
+#[test]
+fn generator_is_reusable() {
+    let mut generator = Generator::new();
+
+    pipeline().setup(&mut generator);
+
+    let first = map_hash(&generator.generate(WAYPOINTS.to_vec(), 42).unwrap().0);
+    let second = map_hash(&generator.generate(WAYPOINTS.to_vec(), 42).unwrap().0);
+
+    assert_eq!(first, second);
+}
+
+#[test]
+fn different_seeds_give_different_maps() {
+    assert_ne!(generate(&pipeline(), 42), generate(&pipeline(), 43));
+}
+
+/// `extended` with a loop running `mutation` in front of the walker loop, the
+/// walker loop overrides whatever it picks, so only its streams matter
+fn with_leading_walker_loop(mutation: WalkerMutation, id: Option<&str>) -> Pipeline {
+    let mut extended = pipeline();
+
+    extended.walker.insert(
+        0,
+        MutationLoop::endless(vec![mutation]).with_ids(vec![id.map(str::to_string)]),
+    );
+
+    extended
+}
+
+#[test]
+fn unrelated_mutations_keep_streams() {
+    // draws from its own stream every step
+    let weighted =
+        WalkerMutation::Weighted(WeightedWalkerMutation::from_weights(1000, [0.25; 4], 0.5));
+
+    assert_eq!(
+        generate(&pipeline(), 42),
+        generate(&with_leading_walker_loop(weighted, None), 42)
+    );
+}
+
+#[test]
+fn mutations_with_ids_keep_streams() {
+    let random = || WalkerMutation::Random(RandomWalkerMutation::new(1000));
+
+    // an id keeps the stream of the random mutation behind it
+    assert_eq!(
+        generate(&pipeline(), 42),
+        generate(&with_leading_walker_loop(random(), Some("detour")), 42)
+    );
+
+    // without one it takes over its stream
+    assert_ne!(
+        generate(&pipeline(), 42),
+        generate(&with_leading_walker_loop(random(), None), 42)
+    );
+
+    // named streams don't depend on the position
+    let mut named = pipeline();
+    named.walker[0].ids = vec![None, Some("detour".to_string())];
+
+    let mut moved = with_leading_walker_loop(
+        WalkerMutation::Straight(StraightWalkerMutation::new(1)),
+        None,
+    );
+    moved.walker[1].ids = vec![None, Some("detour".to_string())];
+
+    assert_eq!(generate(&named, 42), generate(&moved, 42));
+}
+
+/// a failure here means maps of existing seeds have changed, update the
+/// hashes only if that is intended
+#[test]
+fn regression() {
+    assert_eq!(generate(&pipeline(), 0), 7740242056936276247);
+    assert_eq!(generate(&pipeline(), 42), 14295712224900365244);
+}
@@ -0,0 +1,108 @@
+use mapgen_core::{
+    brush::{Brush, OutOfBounds},
+    generator::{GenerationError, Generator},
+    mutations::{
+        brush::transition::TransitionBrushMutation, walker::straight::StraightWalkerMutation,
+    },
+    pipeline::{BrushMutation, MutationLoop, Pipeline, WalkerMutation},
+    position::Vector2,
+};
+use ndarray::Array2;
+use twmap::{GameTile, TileFlags};
+
+const WAYPOINTS: [(f32, f32); 3] = [(0.0, 1.0), (0.5, 0.5), (1.0, 0.0)];
+
+fn pipeline() -> Pipeline {
+    let mut pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 40.0;
+
+    pipeline
+}
+
+fn generate(pipeline: &Pipeline, waypoints: &[(f32, f32)]) -> Result<(), GenerationError> {
+    let mut generator = Generator::new();
+
+    pipeline.setup(&mut generator);
+
+    generator.generate(waypoints.to_vec(), 0).map(|_| ())
+}
+
+#[test]
+fn valid_pipeline() {
+    assert_eq!(generate(&pipeline(), &WAYPOINTS), Ok(()));
+}
+
+#[test]
+fn invalid_config() {
+    let mut invalid_scale = pipeline();
+    invalid_scale.generator.scale_factor = f32::NAN;
+
+    assert!(matches!(
+        generate(&pipeline(), &WAYPOINTS[..1]),
+        Err(GenerationError::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        generate(&pipeline(), &[(0.0, f32::INFINITY), (1.0, 1.0)]),
+        Err(GenerationError::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        generate(&invalid_scale, &WAYPOINTS),
+        Err(GenerationError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn step_limit() {
+    let mut pipeline = pipeline();
+    pipeline.generator.max_steps = 10;
+
+    assert_eq!(
+        generate(&pipeline, &WAYPOINTS),
+        Err(GenerationError::StepLimitExceeded(10))
+    );
+}
+
+#[test]
+fn big_brush_fits() {
+    let mut pipeline = pipeline();
+    pipeline.brush = vec![MutationLoop::endless(vec![BrushMutation::Transition(
+        TransitionBrushMutation::new(100, 100, 1),
+    )])];
+
+    assert_eq!(generate(&pipeline, &WAYPOINTS), Ok(()));
+}
+
+#[test]
+fn brush_is_clipped() {
+    let mut tiles = Array2::from_elem((4, 4), GameTile::new(1, TileFlags::empty()));
+    let brush = Brush::from_texture(Array2::from_elem((5, 5), true));
+    let empty = GameTile::new(0, TileFlags::empty());
+
+    assert_eq!(
+        brush.apply(&mut tiles, Vector2::from(vec![0.5, 0.5]), empty),
+        Ok(())
+    );
+    for ((x, y), tile) in tiles.indexed_iter() {
+        assert_eq!(tile.id == 0, x <= 2 && y <= 2);
+    }
+
+    assert_eq!(
+        brush.apply(&mut tiles, Vector2::from(vec![-1.0, 2.0]), empty),
+        Err(OutOfBounds { x: -1.0, y: 2.0 })
+    );
+}
+
+#[test]
+fn finish_unreachable() {
+    // without walker mutations nothing is carved
+    assert_eq!(
+        generate(&Pipeline::default(), &WAYPOINTS),
+        Err(GenerationError::FinishUnreachable)
+    );
+}
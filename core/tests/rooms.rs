@@ -0,0 +1,33 @@
+use mapgen_core::{
+    block::{BlockMapping, BlockType},
+    map::Map,
+    position::Direction,
+};
+
+#[test]
+fn lines_stop_at_unhookable_walls() {
+    let blocks = BlockMapping::default();
+    let mut map = Map::new();
+    map.reshape(9, 3);
+    map.fill_game(blocks.tile(BlockType::Hookable));
+
+    // a corridor from 2 to 6 with unhookable rims at 1 and 7
+    let tiles = map.game_layer().tiles.unwrap_mut();
+    for x in 1..8 {
+        tiles[[x, 1]] = blocks.tile(BlockType::Empty);
+    }
+    tiles[[1, 1]] = blocks.tile(BlockType::Unhookable);
+    tiles[[7, 1]] = blocks.tile(BlockType::Unhookable);
+
+    map.draw_line((4, 1), Direction::Up, BlockType::Start);
+
+    let row: Vec<_> = (0..9)
+        .map(|x| blocks.block_type(map.game_tile((x, 1)).unwrap().id))
+        .collect();
+
+    assert_eq!(row[0], Some(BlockType::Hookable));
+    assert_eq!(row[1], Some(BlockType::Unhookable));
+    assert!(row[2..7].iter().all(|&block| block == Some(BlockType::Start)));
+    assert_eq!(row[7], Some(BlockType::Unhookable));
+    assert_eq!(row[8], Some(BlockType::Hookable));
+}
@@ -0,0 +1,148 @@
+use mapgen_core::{
+    block::{BlockMapping, BlockType},
+    branch::BranchConfig,
+    generator::{GenerationError, Generator},
+    map::Map,
+    mutations::walker::{random::RandomWalkerMutation, straight::StraightWalkerMutation},
+    pipeline::{BranchSettings, MutationLoop, Pipeline, WalkerMutation},
+};
+use twmap::GameLayer;
+
+const WAYPOINTS: [(f32, f32); 3] = [(0.0, 1.0), (0.5, 0.5), (1.0, 0.0)];
+
+fn pipeline() -> Pipeline {
+    let mut pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![
+            WalkerMutation::Straight(StraightWalkerMutation::new(5)),
+            WalkerMutation::Random(RandomWalkerMutation::new(3)),
+        ])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 60.0;
+    pipeline.generator.branches = Some(BranchSettings {
+        config: BranchConfig {
+            probability: 0.05,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    pipeline
+}
+
+fn generator() -> Generator {
+    let mut generator = Generator::new();
+    pipeline().setup(&mut generator);
+
+    generator
+}
+
+/// tiles that aren't solid hookable anymore
+fn carved_tiles(map: &Map) -> usize {
+    let id = BlockMapping::default().tile_id(BlockType::Hookable);
+
+    map.raw_map()
+        .find_physics_layer::<GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref()
+        .iter()
+        .filter(|tile| tile.id != id)
+        .count()
+}
+
+#[test]
+fn stepping_matches_generate() {
+    let (expected, _, expected_trace) = generator().generate(WAYPOINTS.to_vec(), 42).unwrap();
+
+    let mut generator = generator();
+    generator.begin(WAYPOINTS.to_vec(), 42).unwrap();
+
+    while generator.step_n(7).unwrap() {}
+
+    let (map, _, trace) = generator.finish().unwrap();
+
+    assert_eq!(map, expected);
+    assert_eq!(trace, expected_trace);
+}
+
+#[test]
+fn intermediate_states() {
+    let mut generator = generator();
+    generator.begin(WAYPOINTS.to_vec(), 42).unwrap();
+
+    let session = generator.session().unwrap();
+    assert_eq!(session.steps(), 0);
+    assert_eq!(carved_tiles(session.map()), 0);
+
+    let mut carved = 0;
+
+    for step in 1..=50 {
+        assert!(generator.step().unwrap());
+
+        let session = generator.session().unwrap();
+        assert_eq!(session.steps(), step);
+        assert_eq!(
+            session.main().steps.last().unwrap().position,
+            session.position()
+        );
+
+        // carved tiles are never filled again
+        let now = carved_tiles(session.map());
+        assert!(now >= carved);
+        carved = now;
+    }
+
+    assert!(carved > 0);
+
+    while generator.step().unwrap() {}
+
+    let session = generator.session().unwrap();
+    assert!(!session.is_walking());
+    assert_eq!(session.active_branches(), 0);
+
+    let steps = session.steps();
+    let (_, _, trace) = generator.finish().unwrap();
+
+    assert_eq!(trace.main().unwrap().steps.len(), steps);
+}
+
+#[test]
+fn without_session() {
+    let mut generator = generator();
+
+    assert_eq!(generator.step(), Err(GenerationError::NotStarted));
+    assert!(generator.finish().is_err());
+
+    generator.begin(WAYPOINTS.to_vec(), 0).unwrap();
+    generator.step_n(10).unwrap();
+    generator.cancel();
+
+    assert!(generator.session().is_none());
+    assert_eq!(generator.step_n(1), Err(GenerationError::NotStarted));
+
+    // failed steps end the generation
+    generator.set_max_steps(5);
+    generator.begin(WAYPOINTS.to_vec(), 0).unwrap();
+
+    assert_eq!(
+        generator.step_n(10),
+        Err(GenerationError::StepLimitExceeded(5))
+    );
+    assert!(generator.session().is_none());
+}
+
+#[test]
+fn generation_on_another_thread() {
+    let mut generator = generator();
+
+    let worker = std::thread::spawn(move || {
+        generator.begin(WAYPOINTS.to_vec(), 42).unwrap();
+        generator.finish().unwrap().0
+    });
+
+    let (expected, _, _) = self::generator().generate(WAYPOINTS.to_vec(), 42).unwrap();
+
+    assert_eq!(worker.join().unwrap(), expected);
+}
@@ -0,0 +1,151 @@
+use mapgen_core::{
+    brush::{Brush, BrushShape},
+    stamp::{BrushStamp, StampChannel, StampConfig, StampError},
+};
+use ndarray::Array2;
+
+/// `#` for covered pixels, one string per row
+fn mask(rows: &[&str]) -> Array2<bool> {
+    Array2::from_shape_fn((rows[0].len(), rows.len()), |(x, y)| {
+        rows[y].as_bytes()[x] == b'#'
+    })
+}
+
+fn rows(texture: &Array2<bool>) -> Vec<String> {
+    let (width, height) = texture.dim();
+
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| if texture[[x, y]] { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+fn config(path: &str) -> StampConfig {
+    StampConfig {
+        path: path.into(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn orientation() {
+    let l = mask(&["#..", "###"]);
+    let oriented = |rotation, mirror_x, mirror_y| {
+        let config = StampConfig {
+            rotation,
+            mirror_x,
+            mirror_y,
+            ..Default::default()
+        };
+
+        rows(BrushStamp::from_mask(config, l.clone()).mask())
+    };
+
+    assert_eq!(oriented(0, false, false), vec!["#..", "###"]);
+    assert_eq!(oriented(1, false, false), vec!["##", "#.", "#."]);
+    assert_eq!(oriented(2, false, false), vec!["###", "..#"]);
+    assert_eq!(oriented(3, false, false), vec![".#", ".#", "##"]);
+    assert_eq!(oriented(4, false, false), oriented(0, false, false));
+    assert_eq!(oriented(0, true, false), vec!["..#", "###"]);
+    assert_eq!(oriented(0, false, true), vec!["###", "#.."]);
+    assert_eq!(oriented(1, true, false), vec!["##", ".#", ".#"]);
+}
+
+#[test]
+fn resampling() {
+    let stamp = BrushStamp::from_mask(
+        StampConfig::default(),
+        mask(&["####....", "####....", "........", "........"]),
+    );
+
+    assert_eq!(rows(&stamp.rasterize(4)), vec!["##..", "...."]);
+    assert_eq!(rows(&stamp.rasterize(16)).len(), 8);
+    assert_eq!(rows(&stamp.rasterize(16))[3], "########........");
+    assert_eq!(rows(&stamp.rasterize(0)), vec!["#"]);
+}
+
+#[test]
+fn shipped_stamps() {
+    for entry in std::fs::read_dir("../data/stamps").unwrap() {
+        let path = entry.unwrap().path();
+        let stamp = BrushStamp::load(config(path.to_str().unwrap())).unwrap();
+
+        assert!(stamp.mask().iter().any(|&covered| covered));
+    }
+
+    let wedge = BrushStamp::load(config("../data/stamps/wedge.png")).unwrap();
+    let mut brush = Brush::from_shape(BrushShape::Stamp(wedge.clone()));
+
+    brush.apply_scale(8.0);
+
+    assert_eq!(wedge.mask().dim(), (16, 16));
+    assert_eq!(wedge.rasterize(8).dim(), (8, 8));
+
+    // the wedge is wide on the left and narrow on the right
+    let column = |x| (0..16).filter(|&y| wedge.mask()[[x, y]]).count();
+    assert!(column(0) > column(15));
+}
+
+#[test]
+fn thresholds() {
+    // the stamps are black, so nothing is lighter than the threshold
+    let luminance = StampConfig {
+        channel: StampChannel::Luminance,
+        ..config("../data/stamps/wedge.png")
+    };
+    let covered = |stamp: &BrushStamp| stamp.mask().iter().filter(|&&c| c).count();
+
+    let alpha = BrushStamp::load(config("../data/stamps/wedge.png")).unwrap();
+    let luminance = BrushStamp::load(luminance).unwrap();
+
+    assert!(covered(&alpha) < covered(&luminance));
+    assert_eq!(covered(&luminance), 16 * 16);
+
+    assert!(matches!(
+        BrushStamp::load(StampConfig {
+            channel: StampChannel::Luminance,
+            threshold: 0,
+            ..config("../data/stamps/wedge.png")
+        }),
+        Err(StampError::Empty(_))
+    ));
+    assert!(matches!(
+        BrushStamp::load(config("../data/stamps/missing.png")),
+        Err(StampError::Image(..))
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn stored_in_pipeline() {
+    use mapgen_core::pipeline::{Pipeline, PipelineFormat};
+
+    let data = r#"{
+        "version": 1,
+        "generator": {
+            "brush_shape": { "Stamp": { "path": "../data/stamps/lip.png", "rotation": 2 } }
+        }
+    }"#;
+
+    let pipeline = Pipeline::from_text(data, PipelineFormat::Json).unwrap();
+    let BrushShape::Stamp(stamp) = &pipeline.generator.brush_shape else {
+        panic!("{:?}", pipeline.generator.brush_shape);
+    };
+
+    assert_eq!(stamp.config().rotation, 2);
+    // the lip ends up at the top left
+    assert!(!stamp.mask()[[0, 0]] && stamp.mask()[[15, 15]]);
+
+    for format in [PipelineFormat::Json, PipelineFormat::Ron] {
+        let text = pipeline.to_text(format).unwrap();
+
+        assert_eq!(Pipeline::from_text(&text, format).unwrap(), pipeline);
+    }
+
+    let missing = data.replace("lip.png", "missing.png");
+
+    assert!(Pipeline::from_text(&missing, PipelineFormat::Json).is_err());
+}
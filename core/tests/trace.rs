@@ -0,0 +1,101 @@
+use mapgen_core::{
+    generator::Generator,
+    mutations::{
+        brush::transition::TransitionBrushMutation,
+        walker::{random::RandomWalkerMutation, straight::StraightWalkerMutation},
+    },
+    pipeline::{BrushMutation, MutationLoop, Pipeline, WalkerMutation},
+    trace::WalkerRole,
+};
+
+const WAYPOINTS: [(f32, f32); 3] = [(0.0, 1.0), (0.6, 0.6), (1.0, 0.0)];
+
+fn pipeline() -> Pipeline {
+    let mut pipeline = Pipeline {
+        brush: vec![MutationLoop::endless(vec![BrushMutation::Transition(
+            TransitionBrushMutation::new(2, 6, 20),
+        )])],
+        walker: vec![MutationLoop::endless(vec![
+            WalkerMutation::Straight(StraightWalkerMutation::new(4)),
+            WalkerMutation::Random(RandomWalkerMutation::new(1)),
+        ])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 50.0;
+
+    pipeline
+}
+
+#[test]
+fn main_walk() {
+    let mut generator = Generator::new();
+    pipeline().setup(&mut generator);
+
+    let (_, _, trace) = generator.generate(WAYPOINTS.to_vec(), 9).unwrap();
+    let main = trace.main().unwrap();
+
+    assert_eq!(main.role, WalkerRole::Main);
+    assert_eq!(trace.len(), main.steps.len());
+    assert_eq!(trace.seed, 9);
+    assert_eq!(trace.waypoints, WAYPOINTS.to_vec());
+
+    // every step moves by one tile in its direction
+    for pair in main.steps.windows(2) {
+        let ([fx, fy], [tx, ty]) = (pair[0].position, pair[1].position);
+
+        assert_eq!(fx.abs_diff(tx) + fy.abs_diff(ty), 1);
+    }
+
+    // the random mutation picks any waypoint, the walk ends at the last one
+    assert!(main
+        .steps
+        .iter()
+        .all(|step| step.waypoint < WAYPOINTS.len()));
+    assert_eq!(main.steps.last().unwrap().waypoint, WAYPOINTS.len() - 1);
+
+    let sizes: Vec<_> = main.steps.iter().map(|step| step.brush_size).collect();
+    assert!(sizes.iter().all(|size| (2..=6).contains(size)));
+    assert!(sizes.contains(&2) && sizes.contains(&6));
+}
+
+#[test]
+fn active_mutations() {
+    let mut generator = Generator::new();
+    pipeline().setup(&mut generator);
+
+    let (_, _, trace) = generator.generate(WAYPOINTS.to_vec(), 9).unwrap();
+    let main = trace.main().unwrap();
+
+    for step in main.steps.iter() {
+        assert_eq!(step.mutations[0], "brush/transition/0");
+        assert!(step.mutations[1..]
+            .iter()
+            .all(|name| name.starts_with("walker/")));
+    }
+
+    // the random mutation only runs once the straight one finished
+    assert!(main
+        .steps
+        .iter()
+        .any(|step| step.mutations.contains(&"walker/random/0".to_string())));
+    assert!(main
+        .steps
+        .iter()
+        .any(|step| step.mutations[1..] == ["walker/straight/0"]));
+}
+
+/// seed and waypoints of a trace only regenerate the map with the same pipeline
+#[test]
+fn replay() {
+    let mut generator = Generator::new();
+    pipeline().setup(&mut generator);
+
+    let (map, _, trace) = generator.generate(WAYPOINTS.to_vec(), 21).unwrap();
+    let (replayed, _, replayed_trace) = generator
+        .generate(trace.waypoints.clone(), trace.seed)
+        .unwrap();
+
+    assert_eq!(trace, replayed_trace);
+    assert_eq!(map, replayed);
+}
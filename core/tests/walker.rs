@@ -0,0 +1,130 @@
+use mapgen_core::{
+    generator::Generator,
+    mutations::{
+        walker::weighted::{Turn, WeightedWalkerMutation},
+        MutationContext, MutationState, Mutator, StepInfo,
+    },
+    pipeline::{MutationLoop, Pipeline, WalkerMutation},
+    position::Direction,
+    random::{Random, Seed},
+    walker::Walker,
+};
+
+/// directions picked by `mutation` in `steps` steps drawing from the stream
+/// `seed`, starting from a walker facing up
+fn directions(mutation: &mut WeightedWalkerMutation, seed: Seed, steps: usize) -> Vec<Direction> {
+    let mut walker = Walker::new(1.0);
+    let mut rng = Random::new(seed);
+    let mut context = MutationContext {
+        info: StepInfo::default(),
+        map: None,
+        rng: &mut rng,
+    };
+
+    (0..steps)
+        .map(|_| {
+            assert_eq!(
+                mutation.mutate(&mut walker, &mut context),
+                MutationState::Processing
+            );
+            walker.next_state().unwrap().direction
+        })
+        .collect()
+}
+
+#[test]
+fn turns_relative_to_preferred_direction() {
+    for (turn, direction) in Turn::ALL.into_iter().zip([
+        Direction::Up,
+        Direction::Left,
+        Direction::Right,
+        Direction::Down,
+    ]) {
+        let mut weights = [0.0; 4];
+        weights[Turn::ALL.iter().position(|&t| t == turn).unwrap()] = 1.0;
+
+        let mut mutation = WeightedWalkerMutation::from_weights(3, weights, 0.0);
+
+        assert_eq!(directions(&mut mutation, 1, 3), vec![direction; 3]);
+        assert_eq!(mutation.weight(turn), 1.0);
+    }
+}
+
+#[test]
+fn steps_and_reset() {
+    let mut mutation = WeightedWalkerMutation::from_weights(2, [1.0; 4], 0.0);
+    let mut walker = Walker::new(1.0);
+    let mut rng = Random::default();
+    let mut context = MutationContext {
+        info: StepInfo::default(),
+        map: None,
+        rng: &mut rng,
+    };
+    let mut mutate =
+        |mutation: &mut WeightedWalkerMutation| mutation.mutate(&mut walker, &mut context);
+
+    assert_eq!(mutate(&mut mutation), MutationState::Processing);
+    assert_eq!(mutate(&mut mutation), MutationState::Processing);
+    assert_eq!(mutate(&mut mutation), MutationState::Finished);
+
+    mutation.reset();
+    assert_eq!(mutate(&mut mutation), MutationState::Processing);
+}
+
+#[test]
+fn momentum_repeats_turns() {
+    let mut mutation = WeightedWalkerMutation::from_weights(100, [1.0; 4], 1.0);
+
+    let picked = directions(&mut mutation, 7, 100);
+    assert!(picked.iter().all(|&direction| direction == picked[0]));
+
+    // without momentum every turn shows up
+    let mut mutation = WeightedWalkerMutation::from_weights(100, [1.0; 4], 0.0);
+
+    let picked = directions(&mut mutation, 7, 100);
+    for direction in [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ] {
+        assert!(picked.contains(&direction));
+    }
+}
+
+#[test]
+fn seeded_turns() {
+    let sample = |seed| {
+        let mut mutation = WeightedWalkerMutation::default();
+        mutation.overall_steps = 50;
+        mutation.reset();
+
+        directions(&mut mutation, seed, 50)
+    };
+
+    assert_eq!(sample(3), sample(3));
+    assert_ne!(sample(3), sample(4));
+}
+
+#[test]
+fn invalid_weights_go_straight() {
+    let mut mutation = WeightedWalkerMutation::from_weights(5, [0.0; 4], 0.3);
+
+    assert!(!mutation.dist.is_valid());
+    assert_eq!(directions(&mut mutation, 0, 5), vec![Direction::Up; 5]);
+}
+
+#[test]
+fn generated_with_weighted_walker() {
+    let pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Weighted(
+            WeightedWalkerMutation::from_weights(10, [0.7, 0.1, 0.1, 0.1], 0.6),
+        )])],
+        ..Default::default()
+    };
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    assert!(generator.generate(vec![(0.0, 1.0), (1.0, 0.0)], 5).is_ok());
+}
@@ -0,0 +1,169 @@
+use std::f32::consts::PI;
+
+use mapgen_core::{
+    generator::Generator,
+    mutations::walker::straight::StraightWalkerMutation,
+    pipeline::{MutationLoop, Pipeline, WalkerMutation},
+    walker::waypoints::{WaypointGenerator, WaypointPattern},
+};
+
+const EPSILON: f32 = 1e-4;
+
+fn segments(waypoints: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    waypoints
+        .windows(2)
+        .map(|pair| {
+            let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+
+            (dy.atan2(dx), dx.hypot(dy))
+        })
+        .collect()
+}
+
+fn turn(from: f32, to: f32) -> f32 {
+    ((to - from + PI).rem_euclid(2.0 * PI) - PI).abs()
+}
+
+#[test]
+fn every_pattern_stays_in_the_square() {
+    let patterns = [
+        WaypointPattern::RandomWalk,
+        WaypointPattern::Directed { jitter: 0.5 },
+        WaypointPattern::ZigZag {
+            amplitude: 0.2,
+            frequency: 3.0,
+        },
+        WaypointPattern::Spiral { turns: 2.0 },
+    ];
+
+    for pattern in patterns {
+        let generator = WaypointGenerator {
+            count: 30,
+            ..WaypointGenerator::new(pattern)
+        };
+
+        for seed in 0..20 {
+            let waypoints = generator.generate(seed).waypoints;
+
+            assert_eq!(waypoints.len(), 30, "{}", pattern.name());
+            assert!(waypoints
+                .iter()
+                .all(|&(x, y)| (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y)));
+            assert_eq!(waypoints, generator.generate(seed).waypoints);
+        }
+    }
+}
+
+#[test]
+fn random_walk_limits() {
+    let generator = WaypointGenerator {
+        count: 5,
+        start: (0.5, 0.5),
+        min_segment: 0.05,
+        max_segment: 0.1,
+        max_turn: PI / 6.0,
+        ..WaypointGenerator::new(WaypointPattern::RandomWalk)
+    };
+
+    for seed in 0..20 {
+        let segments = segments(&generator.generate(seed).waypoints);
+
+        for &(_, length) in segments.iter() {
+            assert!((0.05 - EPSILON..=0.1 + EPSILON).contains(&length));
+        }
+
+        for pair in segments.windows(2) {
+            assert!(turn(pair[0].0, pair[1].0) <= PI / 6.0 + EPSILON);
+        }
+    }
+
+    assert_ne!(generator.generate(1), generator.generate(2));
+}
+
+#[test]
+fn directed_with_jitter() {
+    let generator = WaypointGenerator {
+        count: 4,
+        heading: -PI / 4.0,
+        min_segment: 0.15,
+        max_segment: 0.25,
+        max_turn: 0.2,
+        ..WaypointGenerator::new(WaypointPattern::Directed { jitter: 0.3 })
+    };
+
+    let segments = segments(&generator.generate(7).waypoints);
+
+    // the first segment is free to jitter, later ones are bound by the turn limit
+    assert!(turn(segments[0].0, -PI / 4.0) <= 0.3 + EPSILON);
+
+    for pair in segments.windows(2) {
+        assert!(turn(pair[0].0, pair[1].0) <= 0.2 + EPSILON);
+    }
+}
+
+#[test]
+fn zigzag() {
+    let generator = WaypointGenerator {
+        count: 5,
+        start: (0.0, 0.5),
+        heading: 0.0,
+        ..WaypointGenerator::new(WaypointPattern::ZigZag {
+            amplitude: 0.1,
+            frequency: 2.0,
+        })
+    };
+
+    let waypoints = generator.generate(0).waypoints;
+    let expected = [
+        (0.0, 0.5),
+        (0.125, 0.6),
+        (0.375, 0.4),
+        (0.625, 0.6),
+        (0.875, 0.4),
+    ];
+
+    for (&(x, y), &(ex, ey)) in waypoints.iter().zip(expected.iter()) {
+        assert!((x - ex).abs() < EPSILON && (y - ey).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn spiral() {
+    let generator = WaypointGenerator {
+        count: 12,
+        min_segment: 0.0,
+        max_segment: 1.0,
+        max_turn: PI,
+        ..WaypointGenerator::new(WaypointPattern::Spiral { turns: 1.5 })
+    };
+
+    let waypoints = generator.generate(0).waypoints;
+    let radii: Vec<_> = waypoints
+        .iter()
+        .map(|&(x, y)| (x - 0.5).hypot(y - 0.5))
+        .collect();
+
+    assert_eq!(waypoints[0], (0.5, 0.5));
+    assert!(radii.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn generated_along_waypoints() {
+    let waypoints = WaypointGenerator::new(WaypointPattern::Directed { jitter: 0.4 })
+        .generate(3)
+        .waypoints;
+
+    let mut pipeline = Pipeline {
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 60.0;
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    assert!(generator.generate(waypoints, 0).is_ok());
+}
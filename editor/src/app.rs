@@ -1,4 +1,9 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{mpsc, Arc},
+};
 
 use egui_wgpu::wgpu::{
     self, InstanceDescriptor, PowerPreference, RequestAdapterOptions, TextureFormat,
@@ -21,7 +26,7 @@ use crate::components::{
     map::TwGpuComponent,
     ui::{
         bottom_panel::BottomPanelUi, context::UiContext, float::FloatWindowUi,
-        left_panel::LeftPanelUi, UiComponent,
+        left_panel::LeftPanelUi, waypoints::WaypointOverlayUi, UiComponent,
     },
     AppComponent,
 };
@@ -118,18 +123,22 @@ impl<'w, 'a> App<'w, 'a> {
 
         // TODO: ugly
 
-        let bottom_panel = BottomPanelUi::new();
+        let (map_sender, map_receiver) = mpsc::channel();
+
+        let bottom_panel = BottomPanelUi::new(map_sender);
         let generation = bottom_panel.get_generation_handle();
         let twgpu = Box::new(TwGpuComponent::new(
             width,
             height,
             wgpu_context.clone(),
-            generation,
+            generation.clone(),
+            map_receiver,
         ));
         let map_loader = twgpu.get_map_loader_handle();
 
         let mut ui_context = UiContext::new();
 
+        ui_context.add_renderable(WaypointOverlayUi::new(generation));
         ui_context.add_renderable(LeftPanelUi::new(map_loader));
         ui_context.add_renderable(bottom_panel);
         ui_context.add_renderable(FloatWindowUi {});
@@ -151,93 +160,88 @@ impl<'w, 'a> App<'w, 'a> {
         self.event_loop.run(|event, target| {
             target.set_control_flow(ControlFlow::Poll);
 
-            match event {
-                Event::WindowEvent {
-                    event: window_event,
-                    ..
-                } => {
-                    // process user input from top layer to bottom
-                    for component in self.components.iter_mut().rev() {
-                        if component.on_user_input(&self.window, &window_event) {
-                            break;
-                        }
+            if let Event::WindowEvent {
+                event: window_event,
+                ..
+            } = event
+            {
+                // process user input from top layer to bottom
+                for component in self.components.iter_mut().rev() {
+                    if component.on_user_input(&self.window, &window_event) {
+                        break;
                     }
+                }
 
-                    if let WindowEvent::RedrawRequested = window_event {
-                        let surface_texture = self.surface.get_current_texture().ok();
-                        let mut render_context = None;
-
-                        if let Some(frame) = &surface_texture {
-                            let surface_view =
-                                frame.texture.create_view(&TextureViewDescriptor::default());
-
-                            let mut command_encoders = HashMap::new();
-
-                            for component in self.components.iter() {
-                                command_encoders.insert(
-                                    component.label(),
-                                    self.wgpu_context.borrow().device.create_command_encoder(
-                                        &CommandEncoderDescriptor {
-                                            label: Some(component.label()),
-                                        },
-                                    ),
-                                );
-                            }
-
-                            render_context = Some(RenderContext {
-                                command_encoders,
-                                surface_view,
-                            })
-                        }
+                if let WindowEvent::RedrawRequested = window_event {
+                    let surface_texture = self.surface.get_current_texture().ok();
+                    let mut render_context = None;
 
-                        // process render
-                        for component in self.components.iter_mut() {
-                            component.on_render(
-                                &self.window,
-                                render_context.as_mut(),
-                                &self.wgpu_context,
+                    if let Some(frame) = &surface_texture {
+                        let surface_view =
+                            frame.texture.create_view(&TextureViewDescriptor::default());
+
+                        let mut command_encoders = HashMap::new();
+
+                        for component in self.components.iter() {
+                            command_encoders.insert(
+                                component.label(),
+                                self.wgpu_context.borrow().device.create_command_encoder(
+                                    &CommandEncoderDescriptor {
+                                        label: Some(component.label()),
+                                    },
+                                ),
                             );
                         }
 
-                        if render_context.is_some() {
-                            // send command buffers
-                            for component in self.components.iter_mut() {
-                                let command_encoder = render_context
-                                    .as_mut()
-                                    .unwrap()
-                                    .command_encoders
-                                    .remove(component.label())
-                                    .unwrap();
-
-                                self.wgpu_context
-                                    .borrow()
-                                    .queue
-                                    .submit(Some(command_encoder.finish()));
-                            }
-
-                            surface_texture.unwrap().present();
-                            self.window.request_redraw();
-                        }
+                        render_context = Some(RenderContext {
+                            command_encoders,
+                            surface_view,
+                        })
                     }
 
-                    match window_event {
-                        WindowEvent::Resized(size) => {
-                            self.wgpu_context.borrow_mut().set_size(size);
-                            self.surface.configure(
-                                &self.wgpu_context.borrow().device,
-                                &self.wgpu_context.borrow().config,
-                            );
+                    // process render
+                    for component in self.components.iter_mut() {
+                        component.on_render(
+                            &self.window,
+                            render_context.as_mut(),
+                            &self.wgpu_context,
+                        );
+                    }
 
-                            for component in self.components.iter_mut() {
-                                component.on_resize(size);
-                            }
+                    if let Some(render_context) = render_context.as_mut() {
+                        // send command buffers
+                        for component in self.components.iter_mut() {
+                            let command_encoder = render_context
+                                .command_encoders
+                                .remove(component.label())
+                                .unwrap();
+
+                            self.wgpu_context
+                                .borrow()
+                                .queue
+                                .submit(Some(command_encoder.finish()));
                         }
-                        WindowEvent::CloseRequested => target.exit(),
-                        _ => {}
+
+                        surface_texture.unwrap().present();
+                        self.window.request_redraw();
                     }
                 }
 
-                _ => (),
+                match window_event {
+                    WindowEvent::Resized(size) => {
+                        self.wgpu_context.borrow_mut().set_size(size);
+                        self.surface.configure(
+                            &self.wgpu_context.borrow().device,
+                            &self.wgpu_context.borrow().config,
+                        );
+
+                        for component in self.components.iter_mut() {
+                            component.on_resize(size);
+                        }
+                    }
+                    WindowEvent::CloseRequested => target.exit(),
+                    _ => {}
+                }
             }
         })
     }
@@ -1,4 +1,6 @@
-use std::{cell::RefCell, fs::File, io::Read, path::Path, rc::Rc, time::Instant};
+use std::{
+    cell::RefCell, fs::File, io::Read, path::Path, rc::Rc, sync::mpsc::Receiver, time::Instant,
+};
 
 use image::{codecs::png::PngDecoder, ColorType, ImageDecoder, RgbaImage};
 use twgpu::{
@@ -11,29 +13,49 @@ use vek::Vec2;
 use wgpu::{Color, LoadOp, Operations, RenderPassColorAttachment, RenderPassDescriptor, StoreOp};
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
-    event::{MouseScrollDelta, WindowEvent},
+    event::{DeviceId, ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::ModifiersState,
     window::Window,
 };
 
 use crate::{
     app::{RenderContext, WgpuContext},
-    input_handler::{Cursors, Input, MultiInput},
+    input_handler::{map_position, Cursors, Input, MultiInput},
 };
 
 use super::{utils::generation::GenerationContext, AppComponent};
 
+/// distance in pixels at which the cursor grabs a waypoint or segment
+const GRAB_RADIUS: f32 = 12.0;
+
 pub struct MapLoader {
     wgpu_context: Rc<RefCell<WgpuContext>>,
     static_context: GpuMapStaticContext,
     dynamic_context: Option<(TwMap, GpuMapDynamicContext)>,
+    /// maps finished by the generation
+    generated: Receiver<TwMap>,
 }
 
 impl MapLoader {
-    fn new(static_context: GpuMapStaticContext, wgpu_context: Rc<RefCell<WgpuContext>>) -> Self {
+    fn new(
+        static_context: GpuMapStaticContext,
+        wgpu_context: Rc<RefCell<WgpuContext>>,
+        generated: Receiver<TwMap>,
+    ) -> Self {
         Self {
             static_context,
             dynamic_context: None,
             wgpu_context,
+            generated,
+        }
+    }
+
+    /// loads the latest generated map, if a new one arrived
+    fn receive(&mut self) {
+        if let Some(tw_map) = self.generated.try_iter().last() {
+            self.unload();
+            self.load(tw_map);
+            println!("loaded");
         }
     }
 
@@ -104,6 +126,7 @@ impl GpuMapDynamicContext {
 pub struct TwGpuComponent {
     inputs: MultiInput,
     cursors: Cursors,
+    modifiers: ModifiersState,
 
     camera: Camera,
     old_camera: Camera,
@@ -120,6 +143,7 @@ impl TwGpuComponent {
         height: u32,
         wgpu_context: Rc<RefCell<WgpuContext>>,
         generation: Rc<RefCell<GenerationContext>>,
+        generated: Receiver<TwMap>,
     ) -> Self {
         let render_size: Vec2<f32> = Vec2::new(width, height).az();
 
@@ -134,11 +158,13 @@ impl TwGpuComponent {
         let map_loader = Rc::new(RefCell::new(MapLoader::new(
             static_map_context,
             wgpu_context,
+            generated,
         )));
 
         Self {
             inputs,
             cursors,
+            modifiers: ModifiersState::empty(),
             camera,
             old_camera,
             map_loader,
@@ -150,6 +176,39 @@ impl TwGpuComponent {
     pub fn get_map_loader_handle(&self) -> Rc<RefCell<MapLoader>> {
         self.map_loader.clone()
     }
+
+    /// grab radius in tiles at the current zoom
+    fn grab_radius(&self) -> f32 {
+        GRAB_RADIUS * self.camera.base_dimensions.x * self.camera.zoom.x / self.render_size.x
+    }
+
+    /// Left click drags a waypoint, ctrl + left click inserts one and right click deletes one.
+    /// Returns `true`, if the click was used up by the waypoints
+    fn edit_waypoints(&mut self, id: DeviceId, state: ElementState, button: MouseButton) -> bool {
+        let Some(position) = self.cursors.position(id) else {
+            return false;
+        };
+
+        let position = map_position(&self.camera, self.render_size, position);
+        let radius = self.grab_radius();
+
+        let mut generation = self.generation.borrow_mut();
+        let editor = generation.waypoint_editor_mut();
+
+        match (button, state) {
+            (MouseButton::Left, ElementState::Pressed) if self.modifiers.control_key() => {
+                editor.insert(position, radius);
+                true
+            }
+            (MouseButton::Left, ElementState::Pressed) => editor.grab(position, radius),
+            (MouseButton::Left, ElementState::Released) => {
+                editor.release();
+                false
+            }
+            (MouseButton::Right, ElementState::Pressed) => editor.remove(position, radius),
+            _ => false,
+        }
+    }
 }
 
 impl AppComponent for TwGpuComponent {
@@ -167,11 +226,24 @@ impl AppComponent for TwGpuComponent {
             }
             WindowEvent::CursorLeft { device_id } => self.cursors.left(device_id),
             WindowEvent::CursorEntered { device_id } => self.cursors.entered(device_id),
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
             WindowEvent::CursorMoved {
                 device_id,
                 position,
             } => {
-                if let Some(input) = self.cursors.moved(device_id, position) {
+                let input = self.cursors.moved(device_id, position);
+                let map_pos = map_position(&self.camera, self.render_size, position);
+
+                if self
+                    .generation
+                    .borrow_mut()
+                    .waypoint_editor_mut()
+                    .drag(map_pos)
+                {
+                    return false;
+                }
+
+                if let Some(input) = input {
                     self.inputs
                         .update_input(&input, &mut self.camera, self.render_size);
                 }
@@ -181,6 +253,10 @@ impl AppComponent for TwGpuComponent {
                 state,
                 button,
             } => {
+                if self.edit_waypoints(device_id, state, button) {
+                    return false;
+                }
+
                 if let Some(input) = self.cursors.input(device_id, state, button) {
                     self.inputs
                         .update_input(&input, &mut self.camera, self.render_size);
@@ -221,6 +297,11 @@ impl AppComponent for TwGpuComponent {
             self.cursors.any_position(),
         );
 
+        self.generation
+            .borrow_mut()
+            .waypoint_editor_mut()
+            .set_view(self.camera, self.render_size);
+
         let time = Instant::now().elapsed().as_secs() as i64;
 
         self.map_loader
@@ -239,7 +320,7 @@ impl AppComponent for TwGpuComponent {
                 .begin_render_pass(&RenderPassDescriptor {
                     label: Some(self.label()),
                     color_attachments: &[Some(RenderPassColorAttachment {
-                        view: &frame_view,
+                        view: frame_view,
                         resolve_target: None,
                         ops: Operations {
                             load: LoadOp::Clear(Color {
@@ -275,12 +356,7 @@ impl AppComponent for TwGpuComponent {
 
         self.old_camera = self.camera;
 
-        // hack: weird way to poll
-        if let Some(tw_map) = self.generation.borrow_mut().take_map() {
-            self.map_loader.borrow_mut().unload();
-            self.map_loader.borrow_mut().load(tw_map);
-            println!("loaded");
-        }
+        self.map_loader.borrow_mut().receive();
     }
 
     fn on_resize(&mut self, size: PhysicalSize<u32>) {
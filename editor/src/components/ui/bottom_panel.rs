@@ -1,37 +1,55 @@
-use std::{borrow::Borrow, cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::Sender,
+};
 
 use egui::{emath::Numeric, Color32, Id, Label, RichText, Sense, Ui};
+use egui_file_dialog::{DialogMode, DialogState, FileDialog};
 use egui_snarl::{
     ui::{PinInfo, SnarlStyle, SnarlViewer},
     Snarl,
 };
 use mapgen_core::{
-    brush::Brush,
-    map::Map,
+    block::DesignLayer,
+    block::BlockType,
+    brush::{Brush, BrushOrientation, BrushRim, BrushShape},
+    map::{Map, RoomShape},
     mutations::{
-        brush::{pulse::PulseBrushMutation, transition::TransitionBrushMutation},
-        walker::{
-            backwards::BackwardsWalkerMutation, left::LeftWalkerMutation,
-            random::RandomWalkerMutation, right::RightWalkerMutation,
-            straight::StraightWalkerMutation,
+        brush::pulse::PulseBrushMutation,
+        map::{
+            blob_fill::BlobFillMapMutation, edge_freeze::EdgeFreezeMapMutation,
+            roughen::RoughenMapMutation,
         },
-        Mutator,
     },
+    pipeline::{
+        BrushMutation, GeneratorSettings, MapMutation, Pipeline, WalkerMutation,
+        DEFAULT_WAYPOINTS,
+    },
+    random::ProbableValue,
+    stamp::{BrushStamp, StampChannel, StampConfig},
     walker::Walker,
 };
+use twmap::TwMap;
 
 use crate::components::utils::generation::{
-    DesignImageInfo, DesignInfo, DesignLayer, GenerationContext,
+    pipeline_from_snarl, snarl_from_pipeline, unused_mutation_id, DesignImageInfo, DesignInfo,
+    GenerationContext,
 };
 
 use super::context::RenderableUi;
 
 const UNTYPED_COLOR: Color32 = Color32::from_rgb(0xb0, 0xb0, 0xb0);
+const STAMPS_DIR: &str = "data/stamps";
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum UiNode {
-    GeneratorNode,
-    MutationNode(UiMutation),
+    GeneratorNode(Box<GeneratorSettings>),
+    /// the id names the random stream of the mutation, see `MutationLoop::ids`
+    MutationNode(UiMutation, Option<String>),
     LoopStartNode(Option<usize>),
     LoopEndNode,
 }
@@ -39,8 +57,8 @@ pub enum UiNode {
 impl Titled for UiNode {
     fn title(&self) -> &'static str {
         match self {
-            UiNode::GeneratorNode => "Generator",
-            UiNode::MutationNode(mutation) => mutation.title(),
+            UiNode::GeneratorNode(_) => "Generator",
+            UiNode::MutationNode(mutation, _) => mutation.title(),
             UiNode::LoopStartNode(_) => "LoopStart",
             UiNode::LoopEndNode => "LoopEnd"
         }
@@ -51,28 +69,43 @@ impl UiNode {
     // TODO: it's less ugly, but maybe there's something better
     fn default_all_variants() -> Vec<UiNode> {
         vec![
-            UiNode::GeneratorNode,
+            UiNode::GeneratorNode(Default::default()),
             UiNode::MutationNode(UiMutation::Brush(
-                UiBrushMutation::Pulse(Default::default()),
-            )),
-            UiNode::MutationNode(UiMutation::Brush(UiBrushMutation::Transition(
+                BrushMutation::Pulse(Default::default()),
+            ), None),
+            UiNode::MutationNode(UiMutation::Brush(BrushMutation::Transition(
+                Default::default(),
+            )), None),
+            UiNode::MutationNode(UiMutation::Brush(BrushMutation::Taper(
                 Default::default(),
-            ))),
-            UiNode::MutationNode(UiMutation::Walker(UiWalkerMutation::Straight(
+            )), None),
+            UiNode::MutationNode(UiMutation::Map(MapMutation::EdgeFreeze(
+                EdgeFreezeMapMutation::new(1, true, 0),
+            )), None),
+            UiNode::MutationNode(UiMutation::Map(MapMutation::BlobFill(
+                BlobFillMapMutation::new(16, 16, true, 0),
+            )), None),
+            UiNode::MutationNode(UiMutation::Map(MapMutation::Roughen(
+                RoughenMapMutation::new(4.0, 0.3, true, 0),
+            )), None),
+            UiNode::MutationNode(UiMutation::Walker(WalkerMutation::Straight(
                 Default::default(),
-            ))),
-            UiNode::MutationNode(UiMutation::Walker(UiWalkerMutation::Backwards(
+            )), None),
+            UiNode::MutationNode(UiMutation::Walker(WalkerMutation::Backwards(
                 Default::default(),
-            ))),
-            UiNode::MutationNode(UiMutation::Walker(UiWalkerMutation::Left(
+            )), None),
+            UiNode::MutationNode(UiMutation::Walker(WalkerMutation::Left(
                 Default::default(),
-            ))),
-            UiNode::MutationNode(UiMutation::Walker(UiWalkerMutation::Right(
+            )), None),
+            UiNode::MutationNode(UiMutation::Walker(WalkerMutation::Right(
                 Default::default(),
-            ))),
-            UiNode::MutationNode(UiMutation::Walker(UiWalkerMutation::Random(
+            )), None),
+            UiNode::MutationNode(UiMutation::Walker(WalkerMutation::Random(
                 Default::default(),
-            ))),
+            )), None),
+            UiNode::MutationNode(UiMutation::Walker(WalkerMutation::Weighted(
+                Default::default(),
+            )), None),
             UiNode::LoopStartNode(None),
             UiNode::LoopEndNode
         ]
@@ -81,9 +114,9 @@ impl UiNode {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum UiMutation {
-    Brush(UiBrushMutation),
-    Map(UiMapMutation),
-    Walker(UiWalkerMutation),
+    Brush(BrushMutation),
+    Map(MapMutation),
+    Walker(WalkerMutation),
 }
 
 impl UiMutation {
@@ -97,7 +130,7 @@ impl UiMutation {
 }
 
 pub trait ExtractMutation<GivenType> {
-    type ExtractType: ExtractMutation<GivenType> + Titled;
+    type ExtractType: Titled + Clone;
     const INPUT: usize = 0;
 
     fn extract(&self) -> Option<Self::ExtractType> {
@@ -106,7 +139,7 @@ pub trait ExtractMutation<GivenType> {
 }
 
 impl ExtractMutation<Brush> for UiMutation {
-    type ExtractType = UiBrushMutation;
+    type ExtractType = BrushMutation;
 
     fn extract(&self) -> Option<Self::ExtractType> {
         match self {
@@ -119,7 +152,7 @@ impl ExtractMutation<Brush> for UiMutation {
 }
 
 impl ExtractMutation<Map> for UiMutation {
-    type ExtractType = UiMapMutation;
+    type ExtractType = MapMutation;
 
     fn extract(&self) -> Option<Self::ExtractType> {
         match self {
@@ -132,7 +165,7 @@ impl ExtractMutation<Map> for UiMutation {
 }
 
 impl ExtractMutation<Walker> for UiMutation {
-    type ExtractType = UiWalkerMutation;
+    type ExtractType = WalkerMutation;
 
     fn extract(&self) -> Option<Self::ExtractType> {
         match self {
@@ -144,103 +177,35 @@ impl ExtractMutation<Walker> for UiMutation {
     const INPUT: usize = 2;
 }
 
-impl ExtractMutation<Brush> for UiBrushMutation {
-    type ExtractType = Box<dyn Mutator<Brush>>;
-
-    fn extract(&self) -> Option<Self::ExtractType> {
-        Some(match self {
-            UiBrushMutation::Pulse(mutation) => Box::new(mutation.clone()),
-            UiBrushMutation::Transition(mutation) => Box::new(mutation.clone()),
-        })
-    }
-}
-
-impl ExtractMutation<Map> for UiMapMutation {
-    type ExtractType = Box<dyn Mutator<Map>>;
-
-    fn extract(&self) -> Option<Self::ExtractType> {
-        None
-    }
-}
-
-impl ExtractMutation<Walker> for UiWalkerMutation {
-    type ExtractType = Box<dyn Mutator<Walker>>;
-
-    fn extract(&self) -> Option<Self::ExtractType> {
-        Some(match self {
-            UiWalkerMutation::Straight(mutation) => {
-                println!("REAL: {}", mutation.overall_steps);
-                Box::new(mutation.clone())
-            }
-            UiWalkerMutation::Backwards(mutation) => Box::new(mutation.clone()),
-            UiWalkerMutation::Left(mutation) => Box::new(mutation.clone()),
-            UiWalkerMutation::Right(mutation) => Box::new(mutation.clone()),
-            UiWalkerMutation::Random(mutation) => Box::new(mutation.clone()),
-        })
-    }
-}
-
-impl<M> ExtractMutation<M> for Box<dyn Mutator<M>> {
-    type ExtractType = ();
-}
-
-impl<M> ExtractMutation<M> for () {
-    type ExtractType = ();
-}
-
-impl Titled for () {
-    fn title(&self) -> &'static str {
-        ""
-    }
-}
-
-impl<T> Titled for Box<dyn Mutator<T>> {
-    fn title(&self) -> &'static str {
-        "Mutator"
-    }
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum UiBrushMutation {
-    Pulse(PulseBrushMutation),
-    Transition(TransitionBrushMutation),
-}
-
-impl Titled for UiBrushMutation {
+impl Titled for BrushMutation {
     fn title(&self) -> &'static str {
         match self {
-            UiBrushMutation::Pulse(_) => "Pulse",
-            UiBrushMutation::Transition(_) => "Transition",
+            BrushMutation::Pulse(_) => "Pulse",
+            BrushMutation::Transition(_) => "Transition",
+            BrushMutation::Taper(_) => "Taper",
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum UiMapMutation {}
-
-impl Titled for UiMapMutation {
+impl Titled for MapMutation {
     fn title(&self) -> &'static str {
-        unreachable!()
+        match self {
+            MapMutation::EdgeFreeze(_) => "EdgeFreeze",
+            MapMutation::BlobFill(_) => "BlobFill",
+            MapMutation::Roughen(_) => "Roughen",
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum UiWalkerMutation {
-    Straight(StraightWalkerMutation),
-    Backwards(BackwardsWalkerMutation),
-    Left(LeftWalkerMutation),
-    Right(RightWalkerMutation),
-    Random(RandomWalkerMutation),
-}
-
-impl Titled for UiWalkerMutation {
+impl Titled for WalkerMutation {
     fn title(&self) -> &'static str {
         match self {
-            UiWalkerMutation::Straight(_) => "Straight",
-            UiWalkerMutation::Backwards(_) => "Backwards",
-            UiWalkerMutation::Left(_) => "Left",
-            UiWalkerMutation::Right(_) => "Right",
-            UiWalkerMutation::Random(_) => "Random",
+            WalkerMutation::Straight(_) => "Straight",
+            WalkerMutation::Backwards(_) => "Backwards",
+            WalkerMutation::Left(_) => "Left",
+            WalkerMutation::Right(_) => "Right",
+            WalkerMutation::Random(_) => "Random",
+            WalkerMutation::Weighted(_) => "Weighted",
         }
     }
 }
@@ -260,8 +225,8 @@ impl SnarlViewer<UiNode> for UiViewer {
 
     fn outputs(&mut self, node: &UiNode) -> usize {
         match node {
-            UiNode::GeneratorNode => 0,
-            UiNode::MutationNode(_) => 1,
+            UiNode::GeneratorNode(_) => 0,
+            UiNode::MutationNode(..) => 1,
             UiNode::LoopStartNode(_)
             | UiNode::LoopEndNode => 1
         }
@@ -269,8 +234,8 @@ impl SnarlViewer<UiNode> for UiViewer {
 
     fn inputs(&mut self, node: &UiNode) -> usize {
         match node {
-            UiNode::GeneratorNode => 3,
-            UiNode::MutationNode(_) => 1,
+            UiNode::GeneratorNode(_) => 3,
+            UiNode::MutationNode(..) => 1,
             UiNode::LoopStartNode(_)
             | UiNode::LoopEndNode => 1
         }
@@ -278,10 +243,10 @@ impl SnarlViewer<UiNode> for UiViewer {
 
     fn show_input(
         &mut self,
-        pin: &egui_snarl::InPin,
+        _pin: &egui_snarl::InPin,
         ui: &mut egui::Ui,
-        scale: f32,
-        snarl: &mut Snarl<UiNode>,
+        _scale: f32,
+        _snarl: &mut Snarl<UiNode>,
     ) -> PinInfo {
         ui.label("Prev");
         PinInfo::circle().with_fill(UNTYPED_COLOR)
@@ -289,102 +254,274 @@ impl SnarlViewer<UiNode> for UiViewer {
 
     fn show_output(
         &mut self,
-        pin: &egui_snarl::OutPin,
+        _pin: &egui_snarl::OutPin,
         ui: &mut egui::Ui,
-        scale: f32,
-        snarl: &mut Snarl<UiNode>,
+        _scale: f32,
+        _snarl: &mut Snarl<UiNode>,
     ) -> egui_snarl::ui::PinInfo {
         ui.label("Next");
         PinInfo::circle().with_fill(UNTYPED_COLOR)
     }
 
-    fn has_body(&mut self, node: &UiNode) -> bool {
+    fn has_body(&mut self, _node: &UiNode) -> bool {
         true
     }
 
     fn show_body(
         &mut self,
         node: egui_snarl::NodeId,
-        inputs: &[egui_snarl::InPin],
-        outputs: &[egui_snarl::OutPin],
+        _inputs: &[egui_snarl::InPin],
+        _outputs: &[egui_snarl::OutPin],
         ui: &mut Ui,
-        scale: f32,
+        _scale: f32,
         snarl: &mut Snarl<UiNode>,
     ) {
         let id = format!("{}_grid", snarl[node].title());
 
         match &mut snarl[node] {
-            UiNode::GeneratorNode => {
-                if ui.button("Proceed").clicked() {
-                    let mut image_infos = HashMap::new();
-
-                    image_infos.insert(
-                        DesignLayer::Freeze,
-                        DesignImageInfo::new("data/mapres/entities.png", 1),
-                    );
-                    image_infos.insert(
-                        DesignLayer::Hookable,
-                        DesignImageInfo::new("data/mapres/jungle_main.png", 2),
-                    );
-                    image_infos.insert(
-                        DesignLayer::Unhookable,
-                        DesignImageInfo::new("data/mapres/entities.png", 3),
-                    );
-
-                    let design = DesignInfo::new(image_infos);
-                    self.generation.borrow_mut().set_scale_factor(200.0);
-                    self.generation.borrow_mut().generate(
-                        snarl,
-                        node,
-                        &design,
-                        vec![
-                            (0.0, 1.0),
-                            (0.2, 0.8),
-                            (0.4, 0.6),
-                            (0.6, 0.4),
-                            (0.8, 0.2),
-                            (1.0, 0.0),
-                        ],
-                    );
+            UiNode::GeneratorNode(settings) => {
+                egui::Grid::new(&id).show(ui, |ui| {
+                    field_numeric(ui, "ScaleFactor", &mut settings.scale_factor);
+                    field_numeric(ui, "Seed", &mut settings.seed);
+                    field_numeric(ui, "Padding", &mut settings.padding);
+                    field_brush_shape(ui, &id, &mut settings.brush_shape);
+                    field_brush_rims(ui, &id, &mut settings.brush_rims);
+                    field_brush_orientation(ui, &id, &mut settings.brush_orientation);
+
+                    let mut freeze_enabled = settings.freeze.is_some();
+                    ui.label("Freeze");
+                    ui.checkbox(&mut freeze_enabled, "");
+                    ui.end_row();
+
+                    match (freeze_enabled, &mut settings.freeze) {
+                        (true, Some(freeze)) => {
+                            field_numeric(ui, "Thickness", &mut freeze.thickness);
+                            field_numeric(ui, "Thinning", &mut freeze.thinning);
+                            field_numeric(ui, "NoiseAmplitude", &mut freeze.noise_amplitude);
+                            field_numeric(ui, "NoiseScale", &mut freeze.noise_scale);
+                            field_numeric(ui, "MinCorridor", &mut freeze.min_corridor);
+                        }
+                        (true, freeze) => *freeze = Some(Default::default()),
+                        (false, freeze) => *freeze = None,
+                    }
+
+                    let mut rooms_enabled = settings.rooms.is_some();
+                    ui.label("Rooms");
+                    ui.checkbox(&mut rooms_enabled, "");
+                    ui.end_row();
+
+                    match (rooms_enabled, &mut settings.rooms) {
+                        (true, Some(rooms)) => {
+                            field_numeric(ui, "Width", &mut rooms.width);
+                            field_numeric(ui, "Height", &mut rooms.height);
+                            ui.label("Ellipse");
+                            let mut ellipse = rooms.shape == RoomShape::Ellipse;
+                            if ui.checkbox(&mut ellipse, "").changed() {
+                                rooms.shape = match ellipse {
+                                    true => RoomShape::Ellipse,
+                                    false => RoomShape::Rect,
+                                };
+                            }
+                            ui.end_row();
+                            field_numeric(ui, "SpawnCount", &mut rooms.spawn_count);
+                        }
+                        (true, rooms) => *rooms = Some(Default::default()),
+                        (false, rooms) => *rooms = None,
+                    }
+
+                    // mutations of branches are kept as loaded, they aren't nodes
+                    let mut branches_enabled = settings.branches.is_some();
+                    ui.label("Branches");
+                    ui.checkbox(&mut branches_enabled, "");
+                    ui.end_row();
+
+                    match (branches_enabled, &mut settings.branches) {
+                        (true, Some(branches)) => {
+                            let config = &mut branches.config;
+                            field_numeric(ui, "Probability", &mut config.probability);
+                            field_numeric(ui, "MaxActive", &mut config.max_active);
+                            field_numeric(ui, "Lifetime", &mut config.lifetime);
+                            field_numeric(ui, "RejoinChance", &mut config.rejoin_chance);
+                            field_numeric(ui, "BrushSize", &mut config.brush_size);
+                        }
+                        (true, branches) => *branches = Some(Default::default()),
+                        (false, branches) => *branches = None,
+                    }
+                });
+
+                // waypoints are edited on the map view
+                ui.horizontal(|ui| {
+                    let count = self.generation.borrow().waypoint_editor().waypoints().len();
+                    ui.label(format!("Waypoints: {}", count));
+
+                    if ui.button("Reset").clicked() {
+                        self.generation
+                            .borrow_mut()
+                            .waypoint_editor_mut()
+                            .set_waypoints(DEFAULT_WAYPOINTS.to_vec());
+                    }
+                });
+
+                let progress = self.generation.borrow().progress();
+
+                match progress {
+                    Some(progress) => {
+                        let reached = progress.reached as f32 / progress.waypoints.max(1) as f32;
+                        let steps = progress.steps as f32 / progress.max_steps.max(1) as f32;
+
+                        ui.add(egui::ProgressBar::new(reached).text(format!(
+                            "waypoint {}/{}",
+                            progress.reached, progress.waypoints
+                        )));
+                        let steps_text = match progress.arrived {
+                            true => format!("{} steps, finishing branches", progress.steps),
+                            false => format!("{} steps", progress.steps),
+                        };
+
+                        ui.add(egui::ProgressBar::new(steps).text(steps_text));
+
+                        if ui.button("Cancel").clicked() {
+                            self.generation.borrow().cancel();
+                        }
+                    }
+                    None => {
+                        if ui.button("Proceed").clicked() {
+                            let mut image_infos = HashMap::new();
+
+                            image_infos.insert(
+                                DesignLayer::Freeze,
+                                DesignImageInfo::new("data/mapres/entities.png", 1),
+                            );
+                            image_infos.insert(
+                                DesignLayer::Hookable,
+                                DesignImageInfo::new("data/mapres/jungle_main.png", 0),
+                            );
+                            image_infos.insert(
+                                DesignLayer::Unhookable,
+                                DesignImageInfo::new("data/mapres/entities.png", 3),
+                            );
+
+                            let design = DesignInfo::new(image_infos);
+                            self.generation
+                                .borrow_mut()
+                                .generate(snarl, node, design);
+                        }
+                    }
+                }
+
+                ui.label(self.generation.borrow().status());
+
+                for warning in self.generation.borrow().warnings() {
+                    ui.colored_label(Color32::YELLOW, warning);
+                }
+
+                if let Some(report) = self.generation.borrow().report() {
+                    let optional = |value: Option<usize>| match value {
+                        Some(value) => value.to_string(),
+                        None => "-".to_string(),
+                    };
+
+                    egui::CollapsingHeader::new("Report").show(ui, |ui| {
+                        egui::Grid::new(format!("{}_report", id)).show(ui, |ui| {
+                            let mut row = |name: &str, value: String| {
+                                ui.label(name);
+                                ui.label(value);
+                                ui.end_row();
+                            };
+
+                            row("PathLength", optional(report.path_length));
+                            row("Turns", optional(report.turns));
+                            row("DeadEnds", optional(report.dead_ends));
+                            row(
+                                "MeanWidth",
+                                format!("{:.1}", report.mean_corridor_width()),
+                            );
+                            row("Freeze", format!("{:.2}", report.freeze_ratio));
+                            row("Hookable", format!("{:.2}", report.hookable_ratio));
+                            row("Empty", format!("{:.2}", report.empty_ratio));
+
+                            if let Some(bounds) = report.bounding_box {
+                                row("Size", format!("{}x{}", bounds.width, bounds.height));
+                            }
+
+                            if let Some(trace) = self.generation.borrow().trace() {
+                                let steps = trace.main().map_or(0, |main| main.steps.len());
+                                row("Steps", steps.to_string());
+                                row("Branches", trace.branches().count().to_string());
+                            }
+                        });
+                    });
                 }
             }
-            UiNode::MutationNode(mutation) => match mutation {
+            UiNode::MutationNode(mutation, _) => match mutation {
                 UiMutation::Brush(mutation) => match mutation {
-                    UiBrushMutation::Pulse(ref mut mutation) => {
+                    BrushMutation::Pulse(ref mut mutation) => {
                         egui::Grid::new(id).show(ui, |ui| {
                             field_numeric(ui, "BorderValue", &mut mutation.value_border);
                             field_numeric(ui, "ClimaxValue", &mut mutation.value_climax);
                             field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
                         });
                     }
-                    UiBrushMutation::Transition(ref mut mutation) => {
+                    BrushMutation::Transition(ref mut mutation) => {
                         egui::Grid::new(id).show(ui, |ui| {
                             field_numeric(ui, "FromValue", &mut mutation.value_from);
                             field_numeric(ui, "ToValue", &mut mutation.value_to);
                             field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
                         });
                     }
+                    BrushMutation::Taper(ref mut mutation) => {
+                        egui::Grid::new(id).show(ui, |ui| {
+                            field_numeric(ui, "FromValue", &mut mutation.value_from);
+                            field_numeric(ui, "ToValue", &mut mutation.value_to);
+                        });
+                    }
                 },
                 UiMutation::Map(mutation) => match mutation {
-                    _ => {}
+                    MapMutation::EdgeFreeze(ref mut mutation) => {
+                        egui::Grid::new(id).show(ui, |ui| {
+                            field_numeric(ui, "Thickness", &mut mutation.thickness);
+                            field_pass(ui, &mut mutation.at_end, &mut mutation.overall_steps);
+                        });
+                    }
+                    MapMutation::BlobFill(ref mut mutation) => {
+                        egui::Grid::new(id).show(ui, |ui| {
+                            field_numeric(ui, "MaxEmptySize", &mut mutation.max_empty_size);
+                            field_numeric(ui, "MaxWallSize", &mut mutation.max_wall_size);
+                            field_pass(ui, &mut mutation.at_end, &mut mutation.overall_steps);
+                        });
+                    }
+                    MapMutation::Roughen(ref mut mutation) => {
+                        egui::Grid::new(id).show(ui, |ui| {
+                            field_numeric(ui, "Scale", &mut mutation.scale);
+                            field_numeric(ui, "Strength", &mut mutation.strength);
+                            field_pass(ui, &mut mutation.at_end, &mut mutation.overall_steps);
+                        });
+                    }
                 },
                 UiMutation::Walker(mutation) => match mutation {
-                    UiWalkerMutation::Straight(ref mut mutation) => {
+                    WalkerMutation::Straight(ref mut mutation) => {
                         field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
                     }
-                    UiWalkerMutation::Backwards(ref mut mutation) => {
+                    WalkerMutation::Backwards(ref mut mutation) => {
                         field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
                     }
-                    UiWalkerMutation::Left(ref mut mutation) => {
+                    WalkerMutation::Left(ref mut mutation) => {
                         field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
                     }
-                    UiWalkerMutation::Right(ref mut mutation) => {
+                    WalkerMutation::Right(ref mut mutation) => {
                         field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
                     }
-                    UiWalkerMutation::Random(ref mut mutation) => {
+                    WalkerMutation::Random(ref mut mutation) => {
+                        field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
+                    }
+                    WalkerMutation::Weighted(ref mut mutation) => {
                         egui::Grid::new(id).show(ui, |ui| {
-                            field_numeric(ui, "Seed", &mut mutation.seed);
                             field_numeric(ui, "OverallSteps", &mut mutation.overall_steps);
+                            let values = mutation.dist.config.values.iter_mut();
+                            for ProbableValue(weight, turn) in values {
+                                field_numeric(ui, turn.name(), weight);
+                            }
+                            field_numeric(ui, "Momentum", &mut mutation.momentum);
                         });
                     }
                 },
@@ -406,18 +543,18 @@ impl SnarlViewer<UiNode> for UiViewer {
 
     fn input_color(
         &mut self,
-        pin: &egui_snarl::InPin,
-        style: &egui::Style,
-        snarl: &mut Snarl<UiNode>,
+        _pin: &egui_snarl::InPin,
+        _style: &egui::Style,
+        _snarl: &mut Snarl<UiNode>,
     ) -> egui::Color32 {
         UNTYPED_COLOR
     }
 
     fn output_color(
         &mut self,
-        pin: &egui_snarl::OutPin,
-        style: &egui::Style,
-        snarl: &mut Snarl<UiNode>,
+        _pin: &egui_snarl::OutPin,
+        _style: &egui::Style,
+        _snarl: &mut Snarl<UiNode>,
     ) -> egui::Color32 {
         UNTYPED_COLOR
     }
@@ -430,10 +567,10 @@ impl SnarlViewer<UiNode> for UiViewer {
         ui.label("Add Node");
         ui.separator();
 
-        for i in 0..all_variants.len() {
+        for (i, variant) in all_variants.iter().enumerate() {
             if ui
                 .add(
-                    Label::new(RichText::new(all_variants[i].title()).monospace())
+                    Label::new(RichText::new(variant.title()).monospace())
                         .sense(Sense::click()),
                 )
                 .clicked()
@@ -444,7 +581,11 @@ impl SnarlViewer<UiNode> for UiViewer {
         }
 
         if let Some(i) = selected {
-            let node = all_variants[i].clone();
+            let mut node = all_variants[i].clone();
+
+            if let UiNode::MutationNode(_, id) = &mut node {
+                *id = Some(unused_mutation_id(snarl));
+            }
 
             snarl.insert_node(pos, node);
         }
@@ -458,59 +599,52 @@ impl SnarlViewer<UiNode> for UiViewer {
         snarl: &mut Snarl<UiNode>,
     ) {
         match (&snarl[from.id.node], &snarl[to.id.node]) {
-            (UiNode::MutationNode(mutation), UiNode::GeneratorNode) => {
-                let eh_stop_it;
-
-                match mutation {
+            (UiNode::MutationNode(mutation, _), UiNode::GeneratorNode(_)) => {
+                let eh_stop_it = match mutation {
                     UiMutation::Brush(_) => {
-                        eh_stop_it = to.id.input == <UiMutation as ExtractMutation<Brush>>::INPUT
-                    }
-                    UiMutation::Map(_) => {
-                        eh_stop_it = to.id.input == <UiMutation as ExtractMutation<Map>>::INPUT
+                        to.id.input == <UiMutation as ExtractMutation<Brush>>::INPUT
                     }
+                    UiMutation::Map(_) => to.id.input == <UiMutation as ExtractMutation<Map>>::INPUT,
                     UiMutation::Walker(_) => {
-                        eh_stop_it = to.id.input == <UiMutation as ExtractMutation<Walker>>::INPUT
+                        to.id.input == <UiMutation as ExtractMutation<Walker>>::INPUT
                     }
-                }
+                };
 
                 if !eh_stop_it {
                     return;
                 }
             }
-            (UiNode::GeneratorNode, UiNode::MutationNode(mutation)) => {
-                let eh_stop_it;
-
-                match mutation {
+            (UiNode::GeneratorNode(_), UiNode::MutationNode(mutation, _)) => {
+                let eh_stop_it = match mutation {
                     UiMutation::Brush(_) => {
-                        eh_stop_it = from.id.output == <UiMutation as ExtractMutation<Brush>>::INPUT
+                        from.id.output == <UiMutation as ExtractMutation<Brush>>::INPUT
                     }
                     UiMutation::Map(_) => {
-                        eh_stop_it = from.id.output == <UiMutation as ExtractMutation<Map>>::INPUT
+                        from.id.output == <UiMutation as ExtractMutation<Map>>::INPUT
                     }
                     UiMutation::Walker(_) => {
-                        eh_stop_it =
-                            from.id.output == <UiMutation as ExtractMutation<Walker>>::INPUT
+                        from.id.output == <UiMutation as ExtractMutation<Walker>>::INPUT
                     }
-                }
+                };
 
                 if eh_stop_it {
                     return;
                 }
             }
-            (UiNode::LoopStartNode(_) | UiNode::LoopEndNode, UiNode::MutationNode(_)) => {},
-            (UiNode::LoopStartNode(_) | UiNode::LoopEndNode, UiNode::GeneratorNode) => {}
-            (UiNode::MutationNode(_), UiNode::LoopStartNode(_) | UiNode::LoopEndNode) => {}
+            (UiNode::LoopStartNode(_) | UiNode::LoopEndNode, UiNode::MutationNode(..)) => {},
+            (UiNode::LoopStartNode(_) | UiNode::LoopEndNode, UiNode::GeneratorNode(_)) => {}
+            (UiNode::MutationNode(..), UiNode::LoopStartNode(_) | UiNode::LoopEndNode) => {}
             (
-                UiNode::MutationNode(UiMutation::Brush(_)),
-                UiNode::MutationNode(UiMutation::Brush(_)),
+                UiNode::MutationNode(UiMutation::Brush(_), _),
+                UiNode::MutationNode(UiMutation::Brush(_), _),
             ) => {}
             (
-                UiNode::MutationNode(UiMutation::Map(_)),
-                UiNode::MutationNode(UiMutation::Map(_)),
+                UiNode::MutationNode(UiMutation::Map(_), _),
+                UiNode::MutationNode(UiMutation::Map(_), _),
             ) => {}
             (
-                UiNode::MutationNode(UiMutation::Walker(_)),
-                UiNode::MutationNode(UiMutation::Walker(_)),
+                UiNode::MutationNode(UiMutation::Walker(_), _),
+                UiNode::MutationNode(UiMutation::Walker(_), _),
             ) => {}
             _ => return,
         }
@@ -527,29 +661,73 @@ pub struct BottomPanelUi {
     snarl: Snarl<UiNode>,
     style: SnarlStyle,
     viewer: UiViewer,
+
+    file_dialog: FileDialog,
+    /// result of the last pipeline save/load
+    status: String,
 }
 
 impl BottomPanelUi {
-    pub fn new() -> Self {
+    pub fn new(maps: Sender<TwMap>) -> Self {
         let mut snarl = Snarl::new();
 
         snarl.insert_node(
             egui::pos2(-190.0, 0.0),
-            UiNode::MutationNode(UiMutation::Brush(UiBrushMutation::Pulse(
+            UiNode::MutationNode(UiMutation::Brush(BrushMutation::Pulse(
                 PulseBrushMutation::new(1, 20, 200, 0.5),
-            ))),
+            )), Some("0".to_string())),
+        );
+        snarl.insert_node(
+            egui::pos2(240.0, 0.0),
+            UiNode::GeneratorNode(Default::default()),
         );
-        snarl.insert_node(egui::pos2(240.0, 0.0), UiNode::GeneratorNode);
 
         Self {
             snarl,
             style: SnarlStyle::new(),
             viewer: UiViewer {
-                generation: Rc::new(RefCell::new(GenerationContext::new())),
+                generation: Rc::new(RefCell::new(GenerationContext::new(maps))),
             },
+            file_dialog: FileDialog::new(),
+            status: String::new(),
         }
     }
 
+    fn save_pipeline(&self, path: &Path) -> Result<(), String> {
+        let generator_node = self
+            .snarl
+            .node_ids()
+            .find(|(_, node)| matches!(node, UiNode::GeneratorNode(_)))
+            .map(|(id, _)| id)
+            .ok_or("no generator node")?;
+
+        let mut pipeline = pipeline_from_snarl(&self.snarl, generator_node)
+            .ok_or("mutations must be placed between LoopStart and LoopEnd")?;
+
+        pipeline.generator.waypoints = self
+            .viewer
+            .generation
+            .borrow()
+            .waypoint_editor()
+            .waypoints()
+            .to_vec();
+
+        pipeline.save(path).map_err(|err| err.to_string())
+    }
+
+    fn load_pipeline(&mut self, path: &Path) -> Result<(), String> {
+        let pipeline = Pipeline::load(path).map_err(|err| err.to_string())?;
+
+        self.snarl = snarl_from_pipeline(&pipeline);
+        self.viewer
+            .generation
+            .borrow_mut()
+            .waypoint_editor_mut()
+            .set_waypoints(pipeline.generator.waypoints);
+
+        Ok(())
+    }
+
     pub fn get_generation_handle(&self) -> Rc<RefCell<GenerationContext>> {
         self.viewer.generation.clone()
     }
@@ -557,9 +735,39 @@ impl BottomPanelUi {
 
 impl RenderableUi for BottomPanelUi {
     fn ui_with(&mut self, ctx: &egui::Context) {
+        self.viewer.generation.borrow_mut().poll();
+
         egui::panel::TopBottomPanel::bottom("main_bottom_panel")
             .resizable(true)
             .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Save pipeline").clicked() {
+                        self.file_dialog.save_file();
+                    }
+                    if ui.button("Load pipeline").clicked() {
+                        self.file_dialog.select_file();
+                    }
+                    ui.label(&self.status);
+                });
+
+                if self.file_dialog.state() == DialogState::Open {
+                    if let Some(path) = self.file_dialog.update(ctx).selected() {
+                        let path = path.to_path_buf();
+
+                        let (action, result) = match self.file_dialog.mode() {
+                            DialogMode::SaveFile => ("save", self.save_pipeline(&path)),
+                            _ => ("load", self.load_pipeline(&path)),
+                        };
+
+                        self.status = match result {
+                            Ok(()) => format!("{}d '{}'", action, path.display()),
+                            Err(err) => {
+                                format!("failed to {} '{}': {}", action, path.display(), err)
+                            }
+                        };
+                    }
+                }
+
                 self.snarl
                     .show(&mut self.viewer, &self.style, Id::new("node_graph"), ui);
             });
@@ -572,3 +780,195 @@ fn field_numeric(ui: &mut Ui, name: impl Into<String>, value: &mut impl Numeric)
     ui.add(drag_value);
     ui.end_row();
 }
+
+fn field_brush_shape(ui: &mut Ui, id: &str, shape: &mut BrushShape) {
+    let stamps = stamp_paths();
+    let shapes = [
+        BrushShape::default(),
+        BrushShape::Rectangle { aspect: 1.0 },
+        BrushShape::Diamond,
+        BrushShape::Superellipse { exponent: 4.0 },
+        BrushShape::Blob {
+            seed: 0,
+            roughness: 0.3,
+        },
+    ];
+
+    ui.label("BrushShape");
+    egui::ComboBox::from_id_source(format!("{}_brush_shape", id))
+        .selected_text(shape.name())
+        .show_ui(ui, |ui| {
+            for option in shapes {
+                let name = option.name();
+                let selected = shape.name() == name;
+
+                if ui.selectable_label(selected, name).clicked() && !selected {
+                    *shape = option;
+                }
+            }
+
+            let selected = matches!(shape, BrushShape::Stamp(_));
+
+            if ui.selectable_label(selected, "stamp").clicked() && !selected {
+                let loaded = stamps.first().and_then(|path| {
+                    BrushStamp::load(StampConfig {
+                        path: path.clone(),
+                        ..Default::default()
+                    })
+                    .ok()
+                });
+
+                if let Some(stamp) = loaded {
+                    *shape = BrushShape::Stamp(stamp);
+                }
+            }
+        });
+    ui.end_row();
+
+    match shape {
+        BrushShape::Circle { circularity } => field_numeric(ui, "Circularity", circularity),
+        BrushShape::Rectangle { aspect } => field_numeric(ui, "Aspect", aspect),
+        BrushShape::Diamond => {}
+        BrushShape::Superellipse { exponent } => field_numeric(ui, "Exponent", exponent),
+        BrushShape::Blob { seed, roughness } => {
+            field_numeric(ui, "ShapeSeed", seed);
+            field_numeric(ui, "Roughness", roughness);
+        }
+        BrushShape::Stamp(stamp) => {
+            let mut config = stamp.config().clone();
+
+            ui.label("Stamp");
+            egui::ComboBox::from_id_source(format!("{}_stamp", id))
+                .selected_text(config.path.display().to_string())
+                .show_ui(ui, |ui| {
+                    for path in stamps {
+                        let name = path.display().to_string();
+                        ui.selectable_value(&mut config.path, path, name);
+                    }
+                });
+            ui.end_row();
+
+            let mut luminance = config.channel == StampChannel::Luminance;
+            ui.label("Luminance");
+            ui.checkbox(&mut luminance, "");
+            ui.end_row();
+            config.channel = match luminance {
+                true => StampChannel::Luminance,
+                false => StampChannel::Alpha,
+            };
+
+            field_numeric(ui, "Threshold", &mut config.threshold);
+            field_numeric(ui, "Rotation", &mut config.rotation);
+            ui.label("MirrorX");
+            ui.checkbox(&mut config.mirror_x, "");
+            ui.end_row();
+            ui.label("MirrorY");
+            ui.checkbox(&mut config.mirror_y, "");
+            ui.end_row();
+
+            // stamps that fail to load, e.g. with nothing above the threshold, are kept as they were
+            if &config != stamp.config() {
+                if let Ok(loaded) = BrushStamp::load(config) {
+                    *stamp = loaded;
+                }
+            }
+        }
+    }
+}
+
+fn field_brush_orientation(ui: &mut Ui, id: &str, orientation: &mut BrushOrientation) {
+    let orientations = [
+        BrushOrientation::Fixed,
+        BrushOrientation::Mirror,
+        BrushOrientation::Rotate { smoothing: 0.5 },
+    ];
+
+    ui.label("Orientation");
+    egui::ComboBox::from_id_source(format!("{}_brush_orientation", id))
+        .selected_text(orientation.name())
+        .show_ui(ui, |ui| {
+            for option in orientations {
+                let name = option.name();
+                let selected = orientation.name() == name;
+
+                if ui.selectable_label(selected, name).clicked() && !selected {
+                    *orientation = option;
+                }
+            }
+        });
+    ui.end_row();
+
+    if let BrushOrientation::Rotate { smoothing } = orientation {
+        field_numeric(ui, "Smoothing", smoothing);
+    }
+}
+
+/// rings are listed from the inside out
+fn field_brush_rims(ui: &mut Ui, id: &str, rims: &mut Vec<BrushRim>) {
+    let blocks = [
+        BlockType::Freeze,
+        BlockType::DeepFreeze,
+        BlockType::Unhookable,
+        BlockType::Hookable,
+    ];
+    let mut removed = None;
+
+    for (index, rim) in rims.iter_mut().enumerate() {
+        ui.label(format!("Rim {}", index));
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(format!("{}_rim_{}", id, index))
+                .selected_text(format!("{:?}", rim.block))
+                .show_ui(ui, |ui| {
+                    for block in blocks {
+                        ui.selectable_value(&mut rim.block, block, format!("{:?}", block));
+                    }
+                });
+
+            if ui.button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+        ui.end_row();
+
+        field_numeric(ui, "Width", &mut rim.width);
+    }
+
+    if let Some(index) = removed {
+        rims.remove(index);
+    }
+
+    ui.label("Rims");
+    if ui.button("Add").clicked() {
+        rims.push(BrushRim {
+            block: BlockType::Freeze,
+            width: 1,
+        });
+    }
+    ui.end_row();
+}
+
+/// png files in `data/stamps`
+fn stamp_paths() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(STAMPS_DIR) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+        .collect();
+
+    paths.sort();
+    paths
+}
+
+/// map passes either run once at the end or on every step for a while
+fn field_pass(ui: &mut Ui, at_end: &mut bool, overall_steps: &mut usize) {
+    ui.label("AtEnd");
+    ui.checkbox(at_end, "");
+    ui.end_row();
+
+    if !*at_end {
+        field_numeric(ui, "OverallSteps", overall_steps);
+    }
+}
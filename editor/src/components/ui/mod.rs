@@ -2,6 +2,7 @@ pub mod bottom_panel;
 pub mod context;
 pub mod float;
 pub mod left_panel;
+pub mod waypoints;
 
 use std::{cell::RefCell, rc::Rc};
 
@@ -0,0 +1,63 @@
+use std::{cell::RefCell, rc::Rc};
+
+use egui::{Color32, LayerId, Pos2, Stroke};
+
+use crate::components::utils::generation::GenerationContext;
+
+use super::context::RenderableUi;
+
+const PATH_COLOR: Color32 = Color32::from_rgb(0xf0, 0xc0, 0x40);
+const DRAGGED_COLOR: Color32 = Color32::from_rgb(0xff, 0x60, 0x40);
+const MARKER_RADIUS: f32 = 6.0;
+
+/// draws the session waypoints over the map view
+pub struct WaypointOverlayUi {
+    generation: Rc<RefCell<GenerationContext>>,
+}
+
+impl WaypointOverlayUi {
+    pub fn new(generation: Rc<RefCell<GenerationContext>>) -> Self {
+        Self { generation }
+    }
+}
+
+impl RenderableUi for WaypointOverlayUi {
+    fn ui_with(&mut self, ctx: &egui::Context) {
+        let generation = self.generation.borrow();
+        let editor = generation.waypoint_editor();
+
+        let markers: Vec<Pos2> = editor
+            .markers()
+            .into_iter()
+            .map(|marker| egui::pos2(marker.x, marker.y))
+            .collect();
+
+        let painter = ctx.layer_painter(LayerId::background());
+
+        painter.add(egui::Shape::line(
+            markers.clone(),
+            Stroke::new(2.0, PATH_COLOR),
+        ));
+
+        for (index, &marker) in markers.iter().enumerate() {
+            let color = match editor.dragged() == Some(index) {
+                true => DRAGGED_COLOR,
+                false => PATH_COLOR,
+            };
+
+            painter.circle(
+                marker,
+                MARKER_RADIUS,
+                color,
+                Stroke::new(1.0, Color32::BLACK),
+            );
+            painter.text(
+                marker + egui::vec2(MARKER_RADIUS, -MARKER_RADIUS),
+                egui::Align2::LEFT_BOTTOM,
+                index,
+                egui::FontId::monospace(12.0),
+                Color32::WHITE,
+            );
+        }
+    }
+}
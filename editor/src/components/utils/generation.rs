@@ -1,42 +1,42 @@
 use std::{
     collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
 };
 
-use egui_snarl::{InPinId, NodeId, Snarl};
+use egui_snarl::{InPinId, NodeId, OutPinId, Snarl};
 use mapgen_core::{
+    analysis::MapReport,
+    automapper::{Automapper, AutomapperConfig},
+    block::DesignLayer,
     brush::Brush,
-    generator::Generator,
+    generator::{GenerationError, Generator},
     map::Map,
-    mutations::{walker::straight::StraightWalkerMutation, MutationState, Mutator},
+    pipeline::{MutationLoop, Pipeline, DEFAULT_WAYPOINTS},
+    random::sub_seed,
+    trace::GenerationTrace,
     walker::Walker,
 };
-use twmap::{GameLayer, Group, Image, Tile, TileFlags, TilesLayer, TwMap};
+use twmap::{GameLayer, Group, Tile, TileFlags, TilesLayer, TwMap};
 
 use crate::components::{
     map::load_image,
-    ui::bottom_panel::{ExtractMutation, Titled, UiMutation, UiNode},
+    ui::bottom_panel::{ExtractMutation, UiMutation, UiNode},
 };
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub enum DesignLayer {
-    Unhookable,
-    Hookable,
-    Freeze,
-}
+use super::waypoints::WaypointEditor;
 
-impl DesignLayer {
-    pub fn is_same(&self, id: u8) -> bool {
-        match *self {
-            DesignLayer::Unhookable => id == 3,
-            DesignLayer::Hookable => id == 1,
-            DesignLayer::Freeze => id == 0,
-        }
-    }
-}
+/// directory of the `.rules` files, named after the images they belong to
+const RULES_DIR: &str = "data/rules";
 
 pub struct DesignImageInfo {
     path: PathBuf,
+    /// config of the image's rules file the layer is tiled with
     automapper_rule: usize,
 }
 
@@ -47,6 +47,24 @@ impl DesignImageInfo {
             automapper_rule,
         }
     }
+
+    /// `None` if the image comes without rules
+    fn automapper_config(&self) -> Result<Option<AutomapperConfig>, String> {
+        let Some(stem) = self.path.file_stem() else {
+            return Ok(None);
+        };
+
+        let rules = Path::new(RULES_DIR).join(stem).with_extension("rules");
+
+        if !rules.exists() {
+            return Ok(None);
+        }
+
+        let automapper = Automapper::load(&rules)
+            .map_err(|err| format!("failed to load '{}': {}", rules.display(), err))?;
+
+        Ok(automapper.config(self.automapper_rule).cloned())
+    }
 }
 
 pub struct DesignInfo {
@@ -59,274 +77,575 @@ impl DesignInfo {
     }
 }
 
-struct Loop<T> {
-    count: Option<usize>,
-    mutations: Vec<T>,
+/// horizontal distance between nodes of a restored pipeline graph
+const NODE_SPACING: f32 = 200.0;
+/// vertical distance between the brush, map and walker chains
+const CHAIN_SPACING: f32 = 250.0;
+
+type ChainLoops<M> = Vec<MutationLoop<<UiMutation as ExtractMutation<M>>::ExtractType>>;
+
+/// collects the loops connected to one of the generator pins in chain order,
+/// `None` if the chain is malformed
+fn loops_from_snarl<M>(snarl: &Snarl<UiNode>, generator_node: NodeId) -> Option<ChainLoops<M>>
+where
+    UiMutation: ExtractMutation<M>,
+{
+    let pin_in = snarl.in_pin(InPinId {
+        node: generator_node,
+        input: <UiMutation as ExtractMutation<M>>::INPUT,
+    });
+
+    let mut loops = Vec::new();
+
+    let Some(&(mut end)) = pin_in.remotes.first() else {
+        return Some(loops);
+    };
+
+    let mut cur_loop = None;
+
+    // chain is walked backwards, from the generator to the first loop
+    loop {
+        let next_node = &snarl[end.node];
+
+        let pin = snarl.in_pin(InPinId {
+            node: end.node,
+            input: 0,
+        });
+
+        let unwrapped = pin.remotes.first();
+        if let Some(&out_end) = unwrapped {
+            end = out_end;
+        }
+
+        match next_node {
+            UiNode::LoopStartNode(count) => {
+                let mut lp: MutationLoop<_> = cur_loop.take()?;
+
+                lp.count = *count;
+                lp.mutations.reverse();
+                lp.ids.reverse();
+
+                loops.push(lp);
+            }
+            UiNode::LoopEndNode => {
+                cur_loop = Some(MutationLoop::new(None, vec![]));
+            }
+            UiNode::MutationNode(mutation, id) => {
+                let lp = cur_loop.as_mut()?;
+
+                lp.mutations.push(mutation.extract()?);
+                lp.ids.push(id.clone());
+            }
+            UiNode::GeneratorNode(_) => return None,
+        };
+
+        if unwrapped.is_none() {
+            break;
+        }
+    }
+
+    loops.reverse();
+
+    Some(loops)
+}
+
+/// smallest numeric id no mutation node of the graph uses yet
+pub fn unused_mutation_id(snarl: &Snarl<UiNode>) -> String {
+    let used: HashSet<&str> = snarl
+        .node_ids()
+        .filter_map(|(_, node)| match node {
+            UiNode::MutationNode(_, id) => id.as_deref(),
+            _ => None,
+        })
+        .collect();
+
+    (0usize..)
+        .map(|id| id.to_string())
+        .find(|id| !used.contains(id.as_str()))
+        .unwrap()
+}
+
+/// builds a pipeline out of the graph connected to the generator node
+pub fn pipeline_from_snarl(snarl: &Snarl<UiNode>, generator_node: NodeId) -> Option<Pipeline> {
+    let UiNode::GeneratorNode(settings) = &snarl[generator_node] else {
+        return None;
+    };
+
+    Some(Pipeline {
+        generator: settings.as_ref().clone(),
+        brush: loops_from_snarl::<Brush>(snarl, generator_node)?,
+        map: loops_from_snarl::<Map>(snarl, generator_node)?,
+        walker: loops_from_snarl::<Walker>(snarl, generator_node)?,
+        ..Default::default()
+    })
+}
+
+/// builds a graph of the pipeline, every loop becomes a LoopStart/LoopEnd pair
+pub fn snarl_from_pipeline(pipeline: &Pipeline) -> Snarl<UiNode> {
+    fn insert_chain<M>(
+        snarl: &mut Snarl<UiNode>,
+        generator_node: NodeId,
+        loops: &ChainLoops<M>,
+        wrap: impl Fn(<UiMutation as ExtractMutation<M>>::ExtractType) -> UiMutation,
+    ) where
+        UiMutation: ExtractMutation<M>,
+    {
+        let input = <UiMutation as ExtractMutation<M>>::INPUT;
+        let y = input as f32 * CHAIN_SPACING;
+
+        let mut nodes = Vec::new();
+
+        for lp in loops.iter() {
+            nodes.push(UiNode::LoopStartNode(lp.count));
+            nodes.extend(lp.mutations.iter().enumerate().map(|(idx, mutation)| {
+                UiNode::MutationNode(wrap(mutation.clone()), lp.id(idx).map(str::to_string))
+            }));
+            nodes.push(UiNode::LoopEndNode);
+        }
+
+        let count = nodes.len();
+        let mut prev: Option<NodeId> = None;
+
+        for (idx, node) in nodes.into_iter().enumerate() {
+            let x = -((count - idx) as f32) * NODE_SPACING;
+            let id = snarl.insert_node(egui::pos2(x, y), node);
+
+            if let Some(prev) = prev {
+                snarl.connect(
+                    OutPinId {
+                        node: prev,
+                        output: 0,
+                    },
+                    InPinId { node: id, input: 0 },
+                );
+            }
+
+            prev = Some(id);
+        }
+
+        if let Some(prev) = prev {
+            snarl.connect(
+                OutPinId {
+                    node: prev,
+                    output: 0,
+                },
+                InPinId {
+                    node: generator_node,
+                    input,
+                },
+            );
+        }
+    }
+
+    let mut snarl = Snarl::new();
+
+    let generator_node = snarl.insert_node(
+        egui::pos2(0.0, 0.0),
+        UiNode::GeneratorNode(Box::new(pipeline.generator.clone())),
+    );
+
+    insert_chain::<Brush>(
+        &mut snarl,
+        generator_node,
+        &pipeline.brush,
+        UiMutation::Brush,
+    );
+    insert_chain::<Map>(&mut snarl, generator_node, &pipeline.map, UiMutation::Map);
+    insert_chain::<Walker>(
+        &mut snarl,
+        generator_node,
+        &pipeline.walker,
+        UiMutation::Walker,
+    );
+
+    snarl
+}
+
+/// steps walked between two progress updates, cancelling is checked every step
+const STEPS_PER_UPDATE: usize = 200;
+
+/// how far the running generation got
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GenerationProgress {
+    /// waypoints the main walker reached
+    pub reached: usize,
+    pub waypoints: usize,
+    /// steps of the main walker
+    pub steps: usize,
+    pub max_steps: usize,
+    /// whether the main walker arrived, only branches walk on then
+    pub arrived: bool,
+}
+
+impl GenerationProgress {
+    /// whether it's worth sending after `previous`: the main walker took
+    /// another `STEPS_PER_UPDATE` steps or it just arrived
+    fn updates(&self, previous: &GenerationProgress) -> bool {
+        self.arrived != previous.arrived
+            || (self.steps != previous.steps && self.steps.is_multiple_of(STEPS_PER_UPDATE))
+    }
+}
+
+enum WorkerMessage {
+    Progress(GenerationProgress),
+    /// went wrong without stopping the generation
+    Warning(String),
+    Finished(MapReport, GenerationTrace),
+    Failed(GenerationError),
+    Cancelled,
+}
+
+/// generation running on a worker thread
+struct GenerationJob {
+    progress: GenerationProgress,
+    cancel: Arc<AtomicBool>,
+    messages: Receiver<WorkerMessage>,
+    worker: JoinHandle<()>,
 }
 
 pub struct GenerationContext {
-    generator: Generator,
-    current_map: Option<TwMap>,
+    waypoint_editor: WaypointEditor,
+    /// finished maps go straight to the map loader
+    maps: Sender<TwMap>,
+    job: Option<GenerationJob>,
+    /// outcome of the last generation
+    status: String,
+    /// problems of the last generation that didn't stop it
+    warnings: Vec<String>,
+    current_report: Option<MapReport>,
+    current_trace: Option<GenerationTrace>,
 }
 
 impl GenerationContext {
-    pub fn new() -> Self {
+    pub fn new(maps: Sender<TwMap>) -> Self {
+        let generator = Generator::new();
+        let waypoint_editor = WaypointEditor::new(
+            DEFAULT_WAYPOINTS.to_vec(),
+            generator.get_scale_factor(),
+            generator.margin(),
+        );
+
         Self {
-            generator: Generator::new(),
-            current_map: None,
+            waypoint_editor,
+            maps,
+            job: None,
+            status: String::new(),
+            warnings: Vec::new(),
+            current_report: None,
+            current_trace: None,
         }
     }
 
-    fn load_mutations_from_snarl(
-        &mut self,
-        generator_node: NodeId,
-        snarl: &mut Snarl<UiNode>,
-    ) -> Option<(
-        Vec<Loop<Box<dyn Mutator<Brush>>>>,
-        Vec<Loop<Box<dyn Mutator<Map>>>>,
-        Vec<Loop<Box<dyn Mutator<Walker>>>>,
-    )> {
-        match snarl[generator_node] {
-            UiNode::GeneratorNode => {
-                fn get_mutations<M>(
-                    generator_node: NodeId,
-                    snarl: &mut Snarl<UiNode>,
-                ) -> Vec<Loop<<<UiMutation as ExtractMutation<M>>::ExtractType as ExtractMutation<M>>::ExtractType>>
-                where
-                    UiMutation: ExtractMutation<M>,
-                {
-                    let pin_in_brush = snarl.in_pin(InPinId {
-                        node: generator_node,
-                        input: <UiMutation as ExtractMutation<M>>::INPUT,
-                    });
-
-                    let mut loops = Vec::new();
-
-                    let mut end = if let Some(&end) = pin_in_brush.remotes.first() {
-                        end
-                    } else {
-                        return vec![];
-                    };
-
-                    let mut cur_loop = None;
-
-                    loop {
-                        let next_node = &snarl[end.node];
-
-                        let pin_id = InPinId {
-                            node: end.node,
-                            input: 0,
-                        };
-                        let pin = snarl.in_pin(pin_id);
-
-                        let unwrapped = pin.remotes.first();
-                        match unwrapped {
-                            Some(&out_end) => end = out_end,
-                            _ => {}
-                        }
-
-                        match next_node {
-                            UiNode::LoopStartNode(count) => {
-                                let mut lp: Loop<<<UiMutation as ExtractMutation<M>>::ExtractType as ExtractMutation<M>>::ExtractType> = cur_loop.take().unwrap();
-
-                                lp.count = *count;
-                                lp.mutations.reverse();
-
-                                loops.push(lp);
-                            }
-                            UiNode::LoopEndNode => {
-                                cur_loop = Some(Loop {
-                                    count: None,
-                                    mutations: vec![],
-                                });
-                            }
-                            UiNode::MutationNode(mutation) => {
-                                let m = mutation.extract().unwrap();
-                                println!("{}", m.title());
-
-                                cur_loop
-                                    .as_mut()
-                                    .unwrap()
-                                    .mutations
-                                    .push(m.extract().unwrap());
-                            }
-                            _ => unreachable!(),
-                        };
-
-                        if unwrapped.is_none() {
-                            break;
-                        }
+    /// starts generating a map along the waypoints of the session on a worker thread,
+    /// unless a generation is running already
+    pub fn generate(&mut self, snarl: &Snarl<UiNode>, generator_node: NodeId, design: DesignInfo) {
+        if self.job.is_some() {
+            return;
+        }
+
+        let Some(pipeline) = pipeline_from_snarl(snarl, generator_node) else {
+            return;
+        };
+
+        let mut generator = Generator::new();
+        pipeline.setup(&mut generator);
+
+        self.waypoint_editor
+            .set_transform(pipeline.generator.scale_factor, generator.margin());
+
+        let waypoints = self.waypoint_editor.waypoints().to_vec();
+        let progress = GenerationProgress {
+            waypoints: waypoints.len(),
+            max_steps: pipeline.generator.max_steps,
+            ..Default::default()
+        };
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, messages) = mpsc::channel();
+        let maps = self.maps.clone();
+
+        let worker_cancel = cancel.clone();
+
+        let worker = thread::spawn(move || {
+            let message = match run_worker(
+                generator,
+                &pipeline,
+                waypoints,
+                &design,
+                &worker_cancel,
+                &sender,
+            ) {
+                Ok(Some((map, report, trace))) => {
+                    // the editor is gone once nobody receives the map
+                    if maps.send(map).is_err() {
+                        return;
                     }
 
-                    loops
+                    WorkerMessage::Finished(report, trace)
                 }
+                Ok(None) => WorkerMessage::Cancelled,
+                Err(err) => WorkerMessage::Failed(err),
+            };
 
-                let brush = get_mutations::<Brush>(generator_node, snarl);
-                let map = get_mutations::<Map>(generator_node, snarl);
-                let walker = get_mutations::<Walker>(generator_node, snarl);
-
-                Some((brush, map, walker))
-            }
-            _ => None,
-        }
-    }
+            let _ = sender.send(message);
+        });
 
-    pub fn set_scale_factor(&mut self, scale_factor: f32) {
-        self.generator.set_scale_factor(scale_factor);
+        self.status = "generating".to_string();
+        self.warnings.clear();
+        self.job = Some(GenerationJob {
+            progress,
+            cancel,
+            messages,
+            worker,
+        });
     }
 
-    pub fn get_scale_factor(&self) -> f32 {
-        self.generator.get_scale_factor()
+    /// stops the running generation before its next step
+    pub fn cancel(&self) {
+        if let Some(job) = &self.job {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
     }
 
-    pub fn generate(
-        &mut self,
-        snarl: &mut Snarl<UiNode>,
-        generator_node: NodeId,
-        design: &DesignInfo,
-        waypoints: Vec<(f32, f32)>,
-    ) {
-        let Some((mut brush_mutations, mut map_mutations, mut walker_mutations)) =
-            self.load_mutations_from_snarl(generator_node, snarl)
-        else {
+    /// takes the updates of the worker, call it every frame
+    pub fn poll(&mut self) {
+        let Some(job) = &mut self.job else {
             return;
         };
-        for lp in brush_mutations.iter_mut() {
-            for mutation in lp.mutations.iter_mut() {
-                mutation.reset();
+
+        let status = loop {
+            match job.messages.try_recv() {
+                Ok(WorkerMessage::Progress(progress)) => job.progress = progress,
+                Ok(WorkerMessage::Warning(warning)) => self.warnings.push(warning),
+                Ok(WorkerMessage::Finished(report, trace)) => {
+                    self.current_report = Some(report);
+                    self.current_trace = Some(trace);
+                    break "generated".to_string();
+                }
+                Ok(WorkerMessage::Failed(err)) => break format!("generation failed: {}", err),
+                Ok(WorkerMessage::Cancelled) => break "cancelled".to_string(),
+                Err(TryRecvError::Empty) => return,
+                // the worker is gone without a result
+                Err(TryRecvError::Disconnected) => break "generation stopped".to_string(),
             }
-        }
-        for lp in map_mutations.iter_mut() {
-            for mutation in lp.mutations.iter_mut() {
-                mutation.reset();
+        };
+
+        let job = self.job.take().unwrap();
+
+        self.status = match job.worker.join() {
+            Ok(()) => status,
+            Err(panic) => {
+                let reason = panic
+                    .downcast_ref::<&str>()
+                    .map(|reason| reason.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+
+                format!("generation panicked: {}", reason)
             }
+        };
+    }
+
+    /// progress of the running generation
+    pub fn progress(&self) -> Option<GenerationProgress> {
+        self.job.as_ref().map(|job| job.progress)
+    }
+
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn waypoint_editor(&self) -> &WaypointEditor {
+        &self.waypoint_editor
+    }
+
+    pub fn waypoint_editor_mut(&mut self) -> &mut WaypointEditor {
+        &mut self.waypoint_editor
+    }
+
+    /// metrics of the last generated map
+    pub fn report(&self) -> Option<&MapReport> {
+        self.current_report.as_ref()
+    }
+
+    /// walks of the last generated map
+    pub fn trace(&self) -> Option<&GenerationTrace> {
+        self.current_trace.as_ref()
+    }
+}
+
+type Generated = (TwMap, MapReport, GenerationTrace);
+
+/// walks the generation in batches, `None` if it got cancelled
+fn run_worker(
+    mut generator: Generator,
+    pipeline: &Pipeline,
+    waypoints: Vec<(f32, f32)>,
+    design: &DesignInfo,
+    cancel: &AtomicBool,
+    messages: &Sender<WorkerMessage>,
+) -> Result<Option<Generated>, GenerationError> {
+    let mut sent = GenerationProgress {
+        waypoints: waypoints.len(),
+        max_steps: pipeline.generator.max_steps,
+        ..Default::default()
+    };
+
+    generator.begin(waypoints, pipeline.generator.seed)?;
+
+    while generator.step()? {
+        if cancel.load(Ordering::Relaxed) {
+            generator.cancel();
+            return Ok(None);
         }
-        for lp in walker_mutations.iter_mut() {
-            for mutation in lp.mutations.iter_mut() {
-                mutation.reset();
-            }
+
+        let Some(session) = generator.session() else {
+            continue;
+        };
+
+        let progress = GenerationProgress {
+            reached: session.walker().preferred_state().waypoint,
+            steps: session.steps(),
+            arrived: session.has_arrived(),
+            ..sent
+        };
+
+        if progress.updates(&sent) {
+            let _ = messages.send(WorkerMessage::Progress(progress));
+            sent = progress;
         }
+    }
 
-        self.generator.on_step(move |walker, map, brush| {
-            fn mutate_all<T>(mutant: &mut T, loops: &mut Vec<Loop<Box<dyn Mutator<T>>>>) {
-                for lp in loops.iter_mut() {
-                    if let Some(count) = &mut lp.count {
-                        if *count == 0 {
-                            continue;
-                        } else {
-                            *count -= 1;
-                            for mutation in lp.mutations.iter_mut() {
-                                let state = mutation.mutate(mutant);
-
-                                println!("[state]\t\t[idk   ]\t{:?}", state);
-
-                                if state == MutationState::Processing {
-                                    break;
-                                }
-                            }
-                        }
-                    } else {
-                        let mut last_finished = false;
-                        let mut idx = 0;
-                        if lp.mutations.is_empty() {
-                            continue;
-                        }
-                        let last = lp.mutations.len() - 1;
-                        for mutation in lp.mutations.iter_mut() {
-                            let state = mutation.mutate(mutant);
-                            let processed = state == MutationState::Processing;
-
-                            if idx == last {
-                                last_finished = !processed;
-                            }
-
-                            println!("[state]\t\t[idk   ]\t{:?}", state);
-
-                            if processed {
-                                break;
-                            }
-
-                            idx += 1;
-                        }
-
-                        if last_finished {
-                            for mutation in lp.mutations.iter_mut() {
-                                    mutation.reset();
-                            }
-                            lp.mutations.first_mut().unwrap().mutate(mutant);
-                        }
-                    }
-                }
+    let (mut map, report, trace) = generator.finish()?;
+
+    for warning in apply_design(&mut map, pipeline, design) {
+        let _ = messages.send(WorkerMessage::Warning(warning));
+    }
+
+    Ok(Some((map, report, trace)))
+}
+
+/// adds a group of design layers, tiled by the rules of their images, returns
+/// the rules that couldn't be loaded
+fn apply_design(map: &mut TwMap, pipeline: &Pipeline, design: &DesignInfo) -> Vec<String> {
+    // design
+    // weird way to do it but whatever
+    // im done
+
+    let image_ids: HashMap<DesignLayer, u16, std::hash::RandomState> = design
+        .image_infos
+        .iter()
+        .map(|(&layer, info)| {
+            let image = load_image(info.path.as_path());
+
+            let pos = map.images.iter().position(|i| image.eq(i));
+            if let Some(idx) = pos {
+                (layer, idx as u16)
+            } else {
+                let idx = map.images.len();
+
+                map.images.push(image);
+
+                (layer, idx as u16)
             }
+        })
+        .collect();
 
-            mutate_all(brush, &mut brush_mutations);
-            mutate_all(map, &mut map_mutations);
-            mutate_all(walker, &mut walker_mutations);
-        });
+    let shape = map.physics_group().layers[0].shape().unwrap();
+    let blocks = &pipeline.generator.blocks;
 
-        let mut map = self.generator.generate(waypoints);
+    let mut warnings = Vec::new();
+    let mut design_group = Group {
+        name: "Design".to_owned(),
+        ..Default::default()
+    };
 
-        // design
-        // weird way to do it but whatever
-        // im done
+    for (&layer_kind, &id) in image_ids.iter() {
+        let mut layer = TilesLayer::new((shape.w, shape.h));
 
-        let image_ids: HashMap<DesignLayer, u16, std::hash::RandomState> = design
-            .image_infos
-            .iter()
-            .map(|(&layer, info)| {
-                let image = load_image(info.path.as_path());
+        layer.name = layer_kind.name().to_owned();
 
-                let pos = map.images.iter().position(|i| image.eq(i));
-                if let Some(idx) = pos {
-                    (layer, idx as u16)
-                } else {
-                    let idx = map.images.len();
+        let tiles = layer.tiles.unwrap_mut();
 
-                    map.images.push(image);
+        *tiles = map
+            .find_physics_layer::<GameLayer>()
+            .as_ref()
+            .unwrap()
+            .tiles
+            .unwrap_ref()
+            .map(|elem| {
+                let in_layer = blocks.design_layer(elem.id) == Some(layer_kind);
 
-                    (layer, idx as u16)
-                }
-            })
-            .collect();
+                Tile::new(in_layer as u8, TileFlags::empty())
+            });
 
-        let shape = map.physics_group().layers[0].shape().unwrap();
+        match design.image_infos[&layer_kind].automapper_config() {
+            Ok(Some(config)) => config.apply(tiles, sub_seed(pipeline.generator.seed, &layer.name)),
+            Ok(None) => {}
+            Err(err) => warnings.push(err),
+        }
 
-        let mut design_group = Group::default();
+        layer.image = Some(id);
 
-        design_group.name = "Design".to_owned();
+        design_group.layers.push(twmap::Layer::Tiles(layer));
+    }
 
-        for (&design, &id) in image_ids.iter() {
-            let mut layer = TilesLayer::new((shape.w, shape.h));
+    map.groups.push(design_group);
 
-            layer.name = match design {
-                DesignLayer::Unhookable => "Unhookable".to_owned(),
-                DesignLayer::Hookable => "Hookable".to_owned(),
-                DesignLayer::Freeze => "Freeze".to_owned(),
-            };
+    warnings
+}
 
-            let tiles = layer.tiles.unwrap_mut();
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{atomic::AtomicBool, mpsc, Arc},
+        thread,
+    };
 
-            *tiles = map
-                .find_physics_layer::<GameLayer>()
-                .as_ref()
-                .unwrap()
-                .tiles
-                .unwrap_ref()
-                .map(|elem| Tile::new(design.is_same(elem.id) as u8, TileFlags::empty()));
+    use super::{GenerationContext, GenerationJob, GenerationProgress};
 
-            layer.image = Some(id);
+    #[test]
+    fn panicking_worker_ends_the_job() {
+        let (maps, _) = mpsc::channel();
+        let mut context = GenerationContext::new(maps);
 
-            design_group.layers.push(twmap::Layer::Tiles(layer));
-        }
+        let (sender, messages) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            let _sender = sender;
+            panic!("worker broke");
+        });
 
-        map.groups.push(design_group);
+        context.job = Some(GenerationJob {
+            progress: GenerationProgress::default(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            messages,
+            worker,
+        });
 
-        self.current_map = Some(map);
+        while context.progress().is_some() {
+            context.poll();
+        }
 
-        println!("generated");
+        assert_eq!(context.status(), "generation panicked: worker broke");
     }
 
-    pub fn take_map(&mut self) -> Option<TwMap> {
-        self.current_map.take()
+    #[test]
+    fn progress_updates_only_on_changes() {
+        let progress = |steps, arrived| GenerationProgress {
+            steps,
+            arrived,
+            ..Default::default()
+        };
+
+        assert!(progress(200, false).updates(&progress(199, false)));
+        assert!(!progress(201, false).updates(&progress(200, false)));
+
+        // branches walking on after the main walker arrived don't count
+        assert!(progress(400, true).updates(&progress(400, false)));
+        assert!(!progress(400, true).updates(&progress(400, true)));
     }
 }
@@ -1 +1,2 @@
 pub mod generation;
+pub mod waypoints;
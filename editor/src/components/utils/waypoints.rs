@@ -0,0 +1,209 @@
+use twgpu::Camera;
+use vek::Vec2;
+
+/// waypoints of the editor session, placed on the map view in tile coordinates
+///
+/// The generator carves tiles at `[x, y]`, but the map view shows the first
+/// axis of the tile arrays vertically, so a waypoint `(x, y)` sits at `(y, x)`
+/// on the view.
+pub struct WaypointEditor {
+    waypoints: Vec<(f32, f32)>,
+    /// tiles per normalized unit
+    scale_factor: f32,
+    /// tiles between the map border and the normalized origin
+    margin: f32,
+    /// waypoint following the cursor
+    dragged: Option<usize>,
+    /// camera and render size of the last frame, the markers are drawn with them
+    view: Option<(Camera, Vec2<f32>)>,
+}
+
+impl WaypointEditor {
+    pub fn new(waypoints: Vec<(f32, f32)>, scale_factor: f32, margin: usize) -> Self {
+        Self {
+            waypoints,
+            scale_factor,
+            margin: margin as f32,
+            dragged: None,
+            view: None,
+        }
+    }
+
+    pub fn waypoints(&self) -> &[(f32, f32)] {
+        &self.waypoints
+    }
+
+    pub fn set_waypoints(&mut self, waypoints: Vec<(f32, f32)>) {
+        self.waypoints = waypoints;
+        self.dragged = None;
+    }
+
+    /// placement of the normalized waypoints on the generated map
+    pub fn set_transform(&mut self, scale_factor: f32, margin: usize) {
+        self.scale_factor = scale_factor;
+        self.margin = margin as f32;
+    }
+
+    pub fn set_view(&mut self, camera: Camera, render_size: Vec2<f32>) {
+        self.view = Some((camera, render_size));
+    }
+
+    pub fn dragged(&self) -> Option<usize> {
+        self.dragged
+    }
+
+    pub fn to_map(&self, waypoint: (f32, f32)) -> Vec2<f32> {
+        Vec2::new(waypoint.1, waypoint.0) * self.scale_factor + self.margin
+    }
+
+    /// normalized waypoint at the map position, kept inside the unit square
+    pub fn to_normal(&self, position: Vec2<f32>) -> (f32, f32) {
+        let normal = (position - self.margin) / self.scale_factor.max(f32::EPSILON);
+
+        (normal.y.clamp(0.0, 1.0), normal.x.clamp(0.0, 1.0))
+    }
+
+    /// closest waypoint within `radius` tiles
+    fn nearest(&self, position: Vec2<f32>, radius: f32) -> Option<usize> {
+        self.waypoints
+            .iter()
+            .map(|&waypoint| self.to_map(waypoint).distance(position))
+            .enumerate()
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs))
+            .map(|(index, _)| index)
+    }
+
+    /// closest segment within `radius` tiles, by the index of its first waypoint
+    fn nearest_segment(&self, position: Vec2<f32>, radius: f32) -> Option<usize> {
+        self.waypoints
+            .windows(2)
+            .map(|segment| {
+                let (from, to) = (self.to_map(segment[0]), self.to_map(segment[1]));
+                let length = from.distance_squared(to);
+                let t = match length > 0.0 {
+                    true => ((position - from).dot(to - from) / length).clamp(0.0, 1.0),
+                    false => 0.0,
+                };
+
+                Vec2::lerp(from, to, t).distance(position)
+            })
+            .enumerate()
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs))
+            .map(|(index, _)| index)
+    }
+
+    /// starts dragging the waypoint under the cursor, `false` if there is none
+    pub fn grab(&mut self, position: Vec2<f32>, radius: f32) -> bool {
+        self.dragged = self.nearest(position, radius);
+        self.dragged.is_some()
+    }
+
+    /// adds a waypoint into the segment under the cursor or after the last one and drags it
+    pub fn insert(&mut self, position: Vec2<f32>, radius: f32) {
+        let index = match self.nearest_segment(position, radius) {
+            Some(segment) => segment + 1,
+            None => self.waypoints.len(),
+        };
+
+        self.waypoints.insert(index, self.to_normal(position));
+        self.dragged = Some(index);
+    }
+
+    /// moves the dragged waypoint, `false` if none is dragged
+    pub fn drag(&mut self, position: Vec2<f32>) -> bool {
+        let Some(index) = self.dragged else {
+            return false;
+        };
+
+        self.waypoints[index] = self.to_normal(position);
+
+        true
+    }
+
+    pub fn release(&mut self) {
+        self.dragged = None;
+    }
+
+    /// deletes the waypoint under the cursor, the two a generation needs are kept
+    pub fn remove(&mut self, position: Vec2<f32>, radius: f32) -> bool {
+        if self.waypoints.len() <= 2 {
+            return false;
+        }
+
+        let Some(index) = self.nearest(position, radius) else {
+            return false;
+        };
+
+        self.waypoints.remove(index);
+        self.dragged = None;
+
+        true
+    }
+
+    /// screen positions of the waypoints in the last frame
+    pub fn markers(&self) -> Vec<Vec2<f32>> {
+        let Some((camera, render_size)) = self.view else {
+            return Vec::new();
+        };
+
+        let view_size = camera.base_dimensions * camera.zoom;
+
+        self.waypoints
+            .iter()
+            .map(|&waypoint| {
+                let logical = (self.to_map(waypoint) - camera.position) / view_size + 0.5;
+
+                logical * render_size
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use mapgen_core::{
+        brush::Brush, generator::Generator, map::Map, mutations::StepInfo, walker::Walker,
+    };
+    use vek::Vec2;
+
+    use super::WaypointEditor;
+
+    #[test]
+    fn markers_sit_on_the_carved_path() {
+        let waypoints = vec![(0.1, 0.8), (0.9, 0.3)];
+        let last = *waypoints.last().unwrap();
+
+        let position = Arc::new(Mutex::new([0, 0]));
+        let recorded = position.clone();
+
+        let mut generator = Generator::new();
+        generator.set_scale_factor(50.0);
+        generator.on_step(
+            move |walker: &mut Walker, _map: &mut Map, _brush: &mut Brush, info: StepInfo| {
+                let preferred = *walker.preferred_state();
+                walker
+                    .set_next_direction(preferred.direction)
+                    .set_next_waypoint(preferred.waypoint);
+
+                *recorded.lock().unwrap() = info.position;
+            },
+        );
+
+        let editor = WaypointEditor::new(waypoints.clone(), 50.0, generator.margin());
+
+        generator.generate(waypoints, 0).unwrap();
+
+        // the walker stops next to the last waypoint, tile `[x, y]` is drawn at `(y, x)`
+        let [x, y] = *position.lock().unwrap();
+        let carved = Vec2::new(y as f32, x as f32);
+
+        assert!(editor.to_map(last).distance(carved) < 3.0);
+
+        let (nx, ny) = editor.to_normal(carved);
+        assert!((nx - last.0).abs() < 0.06 && (ny - last.1).abs() < 0.06);
+    }
+}
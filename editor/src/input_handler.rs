@@ -51,6 +51,15 @@ fn phys_pos<T>(phys: PhysicalPosition<T>) -> Vec2<T> {
     Vec2::new(phys.x, phys.y)
 }
 
+/// Map-coordinates of a physical position in the render area
+pub fn map_position(
+    camera: &Camera,
+    render_size: Vec2<f32>,
+    position: PhysicalPosition<f64>,
+) -> Vec2<f32> {
+    camera.map_position(phys_pos(position).az() / render_size)
+}
+
 impl Input {
     pub fn from_touch(touch: Touch) -> Self {
         Self {
@@ -226,6 +235,10 @@ impl Cursors {
         self.0.iter().find(|c| c.position.is_some())?.position
     }
 
+    pub fn position(&self, id: DeviceId) -> Option<PhysicalPosition<f64>> {
+        self.0[self.cursor_index(id)?].position
+    }
+
     pub fn entered(&mut self, id: DeviceId) {
         self.0.push(Cursor {
             id,
@@ -0,0 +1,239 @@
+//! Export of generated maps.
+//!
+//! The [`Exporter`] merges the game layer of a generated map into a base map
+//! that carries the visuals, e.g. one of `data/maps`. The base map needs a
+//! design group with a freeze and a hookable tile layer, they are filled from
+//! the game layer and tiled with the automapper rules of their images.
+
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use mapgen_core::{
+    automapper::{Automapper, AutomapperError},
+    block::{BlockMapping, DesignLayer},
+    random::{sub_seed, Seed},
+};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use twmap::{AnyTile, GameLayer, Layer, Tile, TileFlags, TilesLayer, TwMap};
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Automapper(PathBuf, AutomapperError),
+    /// group with the given name is missing in the base map
+    MissingGroup(String),
+    /// tile layer with the given name is missing in the design group
+    MissingLayer(String),
+    MissingGameLayer,
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Io(err) => write!(f, "{}", err),
+            ExportError::Json(err) => write!(f, "invalid exporter config: {}", err),
+            ExportError::Automapper(path, err) => write!(f, "{}: {}", path.display(), err),
+            ExportError::MissingGroup(name) => write!(f, "base map has no group '{}'", name),
+            ExportError::MissingLayer(name) => {
+                write!(f, "design group has no tile layer '{}'", name)
+            }
+            ExportError::MissingGameLayer => write!(f, "map has no game layer"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExporterConfig {
+    /// directory of the `.rules` files, named after the images they belong to
+    pub automapper_rules_path: PathBuf,
+    /// group of the base map holding the design layers
+    pub design_group_name: String,
+    pub freeze_layer_name: String,
+    pub hookable_layer_name: String,
+}
+
+impl Default for ExporterConfig {
+    fn default() -> Self {
+        Self {
+            automapper_rules_path: PathBuf::from("../data/rules"),
+            design_group_name: "Tiles".to_string(),
+            freeze_layer_name: "Freeze".to_string(),
+            hookable_layer_name: "Hookable".to_string(),
+        }
+    }
+}
+
+impl ExporterConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ExportError> {
+        let data = fs::read_to_string(path).map_err(ExportError::Io)?;
+
+        serde_json::from_str(&data).map_err(ExportError::Json)
+    }
+}
+
+pub struct Exporter {
+    config: ExporterConfig,
+    blocks: BlockMapping,
+}
+
+impl Exporter {
+    pub fn new(config: ExporterConfig) -> Self {
+        Self {
+            config,
+            blocks: BlockMapping::default(),
+        }
+    }
+
+    /// has to match the mapping the map was generated with
+    pub fn set_blocks(&mut self, blocks: BlockMapping) {
+        self.blocks = blocks;
+    }
+
+    pub fn config(&self) -> &ExporterConfig {
+        &self.config
+    }
+
+    /// fails if `base` lacks any of the layers the export needs
+    pub fn check_base(&self, base: &TwMap) -> Result<(), ExportError> {
+        base.find_physics_layer::<GameLayer>()
+            .ok_or(ExportError::MissingGameLayer)?;
+
+        for (name, _) in self.design_layers() {
+            self.find_design_layer(base, &name)?;
+        }
+
+        Ok(())
+    }
+
+    /// copy of the loaded `base` with the game layer of `generated`,
+    /// `seed` drives the automapper's `Random` rules
+    pub fn export(
+        &self,
+        base: &TwMap,
+        generated: &TwMap,
+        seed: Seed,
+    ) -> Result<TwMap, ExportError> {
+        self.check_base(base)?;
+
+        let game = generated
+            .find_physics_layer::<GameLayer>()
+            .ok_or(ExportError::MissingGameLayer)?
+            .tiles
+            .unwrap_ref();
+
+        let mut map = base.clone();
+
+        // physics layers share one size
+        for layer in map.physics_group_mut().layers.iter_mut() {
+            match layer {
+                Layer::Game(layer) => *layer.tiles.unwrap_mut() = game.clone(),
+                Layer::Front(layer) => reset(layer.tiles.unwrap_mut(), game.dim()),
+                Layer::Tele(layer) => reset(layer.tiles.unwrap_mut(), game.dim()),
+                Layer::Speedup(layer) => reset(layer.tiles.unwrap_mut(), game.dim()),
+                Layer::Switch(layer) => reset(layer.tiles.unwrap_mut(), game.dim()),
+                Layer::Tune(layer) => reset(layer.tiles.unwrap_mut(), game.dim()),
+                _ => {}
+            }
+        }
+
+        for (name, design_layer) in self.design_layers() {
+            let rules = self.find_design_layer(&map, &name)?.image.map(|image| {
+                self.config
+                    .automapper_rules_path
+                    .join(map.images[image as usize].name())
+                    .with_extension("rules")
+            });
+
+            let layer = self.find_design_layer_mut(&mut map, &name)?;
+
+            *layer.tiles.unwrap_mut() = game.map(|tile| {
+                let in_layer = self.blocks.design_layer(tile.id) == Some(design_layer);
+
+                Tile::new(in_layer as u8, TileFlags::empty())
+            });
+
+            let (Some(rules), Some(config)) = (rules, layer.automapper_config.config) else {
+                continue;
+            };
+
+            if !rules.exists() {
+                continue;
+            }
+
+            let automapper =
+                Automapper::load(&rules).map_err(|err| ExportError::Automapper(rules, err))?;
+
+            if let Some(config) = automapper.config(config as usize) {
+                config.apply(layer.tiles.unwrap_mut(), sub_seed(seed, &name));
+            }
+        }
+
+        Ok(map)
+    }
+
+    fn design_layers(&self) -> [(String, DesignLayer); 2] {
+        [
+            (self.config.freeze_layer_name.clone(), DesignLayer::Freeze),
+            (
+                self.config.hookable_layer_name.clone(),
+                DesignLayer::Hookable,
+            ),
+        ]
+    }
+
+    fn find_design_layer<'a>(
+        &self,
+        map: &'a TwMap,
+        name: &str,
+    ) -> Result<&'a TilesLayer, ExportError> {
+        let group_name = &self.config.design_group_name;
+        let group = map
+            .groups
+            .iter()
+            .find(|group| &group.name == group_name)
+            .ok_or_else(|| ExportError::MissingGroup(group_name.clone()))?;
+
+        group
+            .layers
+            .iter()
+            .find_map(|layer| match layer {
+                Layer::Tiles(layer) if layer.name == name => Some(layer),
+                _ => None,
+            })
+            .ok_or_else(|| ExportError::MissingLayer(name.to_string()))
+    }
+
+    fn find_design_layer_mut<'a>(
+        &self,
+        map: &'a mut TwMap,
+        name: &str,
+    ) -> Result<&'a mut TilesLayer, ExportError> {
+        let group_name = &self.config.design_group_name;
+        let group = map
+            .groups
+            .iter_mut()
+            .find(|group| &group.name == group_name)
+            .ok_or_else(|| ExportError::MissingGroup(group_name.clone()))?;
+
+        group
+            .layers
+            .iter_mut()
+            .find_map(|layer| match layer {
+                Layer::Tiles(layer) if layer.name == name => Some(layer),
+                _ => None,
+            })
+            .ok_or_else(|| ExportError::MissingLayer(name.to_string()))
+    }
+}
+
+fn reset<T: AnyTile>(tiles: &mut Array2<T>, dim: (usize, usize)) {
+    *tiles = Array2::default(dim);
+}
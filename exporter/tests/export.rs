@@ -0,0 +1,123 @@
+use mapgen_core::{
+    generator::Generator,
+    mutations::{
+        brush::transition::TransitionBrushMutation, walker::straight::StraightWalkerMutation,
+    },
+    pipeline::{BrushMutation, MutationLoop, Pipeline, WalkerMutation},
+};
+use mapgen_exporter::{ExportError, Exporter, ExporterConfig};
+use twmap::{GameLayer, Layer, TwMap};
+
+fn base_map(name: &str) -> TwMap {
+    let mut map = TwMap::parse_path(format!("../data/maps/{}.map", name)).unwrap();
+    map.load().unwrap();
+    map
+}
+
+fn generated_map() -> TwMap {
+    let mut pipeline = Pipeline {
+        brush: vec![MutationLoop::endless(vec![BrushMutation::Transition(
+            TransitionBrushMutation::new(7, 7, 1),
+        )])],
+        walker: vec![MutationLoop::endless(vec![WalkerMutation::Straight(
+            StraightWalkerMutation::new(1),
+        )])],
+        ..Default::default()
+    };
+
+    pipeline.generator.scale_factor = 40.0;
+    pipeline.generator.freeze = Some(Default::default());
+
+    let mut generator = Generator::new();
+    pipeline.setup(&mut generator);
+
+    generator
+        .generate(vec![(0.0, 1.0), (1.0, 0.0)], 0)
+        .unwrap()
+        .0
+}
+
+fn design_layer<'a>(map: &'a TwMap, name: &str) -> &'a twmap::TilesLayer {
+    map.groups
+        .iter()
+        .find(|group| group.name == "Tiles")
+        .unwrap()
+        .layers
+        .iter()
+        .find_map(|layer| match layer {
+            Layer::Tiles(layer) if layer.name == name => Some(layer),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn shipped_config_loads() {
+    let config = ExporterConfig::load("../data/configs/exporter/default.json").unwrap();
+
+    assert_eq!(config, ExporterConfig::default());
+}
+
+#[test]
+fn merges_into_base_map() {
+    let exporter = Exporter::new(ExporterConfig::default());
+    let generated = generated_map();
+    let map = exporter.export(&base_map("test"), &generated, 0).unwrap();
+
+    let game = map
+        .find_physics_layer::<GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref();
+    let generated_game = generated
+        .find_physics_layer::<GameLayer>()
+        .unwrap()
+        .tiles
+        .unwrap_ref();
+
+    assert_eq!(game, generated_game);
+
+    for name in ["Freeze", "Hookable"] {
+        let tiles = design_layer(&map, name).tiles.unwrap_ref();
+
+        assert_eq!(tiles.dim(), game.dim(), "{}", name);
+        assert!(tiles.iter().any(|tile| tile.id != 0), "{}", name);
+    }
+
+    // empty game tiles stay empty in the design layers
+    let hookable = design_layer(&map, "Hookable").tiles.unwrap_ref();
+
+    for (tile, design) in game.iter().zip(hookable.iter()) {
+        if tile.id == 0 {
+            assert_eq!(design.id, 0);
+        }
+    }
+
+    assert_eq!(
+        exporter.export(&base_map("test"), &generated, 0).unwrap(),
+        map
+    );
+}
+
+#[test]
+fn missing_layers() {
+    let exporter = Exporter::new(ExporterConfig {
+        design_group_name: "Design".to_string(),
+        ..Default::default()
+    });
+
+    assert!(matches!(
+        exporter.check_base(&base_map("test")),
+        Err(ExportError::MissingGroup(name)) if name == "Design"
+    ));
+
+    let exporter = Exporter::new(ExporterConfig {
+        hookable_layer_name: "Walls".to_string(),
+        ..Default::default()
+    });
+
+    assert!(matches!(
+        exporter.check_base(&base_map("test")),
+        Err(ExportError::MissingLayer(name)) if name == "Walls"
+    ));
+}